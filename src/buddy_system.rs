@@ -1,5 +1,96 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::logger::{log, LogLevel};
 use crate::pcb::PCB;
 
+/// 分配/回收事件类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AllocEventKind {
+    Alloc,  // 分配
+    Free,   // 回收
+}
+
+impl AllocEventKind {
+    pub fn name(&self) -> &str {
+        match self {
+            AllocEventKind::Alloc => "分配",
+            AllocEventKind::Free => "回收",
+        }
+    }
+}
+
+/// 分配策略：所需阶数没有空闲块时，从哪一阶的空闲块分裂
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum AllocStrategy {
+    #[default]
+    SmallestFirst, // 从足够大的最小阶分裂，尽量保留大的连续空闲区域
+    LargestFirst,  // 从最大阶分裂，尽量保留已有的小空闲块
+}
+
+impl AllocStrategy {
+    pub fn name(&self) -> &str {
+        match self {
+            AllocStrategy::SmallestFirst => "最小可用块优先",
+            AllocStrategy::LargestFirst => "最大可用块优先",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            AllocStrategy::SmallestFirst => AllocStrategy::LargestFirst,
+            AllocStrategy::LargestFirst => AllocStrategy::SmallestFirst,
+        }
+    }
+}
+
+/// 分配历史中的一条记录
+#[derive(Debug, Clone)]
+pub struct AllocEvent {
+    pub tick: u32,              // 事件发生时的系统时间
+    pub kind: AllocEventKind,   // 分配或回收
+    pub index: usize,           // 涉及的块索引
+    pub order: usize,           // 块的阶数（大小为2^order）
+}
+
+/// 碎片报告
+pub struct FragmentationReport {
+    pub total_free: usize,              // 空闲槽位总数
+    pub largest_free_block: usize,      // 最大连续空闲块的大小
+    pub fragmentation_percent: f64,     // 不在最大空闲块中的空闲槽位占比
+}
+
+impl std::fmt::Display for FragmentationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "空闲槽位: {}, 最大连续空闲块: {}, 碎片率: {:.2}%",
+            self.total_free, self.largest_free_block, self.fragmentation_percent
+        )
+    }
+}
+
+/// 分配器工作量统计
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AllocStats {
+    pub allocations: u64,   // 成功分配的次数
+    pub deallocations: u64, // 成功回收的次数
+    pub splits: u64,        // 分裂大块的次数
+    pub merges: u64,        // 伙伴块合并的次数
+}
+
+impl std::fmt::Display for AllocStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "分配: {}, 回收: {}, 分裂: {}, 合并: {}",
+            self.allocations, self.deallocations, self.splits, self.merges
+        )
+    }
+}
+
 /// 伙伴系统：用于管理PCB池
 /// 使用伙伴系统算法来分配和回收PCB块
 ///
@@ -8,12 +99,18 @@ use crate::pcb::PCB;
 /// 2. 维护多级空闲链表，每级对应不同大小的块
 /// 3. 分配时从合适大小的空闲链表中取块，必要时分裂大块
 /// 4. 回收时尝试与伙伴块合并，形成更大的空闲块
+#[derive(Clone)]
 pub struct BuddySystem {
     pool: Vec<Option<PCB>>,         // PCB池，实际存储PCB对象
     free_list: Vec<Vec<usize>>,     // 按大小分组的空闲块列表，free_list[k]存储大小为2^k的空闲块起始索引
     max_order: usize,               // 最大阶数（2^max_order = pool_size）
     pool_size: usize,               // 池的实际大小（2的幂）
-    used_count: usize,              // 已使用的PCB数量
+    used_count: usize,              // 已使用的槽位数量
+    allocated: HashMap<usize, usize>, // 已分配块：起始索引 -> 阶数
+    peak_used: usize,               // 已使用数量的历史峰值
+    history: Vec<AllocEvent>,       // 分配/回收历史（按发生顺序）
+    stats: AllocStats,              // 分配/回收/分裂/合并次数
+    strategy: AllocStrategy,        // 选择被分裂块的策略
 }
 
 impl BuddySystem {
@@ -33,37 +130,95 @@ impl BuddySystem {
         // 初始时，整个池是一个大的空闲块，放在最高阶
         free_list[max_order].push(0);
 
-        println!("伙伴系统初始化: 池大小={}, 最大阶数={}", pool_size, max_order);
+        log(LogLevel::Info, format_args!("伙伴系统初始化: 池大小={}, 最大阶数={}", pool_size, max_order));
 
+        Self::with_free_list(pool_size, max_order, free_list)
+    }
+
+    /// 用给定的空闲链表构造伙伴系统，池中尚未存储任何PCB
+    fn with_free_list(pool_size: usize, max_order: usize, free_list: Vec<Vec<usize>>) -> Self {
         BuddySystem {
             pool: vec![None; pool_size],
             free_list,
             max_order,
             pool_size,
             used_count: 0,
+            allocated: HashMap::new(),
+            peak_used: 0,
+            history: Vec::new(),
+            stats: AllocStats::default(),
+            strategy: AllocStrategy::default(),
         }
     }
 
-    /// 分配一个PCB块（扩展一：伙伴系统分配算法）
-    /// 返回分配的索引，如果分配失败返回None
-    pub fn allocate(&mut self) -> Option<usize> {
-        // 我们总是分配大小为1的块（0阶）
-        let order = 0;
-
-        // 查找最小的可用块
-        let mut alloc_order = order;
-        while alloc_order <= self.max_order {
-            if !self.free_list[alloc_order].is_empty() {
-                break;
+    /// 根据已占用块重建伙伴系统（用于加载保存的状态）
+    ///
+    /// occupied中每项为(起始索引, 阶数)。pool_size必须是2的幂。
+    /// 所有未被占用的槽位逐个经过合并算法放回空闲链表，
+    /// 因此得到的空闲链表与正常回收后的形态一致。
+    /// 存在越界、未对齐或重叠的块时返回None
+    pub fn from_occupied(pool_size: usize, occupied: &[(usize, usize)]) -> Option<Self> {
+        if !pool_size.is_power_of_two() {
+            return None;
+        }
+
+        let max_order = pool_size.trailing_zeros() as usize;
+        let mut buddy = Self::with_free_list(pool_size, max_order, vec![Vec::new(); max_order + 1]);
+
+        let mut is_used = vec![false; pool_size];
+        for &(index, order) in occupied {
+            let size = 1 << order;
+            if order > max_order || index % size != 0 || index + size > pool_size {
+                return None;
+            }
+            for slot in &mut is_used[index..index + size] {
+                if *slot {
+                    return None;
+                }
+                *slot = true;
             }
-            alloc_order += 1;
+            buddy.allocated.insert(index, order);
+            buddy.used_count += size;
         }
 
-        // 如果没有可用块
-        if alloc_order > self.max_order {
+        for (index, used) in is_used.iter().enumerate() {
+            if !used {
+                buddy.merge_and_free(index, 0);
+            }
+        }
+        buddy.peak_used = buddy.used_count;
+        // 重建过程中的合并不是真实的回收操作，不计入统计
+        buddy.stats = AllocStats::default();
+
+        Some(buddy)
+    }
+
+    /// 分配一个PCB块（扩展一：伙伴系统分配算法）
+    /// 返回分配的索引，如果分配失败返回None
+    ///
+    /// tick为调用方传入的系统时间，用于记录分配历史
+    #[allow(dead_code)]
+    pub fn allocate(&mut self, tick: u32) -> Option<usize> {
+        self.allocate_order(0, tick)
+    }
+
+    /// 分配一个大小为2^order的连续块
+    /// 返回块的起始索引，如果没有足够大的空闲块返回None
+    ///
+    /// 从哪一阶取块由分配策略决定：最小可用块优先时取不小于order的最小非空阶，
+    /// 最大可用块优先时取最大的非空阶
+    pub fn allocate_order(&mut self, order: usize, tick: u32) -> Option<usize> {
+        if order > self.max_order {
             return None;
         }
 
+        // 按分配策略查找可用块，没有可用块时返回None
+        let mut candidates = (order..=self.max_order).filter(|&k| !self.free_list[k].is_empty());
+        let mut alloc_order = match self.strategy {
+            AllocStrategy::SmallestFirst => candidates.next(),
+            AllocStrategy::LargestFirst => candidates.next_back(),
+        }?;
+
         // 从找到的阶数中取出一个块
         let index = self.free_list[alloc_order].pop().unwrap();
 
@@ -73,12 +228,30 @@ impl BuddySystem {
             let buddy_index = index + (1 << alloc_order);
             // 将分裂出的伙伴块加入对应阶的空闲列表
             self.free_list[alloc_order].push(buddy_index);
+            self.stats.splits += 1;
+            log(LogLevel::Debug, format_args!(
+                "伙伴系统: 分裂块 {} (阶数 {})，伙伴块 {} 放入阶数 {} 的空闲链表",
+                index, alloc_order + 1, buddy_index, alloc_order
+            ));
         }
 
-        self.used_count += 1;
+        self.allocated.insert(index, order);
+        self.used_count += 1 << order;
+        self.peak_used = self.peak_used.max(self.used_count);
+        self.history.push(AllocEvent { tick, kind: AllocEventKind::Alloc, index, order });
+        self.stats.allocations += 1;
         Some(index)
     }
 
+    pub fn strategy(&self) -> AllocStrategy {
+        self.strategy
+    }
+
+    /// 设置分配策略，只影响之后的分配
+    pub fn set_strategy(&mut self, strategy: AllocStrategy) {
+        self.strategy = strategy;
+    }
+
     /// 在指定索引存储PCB
     pub fn store_pcb(&mut self, index: usize, pcb: PCB) {
         if index < self.pool_size {
@@ -97,17 +270,29 @@ impl BuddySystem {
     }
 
     /// 回收PCB到池中（扩展三：PCB回收算法）
-    pub fn deallocate(&mut self, index: usize) {
-        if index >= self.pool_size {
-            return;
+    ///
+    /// 块的阶数在分配时记录，回收时按原阶数开始合并。
+    /// tick为调用方传入的系统时间，用于记录分配历史。
+    ///
+    /// 返回该索引是否确实处于已分配状态：越界、从未分配或重复回收时
+    /// 返回false，且不修改空闲链表和计数
+    pub fn deallocate(&mut self, index: usize, tick: u32) -> bool {
+        if index >= self.pool_size || self.pool[index].is_none() {
+            return false;
         }
+        let Some(order) = self.allocated.remove(&index) else {
+            return false;
+        };
 
         // 清除存储的PCB
         self.pool[index] = None;
-        self.used_count -= 1;
+        self.used_count = self.used_count.saturating_sub(1 << order);
+        self.history.push(AllocEvent { tick, kind: AllocEventKind::Free, index, order });
+        self.stats.deallocations += 1;
 
         // 尝试合并伙伴块（扩展三：空白块合并）
-        self.merge_and_free(index, 0);
+        self.merge_and_free(index, order);
+        true
     }
 
     /// 合并伙伴块并释放（扩展三：空白块合并算法）
@@ -137,8 +322,13 @@ impl BuddySystem {
                 self.free_list[order].remove(pos);
 
                 // 合并：取两个块中索引较小的作为合并后的块索引
+                log(LogLevel::Debug, format_args!(
+                    "伙伴系统: 块 {} 与伙伴块 {} 合并为阶数 {} 的块 {}",
+                    index, buddy_index, order + 1, index.min(buddy_index)
+                ));
                 index = index.min(buddy_index);
                 order += 1;
+                self.stats.merges += 1;
 
                 // 继续尝试向上合并
             } else {
@@ -160,20 +350,372 @@ impl BuddySystem {
         self.used_count
     }
 
+    pub fn get_peak_used_count(&self) -> usize {
+        self.peak_used
+    }
+
     pub fn get_pool_size(&self) -> usize {
         self.pool_size
     }
 
-    /// 打印伙伴系统状态（用于调试）
-    #[allow(dead_code)]
-    pub fn print_status(&self) {
-        println!("\n=== 伙伴系统状态 ===");
-        println!("池大小: {}, 已用: {}, 空闲: {}",
-                 self.pool_size, self.used_count, self.get_free_count());
+    /// 获取池中存有PCB的所有槽位索引（升序）
+    pub fn allocated_indices(&self) -> Vec<usize> {
+        self.pool.iter().enumerate()
+            .filter(|(_, slot)| slot.is_some())
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// 获取所有已分配的块：(起始索引, 阶数)，按索引升序
+    pub fn allocated_blocks(&self) -> Vec<(usize, usize)> {
+        let mut blocks: Vec<(usize, usize)> = self.allocated.iter()
+            .map(|(&index, &order)| (index, order))
+            .collect();
+        blocks.sort_unstable();
+        blocks
+    }
+
+    /// 检查伙伴系统的内部不变量，返回发现的问题描述（正常时为空）
+    ///
+    /// 检查项：已用数量不超过池大小；空闲链表中没有重复或相互重叠的块；
+    /// 空闲块与已分配块互不重叠且恰好覆盖整个池；没有互为伙伴却未合并的空闲块
+    pub fn check_invariants(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.used_count > self.pool_size {
+            problems.push(format!("已用数量 {} 超过池大小 {}", self.used_count, self.pool_size));
+        }
+
+        let mut owner: Vec<Option<&str>> = vec![None; self.pool_size];
+        let free_blocks = self.free_list.iter().enumerate()
+            .flat_map(|(order, list)| list.iter().map(move |&index| (index, order, "空闲")));
+        let used_blocks = self.allocated.iter().map(|(&index, &order)| (index, order, "已分配"));
+        for (index, order, kind) in free_blocks.chain(used_blocks) {
+            let size = 1 << order;
+            if index + size > self.pool_size {
+                problems.push(format!("{}块 {} (大小 {}) 越界", kind, index, size));
+                continue;
+            }
+            for (slot, slot_owner) in owner[index..index + size].iter_mut().enumerate() {
+                if let Some(previous) = slot_owner {
+                    problems.push(format!("{}块 {} (大小 {}) 与{}块重叠于槽位 {}", kind, index, size, previous, index + slot));
+                    break;
+                }
+                *slot_owner = Some(kind);
+            }
+        }
+
+        let covered = owner.iter().filter(|slot| slot.is_some()).count();
+        if covered != self.pool_size {
+            problems.push(format!("空闲块与已分配块只覆盖了 {} / {} 个槽位", covered, self.pool_size));
+        }
+
+        let missed_merges = self.can_coalesce();
+        if missed_merges > 0 {
+            problems.push(format!("有 {} 对互为伙伴的空闲块未被合并", missed_merges));
+        }
+        problems
+    }
+
+    /// 还能进行的合并次数：同一阶中互为伙伴、却都留在空闲链表中的块对数
+    ///
+    /// 回收时总是立即与空闲的伙伴合并，因此正常情况下应为0；大于0说明合并逻辑破坏了伙伴不变量
+    pub fn can_coalesce(&self) -> usize {
+        self.free_list.iter().enumerate()
+            .take(self.max_order) // 最高阶的块没有伙伴
+            .map(|(order, list)| {
+                list.iter()
+                    .filter(|&&index| {
+                        let buddy_index = index ^ (1 << order);
+                        index < buddy_index && list.contains(&buddy_index)
+                    })
+                    .count()
+            })
+            .sum()
+    }
+
+    /// 分配器工作量统计
+    pub fn alloc_stats(&self) -> AllocStats {
+        self.stats
+    }
+
+    /// 获取完整的分配/回收历史
+    pub fn alloc_history(&self) -> Vec<AllocEvent> {
+        self.history.clone()
+    }
+
+    /// 生成碎片报告
+    ///
+    /// 从最高阶向下遍历空闲链表：第一个非空阶的块大小即最大连续空闲块，
+    /// 碎片率 = (空闲槽位总数 - 最大空闲块大小) / 空闲槽位总数
+    pub fn fragmentation_report(&self) -> FragmentationReport {
+        let mut total_free = 0;
+        let mut largest_free_block = 0;
+
+        for order in (0..=self.max_order).rev() {
+            let block_size = 1 << order;
+            let count = self.free_list[order].len();
+            if count > 0 && largest_free_block == 0 {
+                largest_free_block = block_size;
+            }
+            total_free += count * block_size;
+        }
+
+        let fragmentation_percent = if total_free == 0 {
+            0.0
+        } else {
+            (total_free - largest_free_block) as f64 / total_free as f64 * 100.0
+        };
+
+        FragmentationReport {
+            total_free,
+            largest_free_block,
+            fragmentation_percent,
+        }
+    }
+
+    /// 以缩进文本绘制伙伴树：从整个池开始逐级展开已分裂的块
+    ///
+    /// 每行为一个块的槽位范围和阶数，F表示空闲块，A表示已分配块，
+    /// 已分裂的块标记为"分裂"并在下面缩进列出两个子块
+    pub fn render_tree(&self) -> String {
+        let mut lines = Vec::new();
+        self.render_node(0, self.max_order, 0, &mut lines);
+        lines.join("\n")
+    }
+
+    fn render_node(&self, index: usize, order: usize, depth: usize, lines: &mut Vec<String>) {
+        let size = 1 << order;
+        let marker = if self.allocated.get(&index) == Some(&order) {
+            "A"
+        } else if self.free_list[order].contains(&index) {
+            "F"
+        } else if order == 0 {
+            "?"
+        } else {
+            "分裂"
+        };
+        lines.push(format!("{}[{}..{}] 阶数 {}: {}", "  ".repeat(depth), index, index + size - 1, order, marker));
+
+        if marker == "分裂" {
+            let half = size / 2;
+            self.render_node(index, order - 1, depth + 1, lines);
+            self.render_node(index + half, order - 1, depth + 1, lines);
+        }
+    }
+
+    /// 打印伙伴系统状态（空闲链表与碎片情况）
+    pub fn print_status<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "\n=== 伙伴系统状态 ===")?;
+        writeln!(out, "池大小: {}, 已用: {}, 空闲: {}",
+                      self.pool_size, self.used_count, self.get_free_count())?;
+        writeln!(out, "分配策略: {}", self.strategy.name())?;
         for (order, list) in self.free_list.iter().enumerate() {
             if !list.is_empty() {
-                println!("  阶数 {} (大小 {}): {:?}", order, 1 << order, list);
+                writeln!(out, "  阶数 {} (大小 {}): {:?}", order, 1 << order, list)?;
             }
         }
+        writeln!(out, "碎片报告: {}", self.fragmentation_report())?;
+        writeln!(out, "操作统计: {}", self.alloc_stats())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clone_is_independent_of_the_original() {
+        let mut original = BuddySystem::new(16);
+        let first = original.allocate(0).unwrap();
+        original.store_pcb(first, PCB::new(first, 1));
+
+        let clone = original.clone();
+        assert_eq!(clone.max_order, original.max_order);
+        assert_eq!(clone.get_pool_size(), 16);
+
+        let second = original.allocate(1).unwrap();
+        original.store_pcb(second, PCB::new(second, 2));
+        assert!(original.deallocate(first, 2));
+
+        assert_eq!(clone.get_used_count(), 1);
+        assert_eq!(clone.allocated_indices(), vec![first]);
+        assert_eq!(clone.get_pcb(first).map(|pcb| pcb.pid), Some(1));
+        assert!(clone.get_pcb(second).is_none());
+        assert_eq!(clone.alloc_history().len(), 1);
+        assert!(clone.check_invariants().is_empty());
+        assert_eq!(original.allocated_indices(), vec![second]);
+    }
+
+    #[test]
+    fn double_free_is_rejected_without_corrupting_the_pool() {
+        let mut buddy = BuddySystem::new(16);
+        let first = buddy.allocate(0).unwrap();
+        buddy.store_pcb(first, PCB::new(first, 1));
+        let second = buddy.allocate(0).unwrap();
+        buddy.store_pcb(second, PCB::new(second, 2));
+
+        assert!(buddy.deallocate(first, 1));
+        let used = buddy.get_used_count();
+        assert!(!buddy.deallocate(first, 2));
+        assert_eq!(buddy.get_used_count(), used);
+        assert_eq!(buddy.allocated_indices(), vec![second]);
+        assert!(buddy.check_invariants().is_empty());
+    }
+
+    #[test]
+    fn mixed_order_blocks_coalesce_back_to_max_order() {
+        let mut buddy = BuddySystem::new(16);
+        let large = buddy.allocate_order(2, 0).unwrap();
+        buddy.store_pcb(large, PCB::new(large, 1));
+        let small = buddy.allocate_order(0, 1).unwrap();
+        buddy.store_pcb(small, PCB::new(small, 2));
+
+        // 4个槽位的块与单个槽位的块互不重叠
+        assert!(small < large || small >= large + 4);
+        assert_eq!(buddy.get_used_count(), 5);
+        assert_eq!(buddy.allocated_blocks(), {
+            let mut blocks = vec![(large, 2), (small, 0)];
+            blocks.sort_unstable();
+            blocks
+        });
+
+        assert!(buddy.deallocate(large, 2));
+        assert!(buddy.deallocate(small, 3));
+        assert_eq!(buddy.get_used_count(), 0);
+        assert_eq!(buddy.free_list[buddy.max_order], vec![0]);
+        assert!(buddy.free_list[..buddy.max_order].iter().all(Vec::is_empty));
+        assert!(buddy.check_invariants().is_empty());
+        assert_eq!(buddy.allocate_order(buddy.max_order, 4), Some(0));
+    }
+
+    #[test]
+    fn fragmentation_report_tracks_the_shrinking_largest_block() {
+        let mut buddy = BuddySystem::new(16);
+        let report = buddy.fragmentation_report();
+        assert_eq!((report.total_free, report.largest_free_block), (16, 16));
+        assert_eq!(report.fragmentation_percent, 0.0);
+
+        let indices: Vec<usize> = (0..16).map(|tick| {
+            let index = buddy.allocate(tick).unwrap();
+            buddy.store_pcb(index, PCB::new(index, tick + 1));
+            index
+        }).collect();
+        let report = buddy.fragmentation_report();
+        assert_eq!((report.total_free, report.largest_free_block), (0, 0));
+
+        // 隔一个回收一个：伙伴都还在使用中，空闲块无法合并
+        for &index in indices.iter().filter(|&&index| index % 2 == 0) {
+            assert!(buddy.deallocate(index, 16));
+        }
+        let report = buddy.fragmentation_report();
+        assert_eq!((report.total_free, report.largest_free_block), (8, 1));
+        assert_eq!(report.fragmentation_percent, 87.5);
+
+        // 再回收1号和3号槽位后，0..3合并为一个4槽位的块
+        assert!(buddy.deallocate(1, 17));
+        assert!(buddy.deallocate(3, 18));
+        let report = buddy.fragmentation_report();
+        assert_eq!((report.total_free, report.largest_free_block), (10, 4));
+        assert_eq!(report.fragmentation_percent, 60.0);
+    }
+
+    #[test]
+    fn alloc_stats_count_the_splits_and_merges_of_a_known_sequence() {
+        let mut buddy = BuddySystem::new(8);
+        let a = buddy.allocate_order(0, 0).unwrap(); // 8 → 4 → 2 → 1：分裂3次
+        let b = buddy.allocate_order(0, 1).unwrap(); // 直接使用a的伙伴
+        let c = buddy.allocate_order(1, 2).unwrap(); // 使用第一次分裂留下的2槽位块
+        for index in [a, b, c] {
+            buddy.store_pcb(index, PCB::new(index, index as u32 + 1));
+        }
+        let stats = buddy.alloc_stats();
+        assert_eq!((stats.allocations, stats.splits), (3, 3));
+
+        assert!(buddy.deallocate(a, 3)); // 伙伴b仍在使用，不合并
+        assert_eq!(buddy.alloc_stats().merges, 0);
+        assert!(buddy.deallocate(b, 4)); // a+b合并为2槽位块
+        assert_eq!(buddy.alloc_stats().merges, 1);
+        assert!(buddy.deallocate(c, 5)); // 2+2 → 4，4+4 → 8
+        assert!(!buddy.deallocate(c, 6));
+
+        let stats = buddy.alloc_stats();
+        assert_eq!((stats.allocations, stats.deallocations), (3, 3));
+        assert_eq!((stats.splits, stats.merges), (3, 3));
+        assert_eq!(buddy.get_free_count(), 8);
+    }
+
+    #[test]
+    fn render_tree_marks_free_and_allocated_blocks() {
+        let mut buddy = BuddySystem::new(8);
+        assert_eq!(buddy.render_tree(), "[0..7] 阶数 3: F");
+
+        let first = buddy.allocate_order(0, 0).unwrap();
+        let second = buddy.allocate_order(1, 1).unwrap();
+        assert_eq!((first, second), (0, 2));
+        assert_eq!(buddy.render_tree(), [
+            "[0..7] 阶数 3: 分裂",
+            "  [0..3] 阶数 2: 分裂",
+            "    [0..1] 阶数 1: 分裂",
+            "      [0..0] 阶数 0: A",
+            "      [1..1] 阶数 0: F",
+            "    [2..3] 阶数 1: A",
+            "  [4..7] 阶数 2: F",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn strategies_leave_differently_shaped_free_lists() {
+        let shape = |strategy: AllocStrategy| {
+            let mut buddy = BuddySystem::new(16);
+            buddy.set_strategy(strategy);
+            let first = buddy.allocate_order(0, 0).unwrap();
+            let second = buddy.allocate_order(0, 1).unwrap();
+            let lists: Vec<Vec<usize>> = buddy.free_list.iter()
+                .map(|list| {
+                    let mut list = list.clone();
+                    list.sort_unstable();
+                    list
+                })
+                .collect();
+            (first, second, lists, buddy.fragmentation_report().largest_free_block)
+        };
+
+        // 第一次分配两种策略都要把整个池分裂到底
+        let (first, second, lists, largest) = shape(AllocStrategy::SmallestFirst);
+        assert_eq!((first, second), (0, 1));
+        assert_eq!(lists, vec![vec![], vec![2], vec![4], vec![8], vec![]]);
+        assert_eq!(largest, 8);
+
+        // 最大可用块优先时第二次分配分裂了剩下的8槽位块，保留了小的空闲块
+        let (first, second, lists, largest) = shape(AllocStrategy::LargestFirst);
+        assert_eq!((first, second), (0, 8));
+        assert_eq!(lists, vec![vec![1, 9], vec![2, 10], vec![4, 12], vec![], vec![]]);
+        assert_eq!(largest, 4);
+    }
+
+    #[test]
+    fn no_merges_remain_possible_after_a_series_of_frees() {
+        let mut buddy = BuddySystem::new(32);
+        let blocks: Vec<usize> = [0, 2, 0, 1, 0, 3, 0, 1]
+            .iter()
+            .enumerate()
+            .map(|(tick, &order)| buddy.allocate_order(order, tick as u32).unwrap())
+            .collect();
+        for &index in &blocks {
+            buddy.store_pcb(index, PCB::new(index, index as u32 + 1));
+        }
+
+        for (tick, &position) in [5, 0, 3, 7, 1, 6, 2, 4].iter().enumerate() {
+            assert!(buddy.deallocate(blocks[position], 10 + tick as u32));
+            assert_eq!(buddy.can_coalesce(), 0, "回收块 {} 后", blocks[position]);
+        }
+        assert_eq!(buddy.free_list[buddy.max_order], vec![0]);
+
+        // 人为放入一对未合并的伙伴块，自检应能发现
+        buddy.free_list[buddy.max_order].clear();
+        buddy.free_list[buddy.max_order - 1].extend([0, 16]);
+        assert_eq!(buddy.can_coalesce(), 1);
+        assert!(!buddy.check_invariants().is_empty());
     }
 }
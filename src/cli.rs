@@ -0,0 +1,98 @@
+use crate::scheduler::SchedulingPolicy;
+
+/// 命令行用法说明
+pub const USAGE: &str = "用法: os_exp2 [--pool-size <数量>] [--policy <priority|rr|fcfs|sjf|lifo|mlfq|lottery|edf|cfs>] \
+[--time-slice <时间片>] [--script <脚本路径>] [--tui]";
+
+/// 命令行选项：未指定的项保持程序默认设置
+pub struct CliOptions {
+    pub pool_size: Option<usize>,           // PCB池大小（向上取整为2的幂）
+    pub policy: Option<SchedulingPolicy>,   // 初始调度策略
+    pub time_slice: Option<u32>,            // 默认时间片
+    pub script: Option<String>,             // 脚本文件路径，指定时不进入交互菜单
+    pub tui: bool,                          // 使用方向键操作的全屏菜单（需启用tui特性编译）
+}
+
+/// 解析命令行参数（不含程序名）
+pub fn parse_args(args: &[String]) -> Result<CliOptions, String> {
+    let mut options = CliOptions {
+        pool_size: None,
+        policy: None,
+        time_slice: None,
+        script: None,
+        tui: false,
+    };
+
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let mut value = || iter.next().ok_or(format!("{} 需要一个参数", flag));
+        match flag.as_str() {
+            "--pool-size" => {
+                let raw = value()?;
+                match raw.parse::<usize>() {
+                    Ok(size) if size > 0 => options.pool_size = Some(size),
+                    _ => return Err(format!("无效的PCB池大小: {}", raw)),
+                }
+            }
+            "--policy" => {
+                let raw = value()?;
+                options.policy = Some(SchedulingPolicy::from_name(raw)
+                    .ok_or(format!("未知的调度策略: {}", raw))?);
+            }
+            "--time-slice" => {
+                let raw = value()?;
+                match raw.parse::<u32>() {
+                    Ok(slice) if slice > 0 => options.time_slice = Some(slice),
+                    _ => return Err(format!("无效的时间片: {}", raw)),
+                }
+            }
+            "--script" => options.script = Some(value()?.clone()),
+            "--tui" => options.tui = true,
+            _ => return Err(format!("未知参数: {}", flag)),
+        }
+    }
+
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProcessManager;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn pool_size_policy_and_time_slice_flags_preconfigure_the_manager() {
+        let options = parse_args(&args(&["--pool-size", "64", "--policy", "RR", "--time-slice", "3"])).unwrap();
+        assert_eq!(options.pool_size, Some(64));
+        assert_eq!(options.policy, Some(SchedulingPolicy::RoundRobin));
+        assert_eq!(options.time_slice, Some(3));
+
+        let mut pm = ProcessManager::with_pool_size(options.pool_size.unwrap());
+        pm.set_policy(options.policy.unwrap());
+        pm.scheduler.set_default_time_slice(options.time_slice.unwrap()).unwrap();
+        let pid = pm.create_process(1, 10).unwrap();
+        assert_eq!(pm.total_chain[&pid].remaining_time, 3);
+
+        let mut status = Vec::new();
+        pm.print_status(&mut status).unwrap();
+        let status = String::from_utf8(status).unwrap();
+        assert!(status.contains("PCB池: 空闲 63 / 已用 1 / 总计 64"), "{}", status);
+        assert!(status.contains(&format!("调度策略: {}", SchedulingPolicy::RoundRobin.name())));
+    }
+
+    #[test]
+    fn missing_or_invalid_flags_are_rejected() {
+        let defaults = parse_args(&[]).unwrap();
+        assert!(defaults.pool_size.is_none() && defaults.policy.is_none() && defaults.time_slice.is_none());
+        assert!(defaults.script.is_none() && !defaults.tui);
+
+        assert_eq!(parse_args(&args(&["--pool-size", "0"])).err(), Some("无效的PCB池大小: 0".to_string()));
+        assert_eq!(parse_args(&args(&["--policy", "fifo"])).err(), Some("未知的调度策略: fifo".to_string()));
+        assert_eq!(parse_args(&args(&["--time-slice"])).err(), Some("--time-slice 需要一个参数".to_string()));
+        assert_eq!(parse_args(&args(&["--verbose"])).err(), Some("未知参数: --verbose".to_string()));
+    }
+}
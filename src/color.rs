@@ -0,0 +1,59 @@
+use std::cell::Cell;
+use std::io::IsTerminal;
+
+use crate::pcb::ProcessState;
+
+thread_local! {
+    /// 是否输出ANSI颜色（所有着色都经过本模块，关闭后输出不含任何转义序列）
+    ///
+    /// 与日志阈值一样按线程保存，并行运行的测试切换着色时互不影响
+    static ENABLED: Cell<bool> = const { Cell::new(false) };
+}
+
+const GREEN: &str = "\x1B[32m";
+const YELLOW: &str = "\x1B[33m";
+const RED: &str = "\x1B[31m";
+const RESET: &str = "\x1B[0m";
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.with(|flag| flag.set(enabled));
+}
+
+/// 标准输出是终端时默认开启颜色，重定向到文件或管道时默认关闭
+pub fn default_enabled() -> bool {
+    std::io::stdout().is_terminal()
+}
+
+/// 按状态着色的状态名：就绪为绿色，运行为黄色，等待为红色，其余状态不着色
+pub fn state_label(state: &ProcessState) -> String {
+    let code = match state {
+        ProcessState::Ready => Some(GREEN),
+        ProcessState::Running => Some(YELLOW),
+        ProcessState::Waiting => Some(RED),
+        _ => None,
+    };
+    match code {
+        Some(code) if ENABLED.with(Cell::get) => format!("{}{}{}", code, state.to_string(), RESET),
+        _ => state.to_string().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pcb::PCB;
+
+    #[test]
+    fn disabled_color_emits_no_ansi_codes() {
+        let mut pcb = PCB::new(0, 1);
+        set_enabled(true);
+        assert_eq!(state_label(&ProcessState::Running), "\x1B[33m运行\x1B[0m");
+
+        set_enabled(false);
+        for state in [ProcessState::Ready, ProcessState::Running, ProcessState::Waiting] {
+            assert_eq!(state_label(&state), state.to_string());
+            pcb.state = state;
+            assert!(!pcb.display().contains('\x1B'), "{}", pcb.display());
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::fmt;
+
+use crate::pcb::{PriorityRange, ProcessState, NICE_MAX, NICE_MIN};
+
+/// 进程管理原语的错误类型
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProcessError {
+    PcbPoolFull,                        // PCB池已满
+    OutOfMemory(usize),                 // 没有足够大的空闲块容纳所需内存（KB）
+    ProcessNotFound(u32),               // 进程不存在
+    NotInReadyOrRunning(u32),           // 进程不在就绪或运行队列中
+    NotInWaiting(u32),                  // 进程不在等待队列中
+    AlreadyWaiting(u32),                // 进程已处于等待状态
+    AlreadySuspended(u32),              // 进程已被挂起
+    CannotSuspend(u32, ProcessState),   // 进程所处状态不允许挂起
+    NoRunningProcess,                   // 没有正在运行的进程
+    NotRunning(u32),                    // 进程不是当前运行进程
+    NonPreemptible(u32),                // 进程处于临界区，不可抢占
+    NotInCritical(u32),                 // 进程不在临界区中
+    ReadyQueueEmpty,                    // 就绪队列为空
+    RunningQueueCorrupted,              // 运行队列状态异常
+    InvalidSnapshot(String),            // 快照内容无法恢复
+    SemaphoreNotFound(u32),             // 信号量不存在
+    GroupNotFound(u32),                 // 进程组不存在（没有任何成员）
+    InvalidNice(i32),                   // nice值超出范围
+    InvalidPriority(u32, PriorityRange), // 优先级超出允许范围
+    InvalidTimeSlice(u32),              // 时间片必须大于0
+}
+
+impl fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcessError::PcbPoolFull => write!(f, "PCB池已满，无法创建新进程"),
+            ProcessError::OutOfMemory(memory_kb) => {
+                write!(f, "没有足够大的空闲块容纳 {} KB 内存，无法创建新进程", memory_kb)
+            }
+            ProcessError::ProcessNotFound(pid) => write!(f, "进程 {} 不存在", pid),
+            ProcessError::NotInReadyOrRunning(pid) => write!(f, "进程 {} 不在就绪或运行队列中", pid),
+            ProcessError::NotInWaiting(pid) => write!(f, "进程 {} 不在等待队列中", pid),
+            ProcessError::AlreadyWaiting(pid) => write!(f, "进程 {} 已处于等待状态", pid),
+            ProcessError::AlreadySuspended(pid) => write!(f, "进程 {} 已被挂起", pid),
+            ProcessError::CannotSuspend(pid, state) => {
+                write!(f, "进程 {} 处于{}状态，不能挂起", pid, state.to_string())
+            }
+            ProcessError::NoRunningProcess => write!(f, "没有正在运行的进程"),
+            ProcessError::NotRunning(pid) => write!(f, "进程 {} 不是当前运行进程", pid),
+            ProcessError::NonPreemptible(pid) => write!(f, "进程 {} 处于临界区，不可抢占", pid),
+            ProcessError::NotInCritical(pid) => write!(f, "进程 {} 不在临界区中", pid),
+            ProcessError::ReadyQueueEmpty => write!(f, "就绪队列为空，无法调度"),
+            ProcessError::RunningQueueCorrupted => write!(f, "运行队列异常"),
+            ProcessError::InvalidSnapshot(reason) => write!(f, "快照无法恢复: {}", reason),
+            ProcessError::SemaphoreNotFound(id) => write!(f, "信号量 {} 不存在", id),
+            ProcessError::GroupNotFound(gid) => write!(f, "进程组 {} 不存在", gid),
+            ProcessError::InvalidNice(nice) => {
+                write!(f, "nice值 {} 超出范围 ({}..={})", nice, NICE_MIN, NICE_MAX)
+            }
+            ProcessError::InvalidPriority(priority, range) => {
+                write!(f, "优先级 {} 超出范围 ({})", priority, range)
+            }
+            ProcessError::InvalidTimeSlice(slice) => write!(f, "无效的时间片 {}（必须为正整数）", slice),
+        }
+    }
+}
+
+impl std::error::Error for ProcessError {}
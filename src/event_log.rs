@@ -0,0 +1,304 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+
+use crate::error::ProcessError;
+use crate::ProcessManager;
+use crate::ipc::Message;
+use crate::scheduler::SchedulingPolicy;
+use crate::state::ManagerState;
+use crate::workload::ProcessSpec;
+
+/// 事件日志中记录的原语调用（含参数）
+///
+/// 只记录用户直接调用的原语：原语内部引起的操作（如级联撤销子进程、
+/// 调度周期中进程运行完毕后的撤销）在重放该原语时会自然再次发生，不单独记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Primitive {
+    Create { priority: u32, burst: u32, deadline: Option<u32>, memory_kb: usize },
+    Terminate { pid: u32 },
+    Fork { parent: u32 },
+    TimeSliceExpired,
+    Suspend { pid: u32 },
+    Activate { pid: u32 },
+    Block { pid: u32 },
+    BlockOn { pid: u32, event: u32 },
+    Wakeup { pid: u32 },
+    WakeupEvent { event: u32 },
+    SetIoProfile { pid: u32, interval: u32, burst: u32 },
+    SemCreate { initial: i32 },
+    SemWait { sem: u32, pid: u32 },
+    SemSignal { sem: u32 },
+    SemCeiling { sem: u32, ceiling: Option<u32> },
+    Send { from: u32, to: u32, payload: String },
+    Receive { pid: u32 },
+    ReceiveOrBlock { pid: u32 },
+    ChangePriority { pid: u32, priority: u32 },
+    Renice { pid: u32, nice: i32 },
+    EnterCritical { pid: u32 },
+    LeaveCritical { pid: u32 },
+    SetMaxLifetime { pid: u32, max_lifetime: Option<u32> },
+    ScheduleArrival { spec: ProcessSpec },
+    CreateGroup { leader: u32 },
+    JoinGroup { pid: u32, gid: u32 },
+    LeaveGroup { pid: u32 },
+    SuspendGroup { gid: u32 },
+    ActivateGroup { gid: u32 },
+    TerminateGroup { gid: u32 },
+    SetPolicy { policy: SchedulingPolicy },
+    SetNumCpus { num_cpus: usize },
+    Cycle, // 一个调度周期
+}
+
+/// 一条日志记录：调用时的系统时间、原语及其结果的文字形式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedEvent {
+    pub time: u32,
+    pub primitive: Primitive,
+    pub result: String,
+}
+
+impl fmt::Display for LoggedEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[时间 {}] {:?} -> {}", self.time, self.primitive, self.result)
+    }
+}
+
+/// 只追加的事件日志
+///
+/// origin是记录第一条事件前系统的完整状态，从它出发依次重新执行events
+/// 即可重现记录结束时的状态。撤销、加载状态、从快照恢复或重置会使日志
+/// 无法再重现当前状态，这些操作后日志清空，在下一个原语调用时重新开始
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct EventLog {
+    pub origin: Option<ManagerState>,
+    pub events: Vec<LoggedEvent>,
+}
+
+impl EventLog {
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.origin = None;
+        self.events.clear();
+    }
+}
+
+/// 原语返回Result时结果的文字形式：成功时由ok描述，失败时记录错误信息
+pub fn describe<T>(result: &Result<T, ProcessError>, ok: impl FnOnce(&T) -> String) -> String {
+    match result {
+        Ok(value) => ok(value),
+        Err(e) => format!("错误: {}", e),
+    }
+}
+
+/// 没有返回值的原语成功时记为ok
+pub fn done<T>(result: &Result<T, ProcessError>) -> String {
+    describe(result, |_| "ok".to_string())
+}
+
+/// 接收消息的结果：收到时记录发送进程，信箱为空时记为无消息
+pub fn received(message: Option<&Message>) -> String {
+    match message {
+        Some(message) => format!("收到来自 pid {} 的消息", message.from),
+        None => "无消息".to_string(),
+    }
+}
+
+/// 重放结果：重放得到的结果与日志中记录的不同的事件（序号从1开始，日志记录的结果，重放的结果）
+pub struct ReplayReport {
+    pub replayed: usize,
+    pub divergences: Vec<(usize, String, String)>,
+}
+
+impl fmt::Display for ReplayReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "已重放 {} 个事件", self.replayed)?;
+        if self.divergences.is_empty() {
+            write!(f, "✓ 每个事件的结果都与日志一致")
+        } else {
+            write!(f, "✗ {} 个事件的结果与日志不一致:", self.divergences.len())?;
+            for (index, logged, replayed) in &self.divergences {
+                write!(f, "\n  第 {} 个事件: 日志 {}，重放 {}", index, logged, replayed)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// 事件日志：记录原语调用、导出日志并重放到新的进程管理器
+impl ProcessManager {
+    /// 执行一个原语，最外层的调用连同其结果追加到事件日志
+    ///
+    /// 原语内部再调用的原语（嵌套层数大于0）不记录，重放外层原语时会再次发生。
+    /// 日志为空时先记下当前完整状态，作为重放的起点；最外层的原语执行前还生成一个自动快照
+    pub(crate) fn logged<T>(
+        &mut self,
+        primitive: Primitive,
+        op: impl FnOnce(&mut Self) -> T,
+        outcome: impl FnOnce(&T) -> String,
+    ) -> T {
+        if self.primitive_depth > 0 {
+            return op(self);
+        }
+        if self.event_log.origin.is_none() {
+            self.event_log.origin = Some(self.to_state());
+        }
+        self.record_auto_snapshot(&primitive);
+        let time = self.scheduler.current_time();
+        self.primitive_depth += 1;
+        let result = op(self);
+        self.primitive_depth -= 1;
+        self.event_log.events.push(LoggedEvent { time, primitive, result: outcome(&result) });
+        result
+    }
+
+    /// 重新执行一个记录下来的原语，返回其结果的文字形式（与日志中的记法相同）
+    fn apply(&mut self, primitive: Primitive) -> String {
+        match primitive {
+            Primitive::Create { priority, burst, deadline, memory_kb } => {
+                let _ = self.create_process_with_memory(priority, burst, deadline, memory_kb);
+            }
+            Primitive::Terminate { pid } => { let _ = self.terminate_process(pid); }
+            Primitive::Fork { parent } => { let _ = self.fork(parent); }
+            Primitive::TimeSliceExpired => { let _ = self.time_slice_expired(); }
+            Primitive::Suspend { pid } => { let _ = self.suspend_process(pid); }
+            Primitive::Activate { pid } => { let _ = self.activate_process(pid); }
+            Primitive::Block { pid } => { let _ = self.block(pid); }
+            Primitive::BlockOn { pid, event } => { let _ = self.block_on(pid, event); }
+            Primitive::Wakeup { pid } => { let _ = self.wakeup(pid); }
+            Primitive::WakeupEvent { event } => { self.wakeup_event(event); }
+            Primitive::SetIoProfile { pid, interval, burst } => { let _ = self.set_io_profile(pid, interval, burst); }
+            Primitive::SemCreate { initial } => { self.sem_create(initial); }
+            Primitive::SemCeiling { sem, ceiling } => { let _ = self.set_sem_ceiling(sem, ceiling); }
+            Primitive::SemWait { sem, pid } => { let _ = self.sem_wait(sem, pid); }
+            Primitive::SemSignal { sem } => { let _ = self.sem_signal(sem); }
+            Primitive::Send { from, to, payload } => { let _ = self.send_message(from, to, payload); }
+            Primitive::Receive { pid } => { self.receive_message(pid); }
+            Primitive::ReceiveOrBlock { pid } => { let _ = self.receive_or_block(pid); }
+            Primitive::ChangePriority { pid, priority } => { let _ = self.change_priority(pid, priority); }
+            Primitive::Renice { pid, nice } => { let _ = self.renice(pid, nice); }
+            Primitive::EnterCritical { pid } => { let _ = self.enter_critical(pid); }
+            Primitive::LeaveCritical { pid } => { let _ = self.leave_critical(pid); }
+            Primitive::SetMaxLifetime { pid, max_lifetime } => { let _ = self.set_max_lifetime(pid, max_lifetime); }
+            Primitive::ScheduleArrival { spec } => { let _ = self.schedule_arrival(spec); }
+            Primitive::CreateGroup { leader } => { let _ = self.create_group(leader); }
+            Primitive::JoinGroup { pid, gid } => { let _ = self.join_group(pid, gid); }
+            Primitive::LeaveGroup { pid } => { let _ = self.leave_group(pid); }
+            Primitive::SuspendGroup { gid } => { let _ = self.suspend_group(gid); }
+            Primitive::ActivateGroup { gid } => { let _ = self.activate_group(gid); }
+            Primitive::TerminateGroup { gid } => { let _ = self.terminate_group(gid); }
+            Primitive::SetPolicy { policy } => self.set_policy(policy),
+            Primitive::SetNumCpus { num_cpus } => self.set_num_cpus(num_cpus),
+            Primitive::Cycle => self.run_one_cycle(),
+        }
+        self.event_log.events.last().map(|event| event.result.clone()).unwrap_or_default()
+    }
+
+    /// 显示事件日志，每个事件一行
+    pub(crate) fn print_event_log<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "\n=== 事件日志（共 {} 个事件）===", self.event_log.len())?;
+        for (index, event) in self.event_log.events.iter().enumerate() {
+            writeln!(out, "{:>4}. {}", index + 1, event)?;
+        }
+        Ok(())
+    }
+
+    /// 两个进程管理器的完整状态（即保存到文件的内容）是否完全相同
+    pub(crate) fn same_state(&self, other: &Self) -> bool {
+        serde_json::to_value(self.to_state()).ok() == serde_json::to_value(other.to_state()).ok()
+    }
+
+    /// 导出事件日志到文件（JSON格式，包含重放的起始状态）
+    pub(crate) fn export_event_log(&self, path: &str) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(&self.event_log)
+            .map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    /// 重放事件日志文件：在一个新的进程管理器上从日志的起始状态出发
+    /// （日志没有事件时为默认大小的空系统），依次重新执行日志中的原语
+    ///
+    /// 返回重放后的进程管理器，以及结果与日志记录不一致的事件
+    pub(crate) fn replay(path: &str) -> io::Result<(Self, ReplayReport)> {
+        let json = fs::read_to_string(path)?;
+        let log: EventLog = serde_json::from_str(&json)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut pm = match log.origin {
+            Some(state) => Self::from_state(state)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            None => Self::new(),
+        };
+
+        let mut report = ReplayReport { replayed: 0, divergences: Vec::new() };
+        for (index, event) in log.events.into_iter().enumerate() {
+            let result = pm.apply(event.primitive);
+            if result != event.result {
+                report.divergences.push((index + 1, event.result, result));
+            }
+            report.replayed += 1;
+        }
+        Ok((pm, report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MAX_RUN_CYCLES;
+
+    #[test]
+    fn replaying_event_log_reproduces_final_state() {
+        let mut pm = ProcessManager::new();
+        pm.create_process(2, 6).unwrap();
+        pm.run_one_cycle();
+
+        // 日志从这里开始：起点状态中已有一个运行了一个周期的进程
+        pm.event_log.clear();
+        let parent = pm.create_process(4, 8).unwrap();
+        let child = pm.fork(parent).unwrap();
+        let sem = pm.sem_create(0);
+        pm.set_num_cpus(2);
+        pm.run_cycles(3);
+        pm.sem_wait(sem, child).unwrap();
+        pm.send_message(parent, child, "hello".to_string()).unwrap();
+        pm.block_on(parent, 9).unwrap();
+        pm.set_policy(SchedulingPolicy::RoundRobin);
+        pm.run_cycles(4);
+        pm.wakeup_event(9);
+        pm.sem_signal(sem).unwrap();
+        assert!(pm.terminate_process(99).is_err());
+        pm.run_until_idle(MAX_RUN_CYCLES);
+
+        let path = std::env::temp_dir().join(format!("os_exp2_replay_test_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        pm.export_event_log(path).unwrap();
+        let replayed = ProcessManager::replay(path);
+        fs::remove_file(path).unwrap();
+
+        let (replayed, report) = replayed.unwrap();
+        assert_eq!(report.replayed, pm.event_log.len());
+        assert!(report.divergences.is_empty(), "{}", report);
+        assert!(replayed.same_state(&pm));
+        assert_eq!(replayed.scheduler.current_time(), pm.scheduler.current_time());
+    }
+
+    #[test]
+    fn event_log_records_only_top_level_primitives() {
+        let mut pm = ProcessManager::new();
+        let parent = pm.create_process(1, 10).unwrap();
+        let child = pm.fork(parent).unwrap();
+        pm.terminate_process(parent).unwrap();
+
+        let primitives: Vec<&Primitive> = pm.event_log.events.iter().map(|event| &event.primitive).collect();
+        assert_eq!(primitives.len(), 3);
+        assert!(matches!(primitives[0], Primitive::Create { priority: 1, burst: 10, .. }));
+        assert_eq!(primitives[1], &Primitive::Fork { parent });
+        assert_eq!(primitives[2], &Primitive::Terminate { pid: parent });
+        assert_eq!(pm.event_log.events[1].result, format!("pid {}", child));
+        assert_eq!(pm.event_log.events[2].result, format!("ok，级联撤销 {:?}", [child]));
+    }
+}
@@ -0,0 +1,230 @@
+use std::io::{self, Write};
+
+use crate::color;
+use crate::error::ProcessError;
+use crate::event_log::{describe, done, Primitive};
+use crate::logger::{log, LogLevel};
+use crate::pcb::ProcessState;
+use crate::ProcessManager;
+
+/// 进程组（作业控制）：组号即组长的PID，可对整组挂起、激活或撤销
+impl ProcessManager {
+    /// 创建进程组：以leader为组长，组号即组长的PID，组长离开原来所属的组加入新组
+    ///
+    /// 与UNIX的进程组相同，之后可用join_group把其他进程加入该组，
+    /// 对整组进行挂起、激活或撤销（作业控制）
+    pub(crate) fn create_group(&mut self, leader: u32) -> Result<u32, ProcessError> {
+        self.logged(
+            Primitive::CreateGroup { leader },
+            |pm| pm.create_group_unlogged(leader),
+            |result| describe(result, |gid| format!("进程组 {}", gid)),
+        )
+    }
+
+    fn create_group_unlogged(&mut self, leader: u32) -> Result<u32, ProcessError> {
+        let pcb = self.total_chain.get_mut(&leader)
+            .ok_or(ProcessError::ProcessNotFound(leader))?;
+        pcb.group_id = Some(leader);
+        log(LogLevel::Info, format_args!("✓ 已创建进程组 {}，组长为进程 {}", leader, leader));
+        Ok(leader)
+    }
+
+    /// 把进程加入已有的进程组（离开原来所属的组），组中没有任何成员时返回GroupNotFound
+    pub(crate) fn join_group(&mut self, pid: u32, gid: u32) -> Result<(), ProcessError> {
+        self.logged(
+            Primitive::JoinGroup { pid, gid },
+            |pm| pm.join_group_unlogged(pid, gid),
+            done,
+        )
+    }
+
+    fn join_group_unlogged(&mut self, pid: u32, gid: u32) -> Result<(), ProcessError> {
+        if !self.total_chain.contains_key(&pid) {
+            return Err(ProcessError::ProcessNotFound(pid));
+        }
+        if self.group_members(gid).is_empty() {
+            return Err(ProcessError::GroupNotFound(gid));
+        }
+        if let Some(pcb) = self.total_chain.get_mut(&pid) {
+            pcb.group_id = Some(gid);
+        }
+        log(LogLevel::Info, format_args!("✓ 进程 {} 已加入进程组 {}", pid, gid));
+        Ok(())
+    }
+
+    /// 进程离开所属的进程组（本来不属于任何组时什么也不做）
+    pub(crate) fn leave_group(&mut self, pid: u32) -> Result<(), ProcessError> {
+        self.logged(
+            Primitive::LeaveGroup { pid },
+            |pm| pm.leave_group_unlogged(pid),
+            done,
+        )
+    }
+
+    fn leave_group_unlogged(&mut self, pid: u32) -> Result<(), ProcessError> {
+        let pcb = self.total_chain.get_mut(&pid)
+            .ok_or(ProcessError::ProcessNotFound(pid))?;
+        if let Some(gid) = pcb.group_id.take() {
+            log(LogLevel::Info, format_args!("✓ 进程 {} 已离开进程组 {}", pid, gid));
+        }
+        Ok(())
+    }
+
+    /// 进程组的所有成员，按PID升序
+    fn group_members(&self, gid: u32) -> Vec<u32> {
+        self.matching_pids(|pcb| pcb.group_id == Some(gid))
+    }
+
+    /// 挂起整个进程组，返回本次被挂起的成员
+    ///
+    /// 先收集成员并逐个检查，任何一个成员不能挂起时整组都不挂起（全部或全不）；
+    /// 已被挂起的成员跳过。运行中的成员让出CPU，阻塞中的成员转为挂起阻塞
+    pub(crate) fn suspend_group(&mut self, gid: u32) -> Result<Vec<u32>, ProcessError> {
+        self.logged(
+            Primitive::SuspendGroup { gid },
+            |pm| pm.suspend_group_unlogged(gid),
+            |result| describe(result, |pids| format!("挂起 {:?}", pids)),
+        )
+    }
+
+    fn suspend_group_unlogged(&mut self, gid: u32) -> Result<Vec<u32>, ProcessError> {
+        let members = self.group_members(gid);
+        if members.is_empty() {
+            return Err(ProcessError::GroupNotFound(gid));
+        }
+
+        let mut targets = Vec::new();
+        for pid in members {
+            let state = self.total_chain[&pid].state.clone();
+            match state {
+                ProcessState::SuspendedReady | ProcessState::SuspendedBlocked => {}
+                ProcessState::Ready | ProcessState::Running => targets.push(pid),
+                ProcessState::Waiting if self.waiting_queue.contains_pid(pid) => targets.push(pid),
+                _ => return Err(ProcessError::CannotSuspend(pid, state)),
+            }
+        }
+
+        for &pid in &targets {
+            self.suspend_process(pid)?;
+        }
+        log(LogLevel::Info, format_args!("✓ 进程组 {} 已挂起，共 {} 个进程", gid, targets.len()));
+        Ok(targets)
+    }
+
+    /// 激活整个进程组中被挂起的成员，返回本次被激活的成员
+    ///
+    /// 与挂起相同，先检查全部成员再逐个激活；未被挂起的成员跳过
+    pub(crate) fn activate_group(&mut self, gid: u32) -> Result<Vec<u32>, ProcessError> {
+        self.logged(
+            Primitive::ActivateGroup { gid },
+            |pm| pm.activate_group_unlogged(gid),
+            |result| describe(result, |pids| format!("激活 {:?}", pids)),
+        )
+    }
+
+    fn activate_group_unlogged(&mut self, gid: u32) -> Result<Vec<u32>, ProcessError> {
+        let members = self.group_members(gid);
+        if members.is_empty() {
+            return Err(ProcessError::GroupNotFound(gid));
+        }
+
+        let targets: Vec<u32> = members.into_iter()
+            .filter(|pid| matches!(
+                self.total_chain[pid].state,
+                ProcessState::SuspendedReady | ProcessState::SuspendedBlocked
+            ))
+            .collect();
+        if let Some(&pid) = targets.iter().find(|&&pid| !self.waiting_queue.contains_pid(pid)) {
+            return Err(ProcessError::NotInWaiting(pid));
+        }
+
+        for &pid in &targets {
+            self.activate_process(pid)?;
+        }
+        log(LogLevel::Info, format_args!("✓ 进程组 {} 已激活，共 {} 个进程", gid, targets.len()));
+        Ok(targets)
+    }
+
+    /// 撤销整个进程组，返回所有被撤销的PID（含级联撤销的、不在组中的子进程）
+    ///
+    /// 先收集成员再逐个撤销；已随组内父进程级联撤销的成员跳过
+    pub(crate) fn terminate_group(&mut self, gid: u32) -> Result<Vec<u32>, ProcessError> {
+        self.logged(
+            Primitive::TerminateGroup { gid },
+            |pm| pm.terminate_group_unlogged(gid),
+            |result| describe(result, |pids| format!("撤销 {:?}", pids)),
+        )
+    }
+
+    fn terminate_group_unlogged(&mut self, gid: u32) -> Result<Vec<u32>, ProcessError> {
+        let members = self.group_members(gid);
+        if members.is_empty() {
+            return Err(ProcessError::GroupNotFound(gid));
+        }
+
+        let mut terminated = Vec::new();
+        for pid in members {
+            if self.total_chain.contains_key(&pid) {
+                terminated.extend(self.terminate_process(pid)?);
+            }
+        }
+        log(LogLevel::Info, format_args!("✓ 进程组 {} 已撤销，共 {} 个进程", gid, terminated.len()));
+        Ok(terminated)
+    }
+
+    /// 显示所有进程组及其成员
+    pub(crate) fn print_groups<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "\n=== 进程组 ===")?;
+        let mut groups: Vec<u32> = self.total_chain.values().filter_map(|pcb| pcb.group_id).collect();
+        groups.sort_unstable();
+        groups.dedup();
+        if groups.is_empty() {
+            writeln!(out, "  (无)")?;
+            return Ok(());
+        }
+        for gid in groups {
+            let members: Vec<String> = self.group_members(gid).iter()
+                .map(|pid| format!("{}({})", pid, color::state_label(&self.total_chain[pid].state)))
+                .collect();
+            writeln!(out, "  进程组 {}: {}", gid, members.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suspending_a_group_suspends_all_three_members_in_one_call() {
+        let mut pm = ProcessManager::new();
+        let leader = pm.create_process(5, 10).unwrap();
+        let ready = pm.create_process(3, 10).unwrap();
+        let blocked = pm.create_process(2, 10).unwrap();
+        let outsider = pm.create_process(1, 10).unwrap();
+        pm.fill_idle_cores();
+        pm.block_on(blocked, 1).unwrap();
+
+        let gid = pm.create_group(leader).unwrap();
+        pm.join_group(ready, gid).unwrap();
+        pm.join_group(blocked, gid).unwrap();
+
+        assert_eq!(pm.suspend_group(gid).unwrap(), vec![leader, ready, blocked]);
+        let state = |pm: &ProcessManager, pid: u32| pm.total_chain[&pid].state.clone();
+        assert_eq!(state(&pm, leader), ProcessState::SuspendedReady);
+        assert_eq!(state(&pm, ready), ProcessState::SuspendedReady);
+        assert_eq!(state(&pm, blocked), ProcessState::SuspendedBlocked);
+        assert!(!pm.is_running(leader));
+        assert_eq!(pm.ready_queue.pids(), vec![outsider]);
+        assert!(pm.verify_consistency().is_empty());
+
+        // 再次挂起时已挂起的成员被跳过
+        assert!(pm.suspend_group(gid).unwrap().is_empty());
+        assert!(matches!(pm.suspend_group(99), Err(ProcessError::GroupNotFound(99))));
+
+        assert_eq!(pm.activate_group(gid).unwrap(), vec![leader, ready, blocked]);
+        assert_eq!(state(&pm, blocked), ProcessState::Waiting);
+        assert_eq!(pm.ready_queue.len(), 3);
+    }
+}
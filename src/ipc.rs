@@ -0,0 +1,545 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::error::ProcessError;
+use crate::event_log::{describe, done, received, Primitive};
+use crate::logger::{log, LogLevel};
+use crate::queue::ProcessQueue;
+use crate::scheduler::SchedulingPolicy;
+use crate::ProcessManager;
+
+/// 进程间传递的消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Message {
+    pub from: u32,          // 发送进程PID
+    pub payload: String,    // 消息内容
+    pub sent_at: u32,       // 发送时的系统时间
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "来自进程 {} 的消息（时间 {}）: {}", self.from, self.sent_at, self.payload)
+    }
+}
+
+/// 记录型信号量
+///
+/// count >= 0 时表示可用资源数，count < 0 时其绝对值等于阻塞在该信号量上的进程数。
+/// 阻塞进程的PID保存在信号量自己的等待队列中，按先来先到唤醒。
+/// holder记录最近一次获得该信号量的进程，用于构造等待图检测死锁。
+/// ceiling是优先级天花板：持有者在持有期间至少以该优先级运行（None表示不使用天花板协议）
+#[derive(Clone)]
+pub struct Semaphore {
+    count: i32,
+    waiting: ProcessQueue,
+    holder: Option<u32>,
+    ceiling: Option<u32>,
+}
+
+impl Semaphore {
+    pub fn new(initial: i32) -> Self {
+        Semaphore {
+            count: initial,
+            waiting: ProcessQueue::new(),
+            holder: None,
+            ceiling: None,
+        }
+    }
+
+    /// 按保存的计数、阻塞进程（按阻塞顺序）和优先级天花板重建信号量
+    pub fn restore(count: i32, waiters: Vec<u32>, holder: Option<u32>, ceiling: Option<u32>) -> Self {
+        let mut waiting = ProcessQueue::new();
+        for pid in waiters {
+            waiting.enqueue(pid);
+        }
+        Semaphore { count, waiting, holder, ceiling }
+    }
+
+    pub fn count(&self) -> i32 {
+        self.count
+    }
+
+    /// 最近一次获得该信号量的进程
+    pub fn holder(&self) -> Option<u32> {
+        self.holder
+    }
+
+    /// 优先级天花板
+    pub fn ceiling(&self) -> Option<u32> {
+        self.ceiling
+    }
+
+    pub fn set_ceiling(&mut self, ceiling: Option<u32>) {
+        self.ceiling = ceiling;
+    }
+
+    /// P操作的计数部分：count减一，返回调用进程是否需要阻塞
+    ///
+    /// 不需要阻塞时pid成为该信号量的持有者
+    pub fn wait(&mut self, pid: u32) -> bool {
+        self.count -= 1;
+        if self.count < 0 {
+            true
+        } else {
+            self.holder = Some(pid);
+            false
+        }
+    }
+
+    /// 将P操作中需要阻塞的进程放入等待队列
+    pub fn block(&mut self, pid: u32) {
+        self.waiting.enqueue(pid);
+    }
+
+    /// V操作：count加一，若仍有进程阻塞则取出最先阻塞的一个
+    ///
+    /// 被唤醒的进程成为新的持有者；没有进程被唤醒时持有者清空
+    pub fn signal(&mut self) -> Option<u32> {
+        self.count += 1;
+        let woken = if self.count <= 0 {
+            self.waiting.dequeue()
+        } else {
+            None
+        };
+        self.holder = woken;
+        woken
+    }
+
+    /// 将进程从等待队列中移除（进程被撤销时使用）
+    ///
+    /// 移除后该进程不再等待，count相应加一；返回该进程是否在等待队列中
+    pub fn remove_waiter(&mut self, pid: u32) -> bool {
+        let removed = self.waiting.remove_by_pid(pid);
+        if removed {
+            self.count += 1;
+        }
+        removed
+    }
+
+    /// 是否有指定PID的进程阻塞在该信号量上
+    pub fn contains_pid(&self, pid: u32) -> bool {
+        self.waiting.contains_pid(pid)
+    }
+
+    /// 阻塞在该信号量上的进程PID（按阻塞顺序）
+    pub fn waiters(&self) -> Vec<u32> {
+        self.waiting.pids()
+    }
+}
+
+/// 进程管理器的信号量与信箱原语：P/V操作、优先级继承与天花板、死锁检测和消息传递
+impl ProcessManager {
+    /// 创建信号量，返回信号量编号
+    pub(crate) fn sem_create(&mut self, initial: i32) -> u32 {
+        self.logged(
+            Primitive::SemCreate { initial },
+            |pm| pm.sem_create_unlogged(initial),
+            |sem_id| format!("信号量 {}", sem_id),
+        )
+    }
+
+    fn sem_create_unlogged(&mut self, initial: i32) -> u32 {
+        let sem_id = self.next_sem_id;
+        self.next_sem_id += 1;
+        self.semaphores.insert(sem_id, Semaphore::new(initial));
+        log(LogLevel::Info, format_args!("✓ 信号量 {} 创建成功，初值: {}", sem_id, initial));
+        sem_id
+    }
+
+    /// P操作（wait）
+    ///
+    /// 信号量减一；结果为负时进程阻塞，从就绪或运行队列移入该信号量的等待队列。
+    /// 只有就绪或运行中的进程可以执行P操作
+    pub(crate) fn sem_wait(&mut self, sem_id: u32, pid: u32) -> Result<(), ProcessError> {
+        self.logged(
+            Primitive::SemWait { sem: sem_id, pid },
+            |pm| pm.sem_wait_unlogged(sem_id, pid),
+            done,
+        )
+    }
+
+    fn sem_wait_unlogged(&mut self, sem_id: u32, pid: u32) -> Result<(), ProcessError> {
+        if !self.semaphores.contains_key(&sem_id) {
+            return Err(ProcessError::SemaphoreNotFound(sem_id));
+        }
+        self.check_blockable(pid)?;
+
+        let must_block = self.semaphores.get_mut(&sem_id)
+            .ok_or(ProcessError::SemaphoreNotFound(sem_id))?
+            .wait(pid);
+        if !must_block {
+            log(LogLevel::Info, format_args!("✓ 进程 {} 对信号量 {} 执行P操作成功，继续执行", pid, sem_id));
+            self.refresh_inheritance(pid);
+            return Ok(());
+        }
+
+        let was_running = self.detach_for_blocking(pid, None)?;
+        let mut holder = None;
+        if let Some(sem) = self.semaphores.get_mut(&sem_id) {
+            sem.block(pid);
+            holder = sem.holder();
+        }
+        log(LogLevel::Info, format_args!("✓ 进程 {} 阻塞在信号量 {} 上", pid, sem_id));
+
+        if was_running {
+            self.release_cpu()?;
+        }
+        if let Some(holder) = holder {
+            self.refresh_inheritance(holder);
+        }
+        Ok(())
+    }
+
+    /// 设置信号量的优先级天花板（None表示取消）
+    ///
+    /// 天花板必须在允许的优先级范围内；信号量当前有持有者时立即按新的天花板调整其优先级
+    pub(crate) fn set_sem_ceiling(&mut self, sem_id: u32, ceiling: Option<u32>) -> Result<(), ProcessError> {
+        self.logged(
+            Primitive::SemCeiling { sem: sem_id, ceiling },
+            |pm| pm.set_sem_ceiling_unlogged(sem_id, ceiling),
+            done,
+        )
+    }
+
+    fn set_sem_ceiling_unlogged(&mut self, sem_id: u32, ceiling: Option<u32>) -> Result<(), ProcessError> {
+        if let Some(priority) = ceiling {
+            if !self.priority_range.contains(priority) {
+                return Err(ProcessError::InvalidPriority(priority, self.priority_range));
+            }
+        }
+        let sem = self.semaphores.get_mut(&sem_id)
+            .ok_or(ProcessError::SemaphoreNotFound(sem_id))?;
+        sem.set_ceiling(ceiling);
+        let holder = sem.holder();
+        match ceiling {
+            Some(priority) => log(LogLevel::Info, format_args!("✓ 信号量 {} 的优先级天花板设为 {}", sem_id, priority)),
+            None => log(LogLevel::Info, format_args!("✓ 信号量 {} 取消优先级天花板", sem_id)),
+        }
+        if let Some(holder) = holder {
+            self.refresh_inheritance(holder);
+        }
+        Ok(())
+    }
+
+    /// 优先级继承与优先级天花板：重新计算进程因其持有的信号量而获得的优先级
+    ///
+    /// 取以pid为持有者的所有信号量上阻塞进程的最高有效优先级及这些信号量的天花板中的最大值，
+    /// 高于进程自身优先级时继承该值，否则清除继承。有效优先级变化后重新排序就绪队列，
+    /// 开启抢占时可能立即抢占运行进程
+    pub(crate) fn refresh_inheritance(&mut self, pid: u32) {
+        let held: Vec<&Semaphore> = self.semaphores.values()
+            .filter(|sem| sem.holder() == Some(pid))
+            .collect();
+        let ceiling = held.iter().filter_map(|sem| sem.ceiling()).max();
+        let donated = held.iter()
+            .flat_map(|sem| sem.waiters())
+            .filter_map(|waiter| self.total_chain.get(&waiter))
+            .map(|waiter| waiter.effective_priority())
+            .chain(ceiling)
+            .max();
+
+        let Some(pcb) = self.total_chain.get_mut(&pid) else {
+            return;
+        };
+        let inherited = donated.filter(|&priority| priority > pcb.own_priority());
+        if inherited == pcb.inherited_priority {
+            return;
+        }
+        pcb.inherited_priority = inherited;
+        match inherited {
+            Some(priority) => log(LogLevel::Info, format_args!("✓ 进程 {} 继承优先级 {}", pid, priority)),
+            None => log(LogLevel::Info, format_args!("✓ 进程 {} 恢复原优先级 {}", pid, pcb.own_priority())),
+        }
+
+        if self.ready_queue.contains_pid(pid) {
+            if self.scheduler.policy() == SchedulingPolicy::Priority {
+                self.ready_queue.reorder_by_priority(&self.total_chain);
+            }
+            self.preempt_if_outranked(pid);
+        }
+    }
+
+    /// 死锁检测
+    ///
+    /// 以阻塞在信号量上的进程为结点构造等待图：进程P阻塞在信号量S上、
+    /// S的持有者Q也处于阻塞状态时，连一条P→Q的边。每个进程最多阻塞在一个信号量上，
+    /// 因此每个结点至多一条出边，沿边前进回到路径上的结点即找到环。
+    /// 返回所有环上的进程PID（升序），无死锁时返回空
+    pub(crate) fn detect_deadlock(&self) -> Vec<u32> {
+        let mut waits_for: HashMap<u32, u32> = HashMap::new();
+        for sem in self.semaphores.values() {
+            if let Some(holder) = sem.holder() {
+                for waiter in sem.waiters() {
+                    if waiter != holder {
+                        waits_for.insert(waiter, holder);
+                    }
+                }
+            }
+        }
+
+        let mut deadlocked = std::collections::BTreeSet::new();
+        for &start in waits_for.keys() {
+            let mut path = vec![start];
+            let mut current = start;
+            while let Some(&next) = waits_for.get(&current) {
+                if let Some(pos) = path.iter().position(|&pid| pid == next) {
+                    deadlocked.extend(&path[pos..]);
+                    break;
+                }
+                path.push(next);
+                current = next;
+            }
+        }
+        deadlocked.into_iter().collect()
+    }
+
+    /// 阻塞在指定资源上的进程PID（按阻塞顺序）
+    ///
+    /// resource为信号量（"sem3"，或直接写编号"3"）或事件（"event2"），
+    /// 分别从信号量的等待队列和等待队列中读取；资源不存在或名称无法识别时返回空
+    pub(crate) fn blocked_on(&self, resource: &str) -> Vec<u32> {
+        let resource = resource.trim();
+        if let Some(event_id) = resource.strip_prefix("event").and_then(|id| id.trim().parse::<u32>().ok()) {
+            return self.waiting_queue.iter()
+                .filter(|pid| self.total_chain.get(pid).is_some_and(|pcb| pcb.blocked_event == Some(event_id)))
+                .collect();
+        }
+        resource.strip_prefix("sem").unwrap_or(resource).trim().parse::<u32>().ok()
+            .and_then(|sem_id| self.semaphores.get(&sem_id))
+            .map_or_else(Vec::new, |sem| sem.waiters())
+    }
+
+    /// V操作（signal）
+    ///
+    /// 信号量加一；若仍有进程阻塞，唤醒最先阻塞的进程到就绪队列，返回其PID
+    pub(crate) fn sem_signal(&mut self, sem_id: u32) -> Result<Option<u32>, ProcessError> {
+        self.logged(
+            Primitive::SemSignal { sem: sem_id },
+            |pm| pm.sem_signal_unlogged(sem_id),
+            |result| describe(result, |woken| match woken {
+                Some(pid) => format!("唤醒 pid {}", pid),
+                None => "ok".to_string(),
+            }),
+        )
+    }
+
+    fn sem_signal_unlogged(&mut self, sem_id: u32) -> Result<Option<u32>, ProcessError> {
+        let sem = self.semaphores.get_mut(&sem_id)
+            .ok_or(ProcessError::SemaphoreNotFound(sem_id))?;
+        let previous_holder = sem.holder();
+
+        let woken = sem.signal();
+        match woken {
+            Some(pid) => {
+                self.wake(pid);
+                log(LogLevel::Info, format_args!("✓ 信号量 {} 执行V操作，唤醒进程 {}", sem_id, pid));
+            }
+            None => log(LogLevel::Info, format_args!("✓ 信号量 {} 执行V操作，当前值: {}", sem_id, sem.count())),
+        }
+
+        // 原持有者释放信号量后不再继承其等待者的优先级，新持有者可能继承剩余等待者的优先级
+        if let Some(holder) = previous_holder {
+            self.refresh_inheritance(holder);
+        }
+        if let Some(pid) = woken {
+            self.refresh_inheritance(pid);
+            self.preempt_if_outranked(pid);
+        }
+        Ok(woken)
+    }
+
+    /// 发送消息：将消息放入接收进程的信箱
+    ///
+    /// 接收进程正因信箱为空而阻塞时将其唤醒（被挂起的接收进程转为挂起就绪）
+    pub(crate) fn send_message(&mut self, from: u32, to: u32, payload: String) -> Result<(), ProcessError> {
+        self.logged(
+            Primitive::Send { from, to, payload: payload.clone() },
+            |pm| pm.send_message_unlogged(from, to, payload),
+            done,
+        )
+    }
+
+    fn send_message_unlogged(&mut self, from: u32, to: u32, payload: String) -> Result<(), ProcessError> {
+        if !self.total_chain.contains_key(&from) {
+            return Err(ProcessError::ProcessNotFound(from));
+        }
+        let receiver = self.total_chain.get(&to)
+            .ok_or(ProcessError::ProcessNotFound(to))?;
+        let wake_receiver = receiver.awaiting_message;
+
+        let message = Message { from, payload, sent_at: self.scheduler.current_time() };
+        self.mailboxes.entry(to).or_default().push_back(message);
+        log(LogLevel::Info, format_args!("✓ 进程 {} 向进程 {} 发送了一条消息", from, to));
+
+        if wake_receiver {
+            self.unblock(to);
+        }
+        Ok(())
+    }
+
+    /// 接收消息：取出信箱中最早到达的消息，信箱为空时返回None
+    pub(crate) fn receive_message(&mut self, pid: u32) -> Option<Message> {
+        self.logged(
+            Primitive::Receive { pid },
+            |pm| pm.receive_message_unlogged(pid),
+            |message| received(message.as_ref()),
+        )
+    }
+
+    fn receive_message_unlogged(&mut self, pid: u32) -> Option<Message> {
+        self.mailboxes.get_mut(&pid)?.pop_front()
+    }
+
+    /// 阻塞式接收：信箱非空时取出最早的消息；信箱为空时进程转入等待队列，
+    /// 直到有消息到达后被唤醒（唤醒后需再次接收才能取出消息）
+    ///
+    /// 只有就绪或运行中的进程可以阻塞等待消息
+    pub(crate) fn receive_or_block(&mut self, pid: u32) -> Result<Option<Message>, ProcessError> {
+        self.logged(
+            Primitive::ReceiveOrBlock { pid },
+            |pm| pm.receive_or_block_unlogged(pid),
+            |result| describe(result, |message| match message {
+                Some(message) => received(Some(message)),
+                None => "阻塞".to_string(),
+            }),
+        )
+    }
+
+    fn receive_or_block_unlogged(&mut self, pid: u32) -> Result<Option<Message>, ProcessError> {
+        if !self.total_chain.contains_key(&pid) {
+            return Err(ProcessError::ProcessNotFound(pid));
+        }
+        if let Some(message) = self.receive_message(pid) {
+            return Ok(Some(message));
+        }
+
+        let was_running = self.detach_for_blocking(pid, None)?;
+        if let Some(pcb) = self.total_chain.get_mut(&pid) {
+            pcb.awaiting_message = true;
+        }
+        self.waiting_queue.enqueue(pid);
+        log(LogLevel::Info, format_args!("✓ 进程 {} 的信箱为空，阻塞等待消息", pid));
+
+        if was_running {
+            self.release_cpu()?;
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pcb::ProcessState;
+
+    #[test]
+    fn semaphore_ceiling_boosts_holder_while_held() {
+        let mut pm = ProcessManager::new();
+        let first = pm.create_process(1, 10).unwrap();
+        let second = pm.create_process(2, 10).unwrap();
+        let sem = pm.sem_create(1);
+        pm.set_sem_ceiling(sem, Some(8)).unwrap();
+
+        pm.sem_wait(sem, first).unwrap();
+        assert_eq!(pm.total_chain[&first].effective_priority(), 8);
+        pm.sem_wait(sem, second).unwrap();
+        assert_eq!(pm.total_chain[&first].effective_priority(), 8);
+        assert_eq!(pm.total_chain[&second].effective_priority(), 2);
+
+        // 释放后原持有者恢复自身优先级，被唤醒的新持有者获得天花板
+        assert_eq!(pm.sem_signal(sem).unwrap(), Some(second));
+        assert_eq!(pm.total_chain[&first].effective_priority(), 1);
+        assert_eq!(pm.total_chain[&second].effective_priority(), 8);
+
+        pm.sem_signal(sem).unwrap();
+        assert_eq!(pm.total_chain[&second].effective_priority(), 2);
+    }
+
+    #[test]
+    fn semaphore_ceiling_must_be_in_priority_range() {
+        let mut pm = ProcessManager::new();
+        let sem = pm.sem_create(1);
+        let too_high = pm.priority_range.max + 1;
+        assert!(matches!(pm.set_sem_ceiling(sem, Some(too_high)), Err(ProcessError::InvalidPriority(..))));
+        assert!(matches!(pm.set_sem_ceiling(sem + 1, Some(1)), Err(ProcessError::SemaphoreNotFound(_))));
+    }
+
+    #[test]
+    fn blocked_on_lists_semaphore_waiters_in_wait_order() {
+        let mut pm = ProcessManager::new();
+        let holder = pm.create_process(1, 10).unwrap();
+        let first = pm.create_process(5, 10).unwrap();
+        let second = pm.create_process(3, 10).unwrap();
+        let sem = pm.sem_create(1);
+
+        pm.sem_wait(sem, holder).unwrap();
+        pm.sem_wait(sem, second).unwrap();
+        pm.sem_wait(sem, first).unwrap();
+        assert_eq!(pm.blocked_on(&format!("sem{}", sem)), vec![second, first]);
+        assert_eq!(pm.blocked_on(&sem.to_string()), vec![second, first]);
+
+        pm.sem_signal(sem).unwrap();
+        assert_eq!(pm.blocked_on(&format!("sem{}", sem)), vec![first]);
+        assert!(pm.blocked_on("sem99").is_empty());
+        assert!(pm.blocked_on("disk").is_empty());
+    }
+
+    #[test]
+    fn blocked_on_lists_event_waiters() {
+        let mut pm = ProcessManager::new();
+        let first = pm.create_process(1, 10).unwrap();
+        let second = pm.create_process(1, 10).unwrap();
+        pm.block_on(first, 7).unwrap();
+        pm.block_on(second, 8).unwrap();
+        assert_eq!(pm.blocked_on("event7"), vec![first]);
+        assert_eq!(pm.blocked_on("event8"), vec![second]);
+    }
+
+    #[test]
+    fn p_blocks_on_an_empty_semaphore_and_v_wakes_in_wait_order() {
+        let mut pm = ProcessManager::new();
+        let pids: Vec<u32> = (0..3).map(|_| pm.create_process(1, 10).unwrap()).collect();
+        let sem = pm.sem_create(0);
+
+        for &pid in &pids {
+            pm.sem_wait(sem, pid).unwrap();
+            assert_eq!(pm.total_chain[&pid].state, ProcessState::Waiting);
+            assert!(!pm.ready_queue.contains_pid(pid));
+        }
+        assert_eq!(pm.semaphores[&sem].count(), -3);
+        assert_eq!(pm.semaphores[&sem].waiters(), pids);
+
+        // V操作按阻塞的先后顺序逐个唤醒
+        for (i, &pid) in pids.iter().enumerate() {
+            assert_eq!(pm.sem_signal(sem).unwrap(), Some(pid));
+            assert!(pm.ready_queue.contains_pid(pid));
+            assert_eq!(pm.semaphores[&sem].count(), i as i32 - 2);
+        }
+        assert_eq!(pm.sem_signal(sem).unwrap(), None);
+        assert_eq!(pm.semaphores[&sem].count(), 1);
+        pm.sem_wait(sem, pids[0]).unwrap();
+        assert!(pm.ready_queue.contains_pid(pids[0]));
+        assert!(pm.semaphores[&sem].waiters().is_empty());
+    }
+
+    #[test]
+    fn circular_wait_on_two_semaphores_is_reported_as_deadlock() {
+        let mut pm = ProcessManager::new();
+        let first = pm.create_process(1, 10).unwrap();
+        let second = pm.create_process(1, 10).unwrap();
+        let bystander = pm.create_process(1, 10).unwrap();
+        let (a, b) = (pm.sem_create(1), pm.sem_create(1));
+
+        pm.sem_wait(a, first).unwrap();
+        pm.sem_wait(b, second).unwrap();
+        pm.sem_wait(b, first).unwrap();
+        assert!(pm.detect_deadlock().is_empty());
+
+        // 第二个进程再等待第一个进程持有的信号量，形成环；
+        // 等待环上进程但不在环上的进程不算死锁
+        pm.sem_wait(a, second).unwrap();
+        pm.sem_wait(a, bystander).unwrap();
+        assert_eq!(pm.detect_deadlock(), vec![first, second]);
+    }
+}
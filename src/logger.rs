@@ -0,0 +1,120 @@
+use std::cell::{Cell, RefCell};
+use std::fmt;
+
+/// 日志级别，越靠后越详细
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Error,  // 只输出错误
+    Info,   // 常规运行信息（默认）
+    Debug,  // 额外输出伙伴系统分裂/合并等详细跟踪
+}
+
+impl LogLevel {
+    pub fn name(&self) -> &str {
+        match self {
+            LogLevel::Error => "错误",
+            LogLevel::Info => "信息",
+            LogLevel::Debug => "调试",
+        }
+    }
+
+    /// 循环切换到下一个级别
+    pub fn next(&self) -> LogLevel {
+        match self {
+            LogLevel::Error => LogLevel::Info,
+            LogLevel::Info => LogLevel::Debug,
+            LogLevel::Debug => LogLevel::Error,
+        }
+    }
+}
+
+thread_local! {
+    /// 当前输出阈值：级别不超过阈值的消息才会输出
+    ///
+    /// 与输出捕获一样按线程保存，并行运行的测试临时调低级别时互不影响
+    static THRESHOLD: Cell<LogLevel> = const { Cell::new(LogLevel::Info) };
+}
+
+pub fn set_level(level: LogLevel) {
+    THRESHOLD.with(|threshold| threshold.set(level));
+}
+
+pub fn level() -> LogLevel {
+    THRESHOLD.with(Cell::get)
+}
+
+/// 指定级别的消息在当前阈值下是否输出
+pub fn enabled(level: LogLevel) -> bool {
+    level <= self::level()
+}
+
+thread_local! {
+    /// 输出捕获缓冲：为Some时消息追加到缓冲而不是写到标准输出，由菜单转写到自己的输出流
+    static CAPTURED: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// 开始捕获本线程的输出（已在捕获时保留已有内容）
+pub fn start_capture() {
+    CAPTURED.with(|captured| {
+        captured.borrow_mut().get_or_insert_with(String::new);
+    });
+}
+
+/// 取出目前捕获到的输出，之后继续捕获
+pub fn take_captured() -> String {
+    CAPTURED.with(|captured| captured.borrow_mut().as_mut().map(std::mem::take).unwrap_or_default())
+}
+
+/// 停止捕获，返回尚未取出的输出
+pub fn stop_capture() -> String {
+    CAPTURED.with(|captured| captured.borrow_mut().take().unwrap_or_default())
+}
+
+/// 输出一行不受日志级别限制的消息（脚本回显、查询结果等），捕获时写入缓冲
+pub fn output(message: fmt::Arguments) {
+    let line = format!("{}\n", message);
+    let uncaptured = CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+        Some(buffer) => {
+            buffer.push_str(&line);
+            None
+        }
+        None => Some(line),
+    });
+    if let Some(line) = uncaptured {
+        print!("{}", line);
+    }
+}
+
+/// 按级别输出一条消息，调试消息带有前缀以便与常规输出区分
+pub fn log(level: LogLevel, message: fmt::Arguments) {
+    if !enabled(level) {
+        return;
+    }
+    match level {
+        LogLevel::Debug => output(format_args!("[调试] {}", message)),
+        _ => output(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_messages_are_suppressed_at_info_level() {
+        set_level(LogLevel::Info);
+        start_capture();
+        log(LogLevel::Error, format_args!("错误消息"));
+        log(LogLevel::Info, format_args!("常规消息"));
+        log(LogLevel::Debug, format_args!("分裂跟踪"));
+        assert_eq!(take_captured(), "错误消息\n常规消息\n");
+
+        set_level(LogLevel::Debug);
+        log(LogLevel::Debug, format_args!("分裂跟踪"));
+        set_level(LogLevel::Error);
+        log(LogLevel::Info, format_args!("常规消息"));
+        output(format_args!("查询结果"));
+        set_level(LogLevel::Info);
+        assert_eq!(stop_capture(), "[调试] 分裂跟踪\n查询结果\n");
+    }
+}
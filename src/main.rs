@@ -1,42 +1,131 @@
 mod pcb;
 mod buddy_system;
+mod error;
 mod queue;
 mod scheduler;
 mod snapshot;
 mod menu;
+mod state;
+mod summary;
+mod ipc;
+mod cli;
+mod workload;
+mod logger;
+mod color;
+mod event_log;
+mod group;
+mod multicore;
+mod query;
+mod report;
+#[cfg(feature = "tui")]
+mod tui;
 
-use pcb::{ProcessState, PCB};
+use pcb::{memory_order, PriorityRange, ProcessState, DEFAULT_MEMORY_KB, NICE_MAX, NICE_MIN, PCB};
 use buddy_system::BuddySystem;
-use queue::ProcessQueue;
-use scheduler::Scheduler;
+use error::ProcessError;
+use ipc::{Message, Semaphore};
+use queue::{ProcessQueue, QueueSample};
+use scheduler::{cfs_vruntime_delta, mlfq_time_slice, Scheduler, SchedulingPolicy, SuspendPolicy, MLFQ_LEVELS};
 use snapshot::Snapshot;
-use std::collections::HashMap;
+use state::ManagerState;
+use logger::{log, LogLevel};
+use event_log::{describe, done, EventLog, Primitive};
+use workload::ProcessSpec;
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
 
 const MAX_PCB_COUNT: usize = 128; // PCB池的最大容量
+const AGING_THRESHOLD: u32 = 5;    // 就绪进程等待超过该周期数后有效优先级提升一级
+const UNDO_DEPTH: usize = 10;      // 最多可连续撤销的操作数
+const TOP_CONSUMERS: usize = 5;    // 调度器统计中显示的CPU占用排行条数
+const MAX_RUN_CYCLES: u32 = 10000; // 运行至空闲时默认的最大周期数（防止无法结束的负载死循环）
+const AUTO_SNAPSHOT_CAPACITY: usize = 10; // 默认保留的自动快照数
+const DASHBOARD_FRAME_MS: u64 = 500; // 实时监控相邻两帧之间的停顿（毫秒）
+const QUEUE_HISTORY_CAPACITY: usize = 1000; // 最多保留的队列长度采样数（超出时丢弃最早的）
+const STARVATION_THRESHOLD: u32 = 10; // 饥饿检测的默认阈值：就绪进程超过该周期数未运行即视为饥饿
+const LOAD_BALANCE_INTERVAL: u32 = 4; // 多核时每隔该周期数做一次负载均衡（有核空闲时每周期都做）
 
+/// 进程管理器
+///
+/// 实现了Clone：副本深拷贝PCB池（含空闲链表与已用计数）、总链、各队列、调度器和PID计数等全部状态，
+/// 之后对副本的任何操作都不影响原系统，可用于从当前状态分叉做对比实验
+#[derive(Clone)]
 struct ProcessManager {
     pcb_pool: BuddySystem,
-    total_chain: HashMap<u32, PCB>,  // PCB总链：用HashMap维护所有进程，key是PID
-    ready_queue: ProcessQueue,       // 就绪队列
+    total_chain: HashMap<u32, PCB>,  // PCB总链：用HashMap维护所有进程，key是PID（PCB数据的唯一来源）
+    ready_queue: ProcessQueue,       // 就绪队列（只保存PID，下同）
     waiting_queue: ProcessQueue,     // 等待队列
-    running_queue: ProcessQueue,     // 运行队列（通常只有一个）
+    cores: Vec<ProcessQueue>,        // 运行队列：每个CPU核一个，每个核上至多一个进程（长度即CPU核数）
+    terminated: Vec<PCB>,            // 已终止进程记录（PCB块已回收）
     scheduler: Scheduler,
     next_pid: u32,
+    recycle_pids: bool,              // 是否复用已撤销进程的PID（关闭时PID单调递增）
+    free_pids: Vec<u32>,             // 可复用的PID栈（最近撤销的在栈顶）
+    next_seq: u64,                   // 下一个入队序号（就绪队列同优先级的先来先到依据）
+    semaphores: HashMap<u32, Semaphore>, // 信号量表：key是信号量编号
+    mailboxes: HashMap<u32, VecDeque<Message>>, // 信箱：key是接收进程PID，消息按到达顺序排列
+    next_sem_id: u32,
+    fair_activate: bool,             // 激活时是否按累计等待时间在同优先级中排序
+    activation_boost: u32,           // 进程从等待转为就绪时获得的临时优先级提升（0表示不提升）
+    default_max_lifetime: Option<u32>, // 新建进程的最长存活周期数（None表示不限制）
+    suspend_policy: SuspendPolicy,   // 挂起完成后空闲CPU的处理策略
+    created_count: u32,              // 累计创建的进程数
+    terminated_count: u32,           // 累计撤销的进程数
+    undo_stack: VecDeque<ManagerState>, // 操作前的完整状态（最近的在队尾，最多UNDO_DEPTH个）
+    auto_snapshots: VecDeque<Snapshot>, // 每个原语执行前自动生成的快照（最近的在队尾）
+    auto_snapshot_capacity: usize,      // 最多保留的自动快照数
+    priority_range: PriorityRange,      // 创建进程和修改优先级时允许的优先级范围
+    use_color: bool,                    // 是否以ANSI颜色显示进程状态（CSV/JSON导出始终不着色）
+    queue_history: VecDeque<QueueSample>, // 每个调度周期结束时的队列长度（不属于保存状态）
+    pending_arrivals: Vec<ProcessSpec>,   // 尚未到达的定时进程，按到达时间排序（同时到达的按登记顺序）
+    cycles_since_balance: u32,            // 距上次负载均衡的调度周期数（不属于保存状态）
+    event_log: EventLog,                  // 原语调用的事件日志（只追加，不属于保存状态）
+    primitive_depth: u32,                 // 正在执行的原语嵌套层数（只记录最外层的调用）
 }
 
 impl ProcessManager {
     fn new() -> Self {
-        let pm = ProcessManager {
-            pcb_pool: BuddySystem::new(MAX_PCB_COUNT),
+        Self::with_pool_size(MAX_PCB_COUNT)
+    }
+
+    /// 使用指定大小的PCB池创建进程管理器（大小向上取整为2的幂）
+    fn with_pool_size(pool_size: usize) -> Self {
+        ProcessManager {
+            pcb_pool: BuddySystem::new(pool_size),
             total_chain: HashMap::new(),
             ready_queue: ProcessQueue::new(),
             waiting_queue: ProcessQueue::new(),
-            running_queue: ProcessQueue::new(),
+            cores: vec![ProcessQueue::new()],
+            terminated: Vec::new(),
             scheduler: Scheduler::new(),
             next_pid: 1,
-        };
-
-        pm
+            recycle_pids: false,
+            free_pids: Vec::new(),
+            next_seq: 0,
+            semaphores: HashMap::new(),
+            mailboxes: HashMap::new(),
+            next_sem_id: 1,
+            fair_activate: false,
+            activation_boost: 0,
+            default_max_lifetime: None,
+            suspend_policy: SuspendPolicy::KeepIdle,
+            created_count: 0,
+            terminated_count: 0,
+            undo_stack: VecDeque::new(),
+            auto_snapshots: VecDeque::new(),
+            auto_snapshot_capacity: AUTO_SNAPSHOT_CAPACITY,
+            priority_range: PriorityRange::default(),
+            use_color: color::default_enabled(),
+            queue_history: VecDeque::new(),
+            pending_arrivals: Vec::new(),
+            cycles_since_balance: 0,
+            event_log: EventLog::default(),
+            primitive_depth: 0,
+        }
     }
 
     /// 创建进程原语
@@ -45,300 +134,2507 @@ impl ProcessManager {
     /// 1. 从伙伴系统分配一个PCB块
     /// 2. 创建新的PCB并存储到池中
     /// 3. 加入总链（HashMap）
-    /// 4. 加入就绪队列
-    fn create_process(&mut self, priority: u32) -> Result<u32, String> {
-        // 从伙伴系统分配一个PCB块
-        let pool_index = self.pcb_pool.allocate()
-            .ok_or("PCB池已满，无法创建新进程")?;
+    /// 4. 状态由新建转为就绪，加入就绪队列
+    ///
+    /// burst_time为进程总共需要的CPU时间，执行完后进程自动撤销
+    fn create_process(&mut self, priority: u32, burst_time: u32) -> Result<u32, ProcessError> {
+        self.create_process_with_deadline(priority, burst_time, None)
+    }
 
-        let pid = self.next_pid;
-        self.next_pid += 1;
+    /// 创建带截止时间的（实时）进程，deadline为绝对系统时间，None表示非实时进程
+    ///
+    /// 截止时间在进入就绪队列前设置，因此最早截止时间优先调度下立即按截止时间排序
+    fn create_process_with_deadline(&mut self, priority: u32, burst_time: u32, deadline: Option<u32>) -> Result<u32, ProcessError> {
+        self.create_process_with_memory(priority, burst_time, deadline, DEFAULT_MEMORY_KB)
+    }
+
+    /// 创建指定内存需求的进程
+    ///
+    /// 从伙伴系统分配大小为2^memory_order(memory_kb)个槽位的块，
+    /// 没有足够大的空闲块时拒绝创建；优先级超出允许范围时返回InvalidPriority。
+    /// 其余参数同create_process_with_deadline
+    fn create_process_with_memory(&mut self, priority: u32, burst_time: u32, deadline: Option<u32>, memory_kb: usize) -> Result<u32, ProcessError> {
+        self.logged(
+            Primitive::Create { priority, burst: burst_time, deadline, memory_kb },
+            |pm| pm.create_process_with_memory_unlogged(priority, burst_time, deadline, memory_kb),
+            |result| describe(result, |pid| format!("pid {}", pid)),
+        )
+    }
+
+    fn create_process_with_memory_unlogged(
+        &mut self,
+        priority: u32,
+        burst_time: u32,
+        deadline: Option<u32>,
+        memory_kb: usize,
+    ) -> Result<u32, ProcessError> {
+        if !self.priority_range.contains(priority) {
+            return Err(ProcessError::InvalidPriority(priority, self.priority_range));
+        }
+
+        // 从伙伴系统分配所需大小的PCB块
+        let order = memory_order(memory_kb);
+        let pool_index = self.pcb_pool.allocate_order(order, self.scheduler.current_time())
+            .ok_or(if order == 0 { ProcessError::PcbPoolFull } else { ProcessError::OutOfMemory(memory_kb) })?;
 
+        let pid = self.allocate_pid();
+
+        // 新建状态：分配PCB并初始化
         let mut new_pcb = PCB::new(pool_index, pid);
         new_pcb.priority = priority;
-        new_pcb.state = ProcessState::Ready;
-        new_pcb.remaining_time = 5; // 默认时间片
+        new_pcb.burst_time = burst_time;
+        new_pcb.total_burst = burst_time;
+        new_pcb.arrival_time = self.scheduler.current_time();
+        new_pcb.created_at = self.scheduler.current_time();
+        new_pcb.remaining_time = self.time_slice_for(&new_pcb);
+        debug_assert!(new_pcb.remaining_time >= 1, "新进程的时间片必须至少为1");
+        new_pcb.tickets = priority.saturating_add(1);
+        new_pcb.deadline = deadline;
+        new_pcb.memory_kb = memory_kb;
+        new_pcb.max_lifetime = self.default_max_lifetime;
 
         // 存储到PCB池中
         self.pcb_pool.store_pcb(pool_index, new_pcb.clone());
 
+        // 初始化完成，新建 → 就绪
+        new_pcb.state = ProcessState::Ready;
+
         // 加入总链（HashMap）
-        self.total_chain.insert(pid, new_pcb.clone());
+        self.total_chain.insert(pid, new_pcb);
+        self.place_vruntime(pid);
 
-        // 加入就绪队列（按优先级插入）
-        self.ready_queue.enqueue_by_priority(new_pcb);
+        // 按当前调度策略加入就绪队列
+        self.admit_ready(pid);
+        self.created_count += 1;
 
-        println!("✓ 进程 {} 创建成功，优先级: {}, PCB索引: {}", pid, priority, pool_index);
+        log(LogLevel::Info, format_args!("✓ 进程 {} 创建成功，优先级: {}, 运行时间: {}, PCB索引: {}, 内存: {} KB",
+                                         pid, priority, burst_time, pool_index, memory_kb));
+        if let Some(deadline) = deadline {
+            log(LogLevel::Info, format_args!("  截止时间: {}", deadline));
+        }
+        self.preempt_if_outranked(pid);
         Ok(pid)
     }
 
+    /// 分配一个新的PID
+    ///
+    /// 开启PID复用时优先取可复用栈顶的PID（跳过仍在总链中的），否则使用next_pid并递增
+    fn allocate_pid(&mut self) -> u32 {
+        if self.recycle_pids {
+            while let Some(pid) = self.free_pids.pop() {
+                if !self.total_chain.contains_key(&pid) {
+                    return pid;
+                }
+            }
+        }
+        let pid = self.next_pid;
+        self.next_pid += 1;
+        pid
+    }
+
+    /// 开启或关闭状态着色，同步到全局的着色开关
+    fn set_use_color(&mut self, enabled: bool) {
+        self.use_color = enabled;
+        color::set_enabled(enabled);
+    }
+
+    /// 切换PID复用模式，关闭时清空可复用栈，之后的PID恢复单调递增
+    fn set_recycle_pids(&mut self, enabled: bool) {
+        self.recycle_pids = enabled;
+        if !enabled {
+            self.free_pids.clear();
+        }
+    }
+
+    /// 重置整个模拟：撤销所有进程，清空队列、总链、信号量和已终止记录，
+    /// 重新初始化同样大小的PCB池，重置调度器计数，PID从1重新开始
+    ///
+    /// 调度策略及各项配置保持不变；撤销栈也保留，重置后仍可撤销回重置前的状态。
+    /// 事件日志清空，从重置后的第一个原语重新记录
+    fn reset(&mut self) {
+        let alive = self.total_chain.len();
+        let pool_size = self.pcb_pool.get_pool_size();
+        let strategy = self.pcb_pool.strategy();
+
+        self.pcb_pool = BuddySystem::new(pool_size);
+        self.pcb_pool.set_strategy(strategy);
+        self.total_chain.clear();
+        self.ready_queue = ProcessQueue::new();
+        self.waiting_queue = ProcessQueue::new();
+        self.cores = vec![ProcessQueue::new(); self.cores.len()];
+        self.terminated.clear();
+        self.semaphores.clear();
+        self.mailboxes.clear();
+        self.scheduler.reset();
+        self.next_pid = 1;
+        self.free_pids.clear();
+        self.next_seq = 0;
+        self.next_sem_id = 1;
+        self.created_count = 0;
+        self.terminated_count = 0;
+        self.queue_history.clear();
+        self.pending_arrivals.clear();
+        self.event_log.clear();
+
+        log(LogLevel::Info, format_args!("✓ 模拟已重置，撤销了 {} 个进程", alive));
+    }
+
     /// 撤销进程原语
     ///
     /// 步骤：
     /// 1. 从总链中查找进程
     /// 2. 从所有队列中移除
-    /// 3. 回收PCB到伙伴系统
-    fn terminate_process(&mut self, pid: u32) -> Result<(), String> {
+    /// 3. 状态改为终止，记入已终止列表
+    /// 4. 回收PCB到伙伴系统
+    ///
+    /// 撤销父进程时采用级联撤销：先递归撤销所有子进程，再撤销父进程本身；
+    /// 被撤销的进程同时从其父进程的子进程列表中移除
+    ///
+    /// 返回所有被撤销的PID：第一个是pid本身，其后按先序（子进程按fork顺序）列出整棵子树
+    fn terminate_process(&mut self, pid: u32) -> Result<Vec<u32>, ProcessError> {
+        self.logged(
+            Primitive::Terminate { pid },
+            |pm| pm.terminate_process_unlogged(pid),
+            |result| describe(result, |pids| match pids.len() {
+                1 => "ok".to_string(),
+                _ => format!("ok，级联撤销 {:?}", &pids[1..]),
+            }),
+        )
+    }
+
+    fn terminate_process_unlogged(&mut self, pid: u32) -> Result<Vec<u32>, ProcessError> {
+        let children = self.total_chain.get(&pid)
+            .ok_or(ProcessError::ProcessNotFound(pid))?
+            .children.clone();
+        let mut ended = vec![pid];
+        for child in children {
+            if self.total_chain.contains_key(&child) {
+                ended.extend(self.terminate_process(child)?);
+            }
+        }
+
         // 从总链中查找并移除
-        let pcb = self.total_chain.remove(&pid)
-            .ok_or(format!("进程 {} 不存在", pid))?;
+        let mut pcb = self.total_chain.remove(&pid)
+            .ok_or(ProcessError::ProcessNotFound(pid))?;
+        if let Some(parent) = pcb.parent_pid.and_then(|parent| self.total_chain.get_mut(&parent)) {
+            parent.children.retain(|&c| c != pid);
+        }
 
         // 从各个队列中移除
         self.ready_queue.remove_by_pid(pid);
         self.waiting_queue.remove_by_pid(pid);
-        self.running_queue.remove_by_pid(pid);
+        self.remove_running(pid);
+        let mut affected_holders = Vec::new();
+        for sem in self.semaphores.values_mut() {
+            if sem.remove_waiter(pid) {
+                affected_holders.extend(sem.holder());
+            }
+        }
+        for holder in affected_holders {
+            self.refresh_inheritance(holder);
+        }
+        self.mailboxes.remove(&pid);
 
-        // 回收PCB到伙伴系统
+        // 标记为终止并保留记录
+        pcb.state = ProcessState::Terminated;
+        pcb.completion_time = self.scheduler.current_time();
         let pool_index = pcb.pool_index;
-        self.pcb_pool.deallocate(pool_index);
+        self.scheduler.record_completion(&pcb);
+        self.terminated.push(pcb);
 
-        println!("✓ 进程 {} 已撤销，PCB索引 {} 已回收", pid, pool_index);
-        Ok(())
+        // 回收PCB到伙伴系统
+        if !self.pcb_pool.deallocate(pool_index, self.scheduler.current_time()) {
+            log(LogLevel::Error, format_args!("⚠ PCB索引 {} 未处于已分配状态，忽略重复回收", pool_index));
+        }
+        self.terminated_count += 1;
+        if self.recycle_pids {
+            self.free_pids.push(pid);
+        }
+
+        log(LogLevel::Info, format_args!("✓ 进程 {} 已撤销，PCB索引 {} 已回收", pid, pool_index));
+        Ok(ended)
+    }
+
+    /// 批量撤销处于指定状态的所有进程，返回撤销的进程数（含级联撤销的子进程）
+    fn terminate_by_state(&mut self, state: ProcessState) -> usize {
+        self.terminate_matching(|pcb| pcb.state == state)
+    }
+
+    /// 批量撤销指定优先级的所有进程，返回撤销的进程数（含级联撤销的子进程）
+    fn terminate_by_priority(&mut self, priority: u32) -> usize {
+        self.terminate_matching(|pcb| pcb.priority == priority)
     }
 
-    /// 时间片到原语
+    /// 撤销总链中所有满足条件的进程
     ///
-    /// 步骤：
-    /// 1. 从运行队列取出进程
-    /// 2. 重置时间片，状态改为就绪
-    /// 3. 更新总链中的状态
-    /// 4. 加入就绪队列
-    /// 5. 记录进程切换
-    fn time_slice_expired(&mut self) -> Result<(), String> {
-        let mut running_pcb = self.running_queue.dequeue()
-            .ok_or("没有正在运行的进程")?;
-
-        running_pcb.state = ProcessState::Ready;
-        running_pcb.remaining_time = 5; // 重置时间片
+    /// 先收集PID再逐个撤销，避免在遍历总链时修改它；
+    /// 已被级联撤销的进程跳过
+    fn terminate_matching<F>(&mut self, matches: F) -> usize
+    where
+        F: Fn(&PCB) -> bool,
+    {
+        let before = self.terminated_count;
+        for pid in self.matching_pids(matches) {
+            if self.total_chain.contains_key(&pid) {
+                if let Err(e) = self.terminate_process(pid) {
+                    log(LogLevel::Error, format_args!("{}", e));
+                }
+            }
+        }
+        (self.terminated_count - before) as usize
+    }
 
-        // 更新总链中的状态
-        if let Some(pcb_in_chain) = self.total_chain.get_mut(&running_pcb.pid) {
-            pcb_in_chain.state = ProcessState::Ready;
-            pcb_in_chain.remaining_time = 5;
+    /// 总链中满足条件的进程PID，按PID升序
+    fn matching_pids<F>(&self, matches: F) -> Vec<u32>
+    where
+        F: Fn(&PCB) -> bool,
+    {
+        let mut pids: Vec<u32> = self.total_chain.values()
+            .filter(|pcb| matches(pcb))
+            .map(|pcb| pcb.pid)
+            .collect();
+        pids.sort_unstable();
+        pids
+    }
+
+    /// 预演撤销pid：返回terminate_process将会撤销的全部PID（顺序相同），不做任何修改
+    fn plan_terminate(&self, pid: u32) -> Result<Vec<u32>, ProcessError> {
+        let pcb = self.total_chain.get(&pid)
+            .ok_or(ProcessError::ProcessNotFound(pid))?;
+        let mut plan = vec![pid];
+        for &child in &pcb.children {
+            if self.total_chain.contains_key(&child) {
+                plan.extend(self.plan_terminate(child)?);
+            }
         }
+        Ok(plan)
+    }
 
-        // 按优先级加入就绪队列
-        self.ready_queue.enqueue_by_priority(running_pcb.clone());
+    /// 预演批量撤销：返回terminate_matching将会撤销的全部PID（含级联撤销的子进程），不做任何修改
+    fn plan_matching<F>(&self, matches: F) -> Vec<u32>
+    where
+        F: Fn(&PCB) -> bool,
+    {
+        let mut plan: Vec<u32> = Vec::new();
+        for pid in self.matching_pids(matches) {
+            if plan.contains(&pid) {
+                continue;
+            }
+            if let Ok(subtree) = self.plan_terminate(pid) {
+                let fresh: Vec<u32> = subtree.into_iter().filter(|p| !plan.contains(p)).collect();
+                plan.extend(fresh);
+            }
+        }
+        plan
+    }
 
-        // 记录进程切换
-        self.scheduler.record_switch();
+    /// 预演按状态批量撤销
+    fn plan_by_state(&self, state: ProcessState) -> Vec<u32> {
+        self.plan_matching(|pcb| pcb.state == state)
+    }
 
-        println!("✓ 进程 {} 时间片到，转为就绪状态", running_pcb.pid);
-        Ok(())
+    /// 预演按优先级批量撤销
+    fn plan_by_priority(&self, priority: u32) -> Vec<u32> {
+        self.plan_matching(|pcb| pcb.priority == priority)
     }
 
-    /// 挂起进程原语
+    /// fork：以parent_pid为父进程创建子进程
     ///
-    /// 步骤：
-    /// 1. 从总链中查找进程
-    /// 2. 从就绪或运行队列中移除
-    /// 3. 状态改为等待
-    /// 4. 加入等待队列
-    fn suspend_process(&mut self, pid: u32) -> Result<(), String> {
-        // 从总链中查找
-        let pcb = self.total_chain.get_mut(&pid)
-            .ok_or(format!("进程 {} 不存在", pid))?;
+    /// 子进程继承父进程的优先级、剩余运行时间和所属进程组，父子关系同时记录在双方的PCB中
+    fn fork(&mut self, parent_pid: u32) -> Result<u32, ProcessError> {
+        self.logged(
+            Primitive::Fork { parent: parent_pid },
+            |pm| pm.fork_unlogged(parent_pid),
+            |result| describe(result, |pid| format!("pid {}", pid)),
+        )
+    }
 
-        // 从就绪或运行队列中移除
-        let removed_from_ready = self.ready_queue.remove_by_pid(pid).is_some();
-        let removed_from_running = self.running_queue.remove_by_pid(pid).is_some();
+    fn fork_unlogged(&mut self, parent_pid: u32) -> Result<u32, ProcessError> {
+        let parent = self.total_chain.get(&parent_pid)
+            .ok_or(ProcessError::ProcessNotFound(parent_pid))?;
+        let (priority, burst_time, memory_kb) = (parent.priority, parent.burst_time.max(1), parent.memory_kb);
+        let group_id = parent.group_id;
 
-        if !removed_from_ready && !removed_from_running {
-            return Err(format!("进程 {} 不在就绪或运行队列中", pid));
+        let child_pid = self.create_process_with_memory(priority, burst_time, None, memory_kb)?;
+        if let Some(child) = self.total_chain.get_mut(&child_pid) {
+            child.parent_pid = Some(parent_pid);
+            child.group_id = group_id;
+        }
+        if let Some(parent) = self.total_chain.get_mut(&parent_pid) {
+            parent.children.push(child_pid);
         }
 
-        pcb.state = ProcessState::Waiting;
+        log(LogLevel::Info, format_args!("✓ 进程 {} fork出子进程 {}", parent_pid, child_pid));
+        Ok(child_pid)
+    }
 
-        // 加入等待队列
-        self.waiting_queue.enqueue(pcb.clone());
+    /// 时间片到原语：作用于编号最小的忙碌CPU核上的进程（单核时即当前运行进程）
+    fn time_slice_expired(&mut self) -> Result<(), ProcessError> {
+        self.logged(
+            Primitive::TimeSliceExpired,
+            |pm| pm.time_slice_expired_unlogged(),
+            done,
+        )
+    }
 
-        println!("✓ 进程 {} 已挂起", pid);
-        Ok(())
+    fn time_slice_expired_unlogged(&mut self) -> Result<(), ProcessError> {
+        let pid = self.current_running()
+            .ok_or(ProcessError::NoRunningProcess)?;
+        self.expire_time_slice(pid)
     }
 
-    /// 激活进程原语
+    /// 运行进程pid的时间片到
     ///
     /// 步骤：
-    /// 1. 从等待队列中移除
-    /// 2. 状态改为就绪
-    /// 3. 更新总链中的状态
-    /// 4. 加入就绪队列
-    fn activate_process(&mut self, pid: u32) -> Result<(), String> {
-        let mut pcb = self.waiting_queue.remove_by_pid(pid)
-            .ok_or(format!("进程 {} 不在等待队列中", pid))?;
+    /// 1. 从所在CPU核的运行队列取出进程
+    /// 2. 重置时间片，状态改为就绪（直接修改总链中的PCB）
+    /// 3. 加入就绪队列
+    /// 4. 记录进程切换
+    fn expire_time_slice(&mut self, pid: u32) -> Result<(), ProcessError> {
+        let demote = self.scheduler.policy() == SchedulingPolicy::Mlfq;
+        let running_pcb = self.total_chain.get_mut(&pid)
+            .ok_or(ProcessError::RunningQueueCorrupted)?;
+        if running_pcb.non_preemptible {
+            return Err(ProcessError::NonPreemptible(pid));
+        }
 
-        pcb.state = ProcessState::Ready;
+        // 激活提升随每个用完的时间片衰减，避免长期扭曲优先级
+        running_pcb.boost = running_pcb.boost.saturating_sub(1);
 
-        // 更新总链中的状态
-        if let Some(pcb_in_chain) = self.total_chain.get_mut(&pid) {
-            pcb_in_chain.state = ProcessState::Ready;
+        // 多级反馈队列：用完时间片降一级（最低级保持不变）
+        if demote && running_pcb.mlfq_level + 1 < MLFQ_LEVELS {
+            running_pcb.mlfq_level += 1;
+            log(LogLevel::Info, format_args!("进程 {} 用完时间片，降至第 {} 级", pid, running_pcb.mlfq_level));
         }
 
-        // 按优先级加入就绪队列
-        self.ready_queue.enqueue_by_priority(pcb);
-        println!("✓ 进程 {} 已激活，转为就绪状态", pid);
+        // 用完一个时间片即进入时间片变化策略的下一轮
+        self.scheduler.advance_quantum_round();
+        self.requeue_running(pid);
+        log(LogLevel::Info, format_args!("✓ 进程 {} 时间片到，转为就绪状态", pid));
         Ok(())
     }
 
-    /// 调度进程（扩展二：进程调度程序）
+    /// 将运行进程放回就绪队列：重置时间片，状态改为就绪，并记录一次进程切换
+    fn requeue_running(&mut self, pid: u32) {
+        self.remove_running(pid);
+
+        let time_slice = self.total_chain.get(&pid).map(|pcb| self.time_slice_for(pcb));
+        if let Some(pcb) = self.total_chain.get_mut(&pid) {
+            pcb.state = ProcessState::Ready;
+            pcb.remaining_time = time_slice.unwrap_or(pcb.remaining_time); // 重置时间片
+        }
+
+        // 按当前调度策略加入就绪队列
+        self.admit_ready(pid);
+
+        // 记录进程切换
+        self.scheduler.record_switch();
+    }
+
+    /// 抢占式优先级调度：新就绪的进程优先级高于运行进程时，立即将运行进程换下
     ///
-    /// 使用优先级调度策略：
-    /// 1. 从就绪队列选择优先级最高的进程（队列已按优先级排序）
-    /// 2. 将其状态改为运行
-    /// 3. 加入运行队列
-    /// 4. 记录进程切换
-    fn schedule(&mut self) -> Result<(), String> {
-        // 如果运行队列为空，从就绪队列中选择一个进程运行
-        if self.running_queue.is_empty() {
-            let mut pcb = self.ready_queue.dequeue()
-                .ok_or("就绪队列为空，无法调度")?;
+    /// 只在开启抢占且采用优先级调度时生效；临界区内的运行进程不可抢占。
+    /// 还有空闲的CPU核时新进程下一周期即可运行，不抢占；
+    /// 否则与有效优先级最低的运行进程比较（同为最低时取核编号小的）。
+    /// 被换下的进程回到就绪队列，下一次调度时选中优先级最高的新进程
+    fn preempt_if_outranked(&mut self, pid: u32) {
+        if !self.scheduler.preemptive() || self.scheduler.policy() != SchedulingPolicy::Priority {
+            return;
+        }
+        if self.idle_core().is_some() {
+            return;
+        }
+        let Some(running) = self.running_pids().into_iter()
+            .min_by_key(|running| self.total_chain.get(running).map(|pcb| pcb.effective_priority()))
+        else {
+            return;
+        };
+        let (Some(newcomer), Some(current)) = (self.total_chain.get(&pid), self.total_chain.get(&running)) else {
+            return;
+        };
+        if newcomer.effective_priority() <= current.effective_priority() {
+            return;
+        }
+        if current.non_preemptible {
+            log(LogLevel::Info, format_args!("进程 {} 处于临界区，暂不被进程 {} 抢占", running, pid));
+            return;
+        }
 
-            pcb.state = ProcessState::Running;
+        self.requeue_running(running);
+        log(LogLevel::Info, format_args!("✓ 进程 {} 被更高优先级的进程 {} 抢占，转为就绪状态", running, pid));
+    }
 
-            // 更新总链中的状态
-            if let Some(pcb_in_chain) = self.total_chain.get_mut(&pcb.pid) {
-                pcb_in_chain.state = ProcessState::Running;
-            }
+    /// 进程新获得的时间片长度
+    ///
+    /// 多级反馈队列下由进程所在级别决定，其他策略按优先级查调度器的时间片表
+    ///
+    /// 结果至少为1：从旧状态文件读入的时间片表可能含0，不能让进程一上CPU就被换下
+    fn time_slice_for(&self, pcb: &PCB) -> u32 {
+        let slice = match self.scheduler.policy() {
+            SchedulingPolicy::Mlfq => mlfq_time_slice(pcb.mlfq_level),
+            _ => self.scheduler.time_slice_for(pcb.priority),
+        };
+        slice.max(1)
+    }
 
-            self.running_queue.enqueue(pcb.clone());
+    /// 挂起进程原语
+    ///
+    /// 按目标进程当前所处的状态处理：
+    ///
+    /// | 目标状态 | 处理方式 |
+    /// |----------|----------|
+    /// | 就绪 | 移出就绪队列，转入等待队列，状态置为挂起就绪 |
+    /// | 运行 | 移出运行队列，转入等待队列，状态置为挂起就绪，记录一次切换 |
+    /// | 等待（等待队列中） | 留在等待队列，状态置为挂起阻塞，所等待的事件或I/O保持不变 |
+    /// | 等待（信号量上） | 返回错误，不做任何改动 |
+    /// | 挂起就绪/挂起阻塞 | 已被挂起，返回错误，不做任何改动 |
+    ///
+    /// 成功挂起后按挂起策略处理CPU（见SuspendPolicy的策略表）
+    fn suspend_process(&mut self, pid: u32) -> Result<(), ProcessError> {
+        self.logged(
+            Primitive::Suspend { pid },
+            |pm| pm.suspend_process_unlogged(pid),
+            done,
+        )
+    }
 
-            // 记录进程切换（从就绪到运行）
-            self.scheduler.record_switch();
+    fn suspend_process_unlogged(&mut self, pid: u32) -> Result<(), ProcessError> {
+        let state = self.total_chain.get(&pid)
+            .ok_or(ProcessError::ProcessNotFound(pid))?
+            .state
+            .clone();
 
-            // 使用调度器执行
-            self.scheduler.execute_process(&pcb);
-            Ok(())
-        } else {
-            // 如果已有运行进程，继续执行
-            if let Some(pcb) = self.running_queue.front() {
-                self.scheduler.execute_process(pcb);
-                Ok(())
-            } else {
-                Err("运行队列异常".to_string())
+        match state {
+            ProcessState::Waiting if self.waiting_queue.contains_pid(pid) => {
+                if let Some(pcb) = self.total_chain.get_mut(&pid) {
+                    pcb.state = ProcessState::SuspendedBlocked;
+                }
+                log(LogLevel::Info, format_args!("✓ 进程 {} 在阻塞中被挂起", pid));
             }
+            ProcessState::Waiting => return Err(ProcessError::CannotSuspend(pid, state)),
+            _ => self.move_to_waiting(pid, None)?,
         }
+        self.apply_suspend_policy();
+        Ok(())
     }
 
-    /// 执行一次调度周期
+    /// 阻塞原语（wait）：进程因等待事件event_id而阻塞
     ///
-    /// 步骤：
-    /// 1. 如果没有运行进程，从就绪队列调度一个
-    /// 2. 执行当前运行进程
-    /// 3. 减少时间片
-    /// 4. 如果时间片用完，执行时间片到原语
-    fn run_one_cycle(&mut self) {
-        println!("\n=== 执行一次调度周期 ===");
+    /// 与挂起相同地转入等待队列，并记录所等待的事件，
+    /// 之后由wakeup_event统一唤醒
+    fn block_on(&mut self, pid: u32, event_id: u32) -> Result<(), ProcessError> {
+        self.logged(
+            Primitive::BlockOn { pid, event: event_id },
+            |pm| pm.block_on_unlogged(pid, event_id),
+            done,
+        )
+    }
 
-        // 如果运行队列为空，尝试调度
-        if self.running_queue.is_empty() {
-            if let Err(e) = self.schedule() {
-                println!("{}", e);
-                return;
-            }
+    fn block_on_unlogged(&mut self, pid: u32, event_id: u32) -> Result<(), ProcessError> {
+        self.move_to_waiting(pid, Some(event_id))
+    }
+
+    /// 阻塞原语（block）：由系统发起，当前运行进程主动放弃CPU转入等待
+    ///
+    /// 与用户发起的挂起不同，只能作用于正在运行的进程，且不关联任何事件，
+    /// 只能由wakeup按PID唤醒；pid不是当前运行进程时返回NotRunning
+    fn block(&mut self, pid: u32) -> Result<(), ProcessError> {
+        self.logged(
+            Primitive::Block { pid },
+            |pm| pm.block_unlogged(pid),
+            done,
+        )
+    }
+
+    fn block_unlogged(&mut self, pid: u32) -> Result<(), ProcessError> {
+        if !self.total_chain.contains_key(&pid) {
+            return Err(ProcessError::ProcessNotFound(pid));
+        }
+        if !self.is_running(pid) {
+            return Err(ProcessError::NotRunning(pid));
         }
 
-        // 执行当前运行进程
-        if let Some(pcb) = self.running_queue.front() {
-            println!("正在执行进程 {} (优先级: {}, 剩余时间片: {})",
-                     pcb.pid, pcb.priority, pcb.remaining_time);
+        self.detach_for_blocking(pid, None)?;
+        self.waiting_queue.enqueue(pid);
+        log(LogLevel::Info, format_args!("✓ 进程 {} 主动阻塞，转入等待队列", pid));
 
-            let pid = pcb.pid;
+        self.release_cpu()
+    }
 
-            // 减少剩余时间片
-            if let Some(pcb_mut) = self.running_queue.front_mut() {
-                pcb_mut.remaining_time -= 1;
+    /// 唤醒原语（wakeup）：由系统发起，将指定的阻塞进程转为就绪
+    ///
+    /// 与激活不同，只作用于阻塞（等待）中的进程：挂起阻塞的进程转为挂起就绪，
+    /// 仍需激活才能运行；已挂起就绪的进程返回AlreadySuspended
+    fn wakeup(&mut self, pid: u32) -> Result<(), ProcessError> {
+        self.logged(
+            Primitive::Wakeup { pid },
+            |pm| pm.wakeup_unlogged(pid),
+            done,
+        )
+    }
 
-                // 同步更新总链中的时间片
-                if let Some(pcb_in_chain) = self.total_chain.get_mut(&pid) {
-                    pcb_in_chain.remaining_time = pcb_mut.remaining_time;
-                }
+    fn wakeup_unlogged(&mut self, pid: u32) -> Result<(), ProcessError> {
+        let state = self.total_chain.get(&pid)
+            .ok_or(ProcessError::ProcessNotFound(pid))?
+            .state
+            .clone();
+        if !self.waiting_queue.contains_pid(pid) {
+            return Err(ProcessError::NotInWaiting(pid));
+        }
+        match state {
+            ProcessState::Waiting | ProcessState::SuspendedBlocked => {
+                self.unblock(pid);
+                Ok(())
             }
+            _ => Err(ProcessError::AlreadySuspended(pid)),
+        }
+    }
 
-            // 如果时间片用完，执行时间片到原语
-            if let Some(pcb) = self.running_queue.front() {
-                if pcb.remaining_time <= 0 {
-                    if let Err(e) = self.time_slice_expired() {
-                        println!("{}", e);
+    /// 设置进程的I/O模式：每运行interval个时间单位发起一次I/O，阻塞burst个周期
+    ///
+    /// interval或burst为0表示该进程不做I/O
+    fn set_io_profile(&mut self, pid: u32, interval: u32, burst: u32) -> Result<(), ProcessError> {
+        self.logged(
+            Primitive::SetIoProfile { pid, interval, burst },
+            |pm| pm.set_io_profile_unlogged(pid, interval, burst),
+            done,
+        )
+    }
+
+    fn set_io_profile_unlogged(&mut self, pid: u32, interval: u32, burst: u32) -> Result<(), ProcessError> {
+        let pcb = self.total_chain.get_mut(&pid)
+            .ok_or(ProcessError::ProcessNotFound(pid))?;
+        pcb.io_interval = interval;
+        pcb.io_burst = burst;
+
+        if interval == 0 || burst == 0 {
+            log(LogLevel::Info, format_args!("✓ 进程 {} 不再发起I/O", pid));
+        } else {
+            log(LogLevel::Info, format_args!("✓ 进程 {} 每运行 {} 个时间单位发起一次I/O，每次阻塞 {} 个周期", pid, interval, burst));
+        }
+        Ok(())
+    }
+
+    /// 运行进程到达I/O点：主动阻塞，转入等待队列等待I/O完成
+    fn start_io(&mut self, pid: u32) -> Result<(), ProcessError> {
+        let io_burst = self.total_chain.get(&pid)
+            .ok_or(ProcessError::ProcessNotFound(pid))?
+            .io_burst;
+        self.detach_for_blocking(pid, None)?;
+        if let Some(pcb) = self.total_chain.get_mut(&pid) {
+            pcb.remaining_io = io_burst;
+        }
+        self.waiting_queue.enqueue(pid);
+        log(LogLevel::Info, format_args!("✓ 进程 {} 发起I/O，阻塞 {} 个周期", pid, io_burst));
+
+        self.release_cpu()
+    }
+
+    /// 推进I/O：等待队列中在之前周期发起I/O的进程剩余I/O周期数减一，减到0时自动激活
+    ///
+    /// 本周期刚发起I/O的进程（suspended_at等于当前时间）不计数，
+    /// 因此io_burst为n的进程恰好错过n个调度周期
+    fn advance_io(&mut self) {
+        let now = self.scheduler.current_time();
+        let mut completed = Vec::new();
+        for pid in self.waiting_queue.iter() {
+            if let Some(pcb) = self.total_chain.get_mut(&pid) {
+                if pcb.remaining_io > 0 && pcb.suspended_at < now {
+                    pcb.remaining_io -= 1;
+                    if pcb.remaining_io == 0 {
+                        completed.push(pid);
                     }
                 }
             }
         }
+
+        for pid in completed {
+            log(LogLevel::Info, format_args!("进程 {} 的I/O完成", pid));
+            self.unblock(pid);
+        }
     }
 
-    /// 获取快照
-    fn get_snapshot(&self, name: &str) -> Snapshot {
-        // 将HashMap转换为Vec用于快照
-        let total_chain_vec: Vec<PCB> = self.total_chain.values().cloned().collect();
+    /// 进程等待的事件或I/O已完成
+    ///
+    /// 挂起阻塞的进程转为挂起就绪，留在等待队列中直到被激活；其余进程直接激活
+    fn unblock(&mut self, pid: u32) {
+        if let Some(pcb) = self.total_chain.get_mut(&pid) {
+            if pcb.state == ProcessState::SuspendedBlocked {
+                pcb.state = ProcessState::SuspendedReady;
+                pcb.blocked_event = None;
+                pcb.remaining_io = 0;
+                pcb.awaiting_message = false;
+                log(LogLevel::Info, format_args!("✓ 进程 {} 等待的事件已发生，转为挂起就绪状态", pid));
+                return;
+            }
+        }
+        if let Err(e) = self.activate_process(pid) {
+            log(LogLevel::Error, format_args!("{}", e));
+        }
+    }
 
-        Snapshot::new(
-            name,
-            self.pcb_pool.get_free_count(),
-            self.pcb_pool.get_used_count(),
-            total_chain_vec,
-            self.ready_queue.clone(),
-            self.waiting_queue.clone(),
-            self.running_queue.clone(),
+    /// 唤醒原语（signal）：唤醒所有阻塞在event_id上的进程
+    ///
+    /// 按等待队列中的顺序依次激活，返回被唤醒的进程PID
+    fn wakeup_event(&mut self, event_id: u32) -> Vec<u32> {
+        self.logged(
+            Primitive::WakeupEvent { event: event_id },
+            |pm| pm.wakeup_event_unlogged(event_id),
+            |pids| format!("唤醒 {:?}", pids),
         )
     }
 
-    /// 打印当前状态
-    fn print_status(&self) {
-        println!("\n========== 系统状态 ==========");
-        println!("PCB池: 空闲 {} / 已用 {} / 总计 {}",
-                 self.pcb_pool.get_free_count(),
-                 self.pcb_pool.get_used_count(),
-                 self.pcb_pool.get_pool_size());
-        println!("总链: {} 个进程", self.total_chain.len());
-        println!("就绪队列: {} 个进程", self.ready_queue.len());
-        println!("等待队列: {} 个进程", self.waiting_queue.len());
-        println!("运行队列: {} 个进程", self.running_queue.len());
+    fn wakeup_event_unlogged(&mut self, event_id: u32) -> Vec<u32> {
+        let pids: Vec<u32> = self.waiting_queue.iter()
+            .filter(|pid| self.total_chain.get(pid).is_some_and(|pcb| pcb.blocked_event == Some(event_id)))
+            .collect();
+
+        for &pid in &pids {
+            self.unblock(pid);
+        }
+        pids
+    }
+
+    /// 将就绪或运行中的进程转入等待队列（挂起与阻塞的公共部分）
+    ///
+    /// blocked_event为None表示手动挂起，否则表示阻塞在该事件上
+    fn move_to_waiting(&mut self, pid: u32, blocked_event: Option<u32>) -> Result<(), ProcessError> {
+        let was_running = self.detach_for_blocking(pid, blocked_event)?;
+        if blocked_event.is_none() {
+            if let Some(pcb) = self.total_chain.get_mut(&pid) {
+                pcb.state = ProcessState::SuspendedReady;
+            }
+        }
+
+        // 加入等待队列
+        self.waiting_queue.enqueue(pid);
+        match blocked_event {
+            Some(event_id) => log(LogLevel::Info, format_args!("✓ 进程 {} 阻塞，等待事件 {}", pid, event_id)),
+            None => log(LogLevel::Info, format_args!("✓ 进程 {} 已挂起", pid)),
+        }
+
+        if was_running {
+            self.release_cpu()?;
+        }
+        Ok(())
+    }
+
+    /// 检查进程能否转入等待状态，返回其当前是否正在运行
+    fn check_blockable(&self, pid: u32) -> Result<bool, ProcessError> {
+        let pcb = self.total_chain.get(&pid)
+            .ok_or(ProcessError::ProcessNotFound(pid))?;
 
-        if !self.running_queue.is_empty() {
-            if let Some(pcb) = self.running_queue.front() {
-                println!("当前运行进程: PID={}, 优先级={}, 剩余时间片={}",
-                         pcb.pid, pcb.priority, pcb.remaining_time);
+        match pcb.state {
+            ProcessState::Waiting => Err(ProcessError::AlreadyWaiting(pid)),
+            ProcessState::SuspendedReady | ProcessState::SuspendedBlocked => {
+                Err(ProcessError::AlreadySuspended(pid))
+            }
+            ProcessState::Running => Ok(true),
+            ProcessState::Ready => Ok(false),
+            ProcessState::New | ProcessState::Terminated => {
+                Err(ProcessError::CannotSuspend(pid, pcb.state.clone()))
             }
         }
+    }
+
+    /// 将就绪或运行中的进程移出所在队列，并把总链中的状态置为等待
+    ///
+    /// 返回进程原先是否正在运行；由调用方将其放入相应的等待队列
+    fn detach_for_blocking(&mut self, pid: u32, blocked_event: Option<u32>) -> Result<bool, ProcessError> {
+        let was_running = self.check_blockable(pid)?;
 
-        println!("\n--- 总链详情（所有进程） ---");
-        if self.total_chain.is_empty() {
-            println!("  (空)");
+        // 从就绪或运行队列中移除
+        let removed = if was_running {
+            self.remove_running(pid)
         } else {
-            let mut processes: Vec<_> = self.total_chain.values().collect();
-            processes.sort_by_key(|p| p.pid);
-            for (i, pcb) in processes.iter().enumerate() {
-                println!("  [{}] {}", i + 1, pcb.display());
+            self.ready_queue.remove_by_pid(pid)
+        };
+        if !removed {
+            return Err(ProcessError::NotInReadyOrRunning(pid));
+        }
+
+        let pcb = self.total_chain.get_mut(&pid)
+            .ok_or(ProcessError::ProcessNotFound(pid))?;
+        pcb.state = ProcessState::Waiting;
+        pcb.suspended_at = self.scheduler.current_time();
+        pcb.blocked_event = blocked_event;
+        Ok(was_running)
+    }
+
+    /// 运行进程让出CPU：记录一次切换，并按挂起策略决定是否立即调度下一个就绪进程
+    fn release_cpu(&mut self) -> Result<(), ProcessError> {
+        self.scheduler.record_switch();
+        self.apply_suspend_policy();
+        Ok(())
+    }
+
+    /// Reschedule策略下为空闲的CPU核调度就绪进程，KeepIdle策略下不做任何事
+    fn apply_suspend_policy(&mut self) {
+        if self.suspend_policy == SuspendPolicy::Reschedule {
+            for next in self.fill_idle_cores() {
+                log(LogLevel::Info, format_args!("✓ 已立即调度进程 {} 上CPU", next));
+            }
+        }
+    }
+
+    /// 激活进程原语
+    ///
+    /// 挂起阻塞的进程只恢复为等待状态，仍留在等待队列中等待原来的事件或I/O。
+    /// 其余等待队列中的进程（挂起就绪，或被直接激活的阻塞进程）：
+    /// 1. 从等待队列中移除
+    /// 2. 总链中的状态改为就绪，累计本次等待时间
+    /// 3. 加入就绪队列（开启公平激活时，同优先级中等待更久的排在前面）
+    fn activate_process(&mut self, pid: u32) -> Result<(), ProcessError> {
+        self.logged(
+            Primitive::Activate { pid },
+            |pm| pm.activate_process_unlogged(pid),
+            done,
+        )
+    }
+
+    fn activate_process_unlogged(&mut self, pid: u32) -> Result<(), ProcessError> {
+        if !self.waiting_queue.contains_pid(pid) {
+            return Err(ProcessError::NotInWaiting(pid));
+        }
+        if let Some(pcb) = self.total_chain.get_mut(&pid) {
+            if pcb.state == ProcessState::SuspendedBlocked {
+                pcb.state = ProcessState::Waiting;
+                log(LogLevel::Info, format_args!("✓ 进程 {} 已激活，继续阻塞等待", pid));
+                return Ok(());
             }
         }
 
-        println!("\n--- 就绪队列详情（按优先级排序） ---");
-        self.ready_queue.print_all();
-        println!("\n--- 等待队列详情 ---");
-        self.waiting_queue.print_all();
-        println!("\n--- 运行队列详情 ---");
-        self.running_queue.print_all();
-        println!("==============================\n");
+        if !self.waiting_queue.remove_by_pid(pid) {
+            return Err(ProcessError::NotInWaiting(pid));
+        }
+
+        self.wake(pid);
+        log(LogLevel::Info, format_args!("✓ 进程 {} 已激活，转为就绪状态", pid));
+        self.preempt_if_outranked(pid);
+        Ok(())
     }
 
-    /// 显示调度器统计信息
-    fn show_scheduler_stats(&self) {
-        let stats = self.scheduler.get_stats();
-        stats.display();
+    /// 将已移出等待队列（或信号量队列）的进程转为就绪并加入就绪队列
+    ///
+    /// 多级反馈队列下，时间片未用完就阻塞的进程留在原级别，并重新获得完整时间片。
+    /// 设置了激活提升时，进程获得该提升（覆盖尚未衰减完的旧提升），以偏向I/O密集型进程
+    fn wake(&mut self, pid: u32) {
+        let Some(pcb) = self.total_chain.get(&pid) else {
+            return;
+        };
+        let time_slice = self.time_slice_for(pcb);
+        let refresh_slice = self.scheduler.policy() == SchedulingPolicy::Mlfq;
+        let now = self.scheduler.current_time();
+
+        if let Some(pcb) = self.total_chain.get_mut(&pid) {
+            if refresh_slice {
+                pcb.remaining_time = time_slice;
+            }
+            pcb.state = ProcessState::Ready;
+            pcb.waited_time += now.saturating_sub(pcb.suspended_at);
+            pcb.blocked_event = None;
+            pcb.remaining_io = 0;
+            pcb.awaiting_message = false;
+            if self.activation_boost > 0 {
+                pcb.boost = self.activation_boost;
+            }
+        }
+        self.place_vruntime(pid);
+
+        if self.fair_activate && self.scheduler.policy() == SchedulingPolicy::Priority {
+            self.stamp_seq(pid);
+            self.ready_queue.enqueue_ordered(pid, &self.total_chain, |new, old| {
+                let (new_priority, old_priority) = (new.effective_priority(), old.effective_priority());
+                new_priority > old_priority ||
+                    (new_priority == old_priority && new.waited_time > old.waited_time)
+            });
+        } else {
+            // 按当前调度策略加入就绪队列
+            self.admit_ready(pid);
+        }
     }
-}
 
-fn main() {
-    println!("╔══════════════════════════════════════════════════════╗");
-    println!("║    进程PCB组织、管理与调度模拟系统                    ║");
-    println!("║    操作系统实验2 - 完整实现版                        ║");
-    println!("╚══════════════════════════════════════════════════════╝\n");
+    /// 修改进程优先级
+    ///
+    /// 修改总链中的优先级；进程在就绪队列中且采用优先级调度时，
+    /// 重新排序就绪队列以保持有序。新优先级必须在允许范围内
+    fn change_priority(&mut self, pid: u32, new_priority: u32) -> Result<(), ProcessError> {
+        self.logged(
+            Primitive::ChangePriority { pid, priority: new_priority },
+            |pm| pm.change_priority_unlogged(pid, new_priority),
+            done,
+        )
+    }
 
-    let mut pm = ProcessManager::new();
-    
-    // 初始化快照
-    let init_snapshot = pm.get_snapshot("初始化快照");
-    println!("{}", init_snapshot);
-    
-    // 启动菜单系统
-    menu::run_menu(&mut pm);
-}
\ No newline at end of file
+    fn change_priority_unlogged(&mut self, pid: u32, new_priority: u32) -> Result<(), ProcessError> {
+        if !self.priority_range.contains(new_priority) {
+            return Err(ProcessError::InvalidPriority(new_priority, self.priority_range));
+        }
+        let pcb = self.total_chain.get_mut(&pid)
+            .ok_or(ProcessError::ProcessNotFound(pid))?;
+        let old_priority = pcb.priority;
+        pcb.priority = new_priority;
+
+        if self.ready_queue.contains_pid(pid) && self.scheduler.policy() == SchedulingPolicy::Priority {
+            self.ready_queue.reorder_by_priority(&self.total_chain);
+        }
+
+        log(LogLevel::Info, format_args!("✓ 进程 {} 的优先级已从 {} 修改为 {}", pid, old_priority, new_priority));
+        Ok(())
+    }
+
+    /// 调整进程的nice值（类似UNIX的renice）
+    ///
+    /// nice值影响有效优先级而不改变基础优先级；进程在就绪队列中且采用优先级调度时，
+    /// 重新排序就绪队列
+    fn renice(&mut self, pid: u32, nice: i32) -> Result<(), ProcessError> {
+        self.logged(
+            Primitive::Renice { pid, nice },
+            |pm| pm.renice_unlogged(pid, nice),
+            done,
+        )
+    }
+
+    fn renice_unlogged(&mut self, pid: u32, nice: i32) -> Result<(), ProcessError> {
+        if !(NICE_MIN..=NICE_MAX).contains(&nice) {
+            return Err(ProcessError::InvalidNice(nice));
+        }
+        let pcb = self.total_chain.get_mut(&pid)
+            .ok_or(ProcessError::ProcessNotFound(pid))?;
+        let old_nice = pcb.nice;
+        pcb.nice = nice;
+        let effective = pcb.effective_priority();
+
+        if self.ready_queue.contains_pid(pid) && self.scheduler.policy() == SchedulingPolicy::Priority {
+            self.ready_queue.reorder_by_priority(&self.total_chain);
+        }
+
+        log(LogLevel::Info, format_args!("✓ 进程 {} 的nice值已从 {} 修改为 {}，有效优先级: {}", pid, old_nice, nice, effective));
+        Ok(())
+    }
+
+    /// 进入临界区：将当前运行进程标记为不可抢占
+    ///
+    /// 模拟关中断：标记期间时间片用完也不会被换下，
+    /// 直到调用leave_critical或进程被撤销
+    fn enter_critical(&mut self, pid: u32) -> Result<(), ProcessError> {
+        self.logged(
+            Primitive::EnterCritical { pid },
+            |pm| pm.enter_critical_unlogged(pid),
+            done,
+        )
+    }
+
+    fn enter_critical_unlogged(&mut self, pid: u32) -> Result<(), ProcessError> {
+        let running = self.is_running(pid);
+        let pcb = self.total_chain.get_mut(&pid)
+            .filter(|_| running)
+            .ok_or(ProcessError::NotRunning(pid))?;
+        pcb.non_preemptible = true;
+
+        log(LogLevel::Info, format_args!("✓ 进程 {} 进入临界区，暂不可抢占", pid));
+        Ok(())
+    }
+
+    /// 离开临界区：恢复可抢占
+    ///
+    /// 如果在临界区内时间片已经用完，立即执行时间片到原语
+    fn leave_critical(&mut self, pid: u32) -> Result<(), ProcessError> {
+        self.logged(
+            Primitive::LeaveCritical { pid },
+            |pm| pm.leave_critical_unlogged(pid),
+            done,
+        )
+    }
+
+    fn leave_critical_unlogged(&mut self, pid: u32) -> Result<(), ProcessError> {
+        let running = self.is_running(pid);
+        let pcb = self.total_chain.get_mut(&pid)
+            .filter(|p| running && p.non_preemptible)
+            .ok_or(ProcessError::NotInCritical(pid))?;
+        pcb.non_preemptible = false;
+        let slice_used_up = pcb.remaining_time == 0;
+
+        log(LogLevel::Info, format_args!("✓ 进程 {} 离开临界区", pid));
+        if slice_used_up {
+            self.expire_time_slice(pid)?;
+        }
+        Ok(())
+    }
+
+    /// 为即将进入就绪队列的进程分配入队序号（记录在总链中）
+    fn stamp_seq(&mut self, pid: u32) {
+        if let Some(pcb) = self.total_chain.get_mut(&pid) {
+            pcb.seq = self.next_seq;
+            self.next_seq += 1;
+        }
+    }
+
+    /// 按当前调度策略将进程加入就绪队列
+    fn admit_ready(&mut self, pid: u32) {
+        self.stamp_seq(pid);
+        let chain = &self.total_chain;
+        match self.scheduler.policy() {
+            SchedulingPolicy::Priority => self.ready_queue.enqueue_by_priority(pid, chain),
+            SchedulingPolicy::Sjf => {
+                self.ready_queue.enqueue_ordered(pid, chain, |new, old| new.burst_time < old.burst_time);
+            }
+            SchedulingPolicy::RoundRobin | SchedulingPolicy::Fcfs | SchedulingPolicy::Lottery => {
+                self.ready_queue.enqueue(pid);
+            }
+            SchedulingPolicy::Lifo => self.ready_queue.push_front(pid),
+            SchedulingPolicy::Mlfq => {
+                self.ready_queue.enqueue_ordered(pid, chain, |new, old| new.mlfq_level < old.mlfq_level);
+            }
+            SchedulingPolicy::Edf => {
+                // 没有截止时间的进程视为截止时间无穷远
+                self.ready_queue.enqueue_ordered(pid, chain, |new, old| {
+                    new.deadline.unwrap_or(u32::MAX) < old.deadline.unwrap_or(u32::MAX)
+                });
+            }
+            SchedulingPolicy::Cfs => {
+                self.ready_queue.enqueue_ordered(pid, chain, |new, old| new.vruntime < old.vruntime);
+            }
+        }
+    }
+
+    /// 将新建或被唤醒进程的虚拟运行时间提升到就绪与运行进程中的最小值
+    ///
+    /// 否则长时间未运行的进程虚拟运行时间远小于其他进程，进入就绪队列后会长期独占CPU
+    fn place_vruntime(&mut self, pid: u32) {
+        let floor = self.ready_queue.iter()
+            .chain(self.running_pids())
+            .filter(|&other| other != pid)
+            .filter_map(|other| self.total_chain.get(&other).map(|pcb| pcb.vruntime))
+            .min();
+        if let (Some(floor), Some(pcb)) = (floor, self.total_chain.get_mut(&pid)) {
+            pcb.vruntime = pcb.vruntime.max(floor);
+        }
+    }
+
+    /// 切换调度策略
+    ///
+    /// 切换到按键排序的策略（优先级、短作业优先、多级反馈队列、最早截止时间优先、完全公平调度）时，就绪队列可能是按其他策略排列的，
+    /// 需要按新策略重新排序以恢复有序性；其他策略沿用现有顺序
+    fn set_policy(&mut self, policy: SchedulingPolicy) {
+        self.logged(
+            Primitive::SetPolicy { policy },
+            |pm| pm.set_policy_unlogged(policy),
+            |_| "ok".to_string(),
+        )
+    }
+
+    fn set_policy_unlogged(&mut self, policy: SchedulingPolicy) {
+        self.scheduler.set_policy(policy);
+
+        if matches!(
+            policy,
+            SchedulingPolicy::Priority | SchedulingPolicy::Sjf | SchedulingPolicy::Mlfq | SchedulingPolicy::Edf | SchedulingPolicy::Cfs
+        ) {
+            let old_queue = std::mem::replace(&mut self.ready_queue, ProcessQueue::new());
+            for pid in old_queue.iter() {
+                self.admit_ready(pid);
+            }
+        }
+
+        log(LogLevel::Info, format_args!("✓ 调度策略已切换为: {}", policy.name()));
+    }
+
+    /// 调度进程（扩展二：进程调度程序）
+    ///
+    /// 使用优先级调度策略：
+    /// 1. 每个空闲的CPU核依次从就绪队列选择优先级最高的进程（队列已按优先级排序）
+    /// 2. 将其状态改为运行
+    /// 3. 加入该核的运行队列
+    /// 4. 记录进程切换
+    ///
+    /// 之后所有核上的进程在同一个时间单位内并行执行，系统时间只前进一次；
+    /// 没有任何进程可运行时返回ReadyQueueEmpty
+    fn schedule(&mut self) -> Result<(), ProcessError> {
+        // 空闲的核从就绪队列中补上进程
+        self.fill_idle_cores();
+
+        let running = self.running_pids();
+        if running.is_empty() {
+            return Err(ProcessError::ReadyQueueEmpty);
+        }
+
+        // 执行各核上的运行进程：第一个推进系统时间，其余与之并行
+        for (index, pid) in running.iter().enumerate() {
+            let pcb = self.total_chain.get(pid)
+                .ok_or(ProcessError::RunningQueueCorrupted)?;
+            if index == 0 {
+                self.scheduler.execute_process(pcb);
+            } else {
+                self.scheduler.execute_concurrent(pcb);
+            }
+        }
+        for _ in running.len()..self.num_cpus() {
+            self.scheduler.record_idle_core();
+        }
+        Ok(())
+    }
+
+    /// 每个空闲的CPU核依次分派一个可在该核上运行的就绪进程，返回被分派的PID
+    fn fill_idle_cores(&mut self) -> Vec<u32> {
+        let mut dispatched = Vec::new();
+        for core in 0..self.num_cpus() {
+            if self.cores[core].is_empty() {
+                dispatched.extend(self.dispatch(core));
+            }
+        }
+        dispatched
+    }
+
+    /// 进程分派：从就绪队列取出下一个可在core上运行的进程，放入该核的运行队列，返回其PID
+    ///
+    /// 彩票调度下按彩票数抽签选出中签进程，其他策略取第一个符合条件的进程；
+    /// 进程被分派后归属于该核，没有可运行的进程时返回None
+    fn dispatch(&mut self, core: usize) -> Option<u32> {
+        let pid = match self.scheduler.policy() {
+            SchedulingPolicy::Lottery => self.draw_lottery_winner(core),
+            _ => {
+                let pid = self.ready_queue.iter().find(|&pid| self.runnable_on(pid, core))?;
+                self.ready_queue.remove_by_pid(pid);
+                Some(pid)
+            }
+        }?;
+
+        // 更新总链中的状态
+        if let Some(pcb) = self.total_chain.get_mut(&pid) {
+            pcb.state = ProcessState::Running;
+            pcb.wait_ticks = 0;
+            pcb.ready_ticks = 0;
+            pcb.aging_boost = 0;
+            pcb.cpu = Some(core);
+        }
+
+        self.cores[core].enqueue(pid);
+
+        // 记录进程切换（从就绪到运行）
+        self.scheduler.record_switch();
+        Some(pid)
+    }
+
+    /// 检查截止时间：当前系统时间已超过截止时间而仍未完成的进程记为错过一次
+    ///
+    /// 每个进程只记一次；在截止时间当刻完成的进程不算错过
+    fn check_deadlines(&mut self) {
+        let now = self.scheduler.current_time();
+        let mut missed: Vec<u32> = self.total_chain.values()
+            .filter(|pcb| !pcb.deadline_missed && pcb.deadline.is_some_and(|deadline| deadline < now))
+            .map(|pcb| pcb.pid)
+            .collect();
+        missed.sort_unstable();
+
+        for pid in missed {
+            if let Some(pcb) = self.total_chain.get_mut(&pid) {
+                pcb.deadline_missed = true;
+                log(LogLevel::Info, format_args!("⚠ 进程 {} 错过截止时间 {}（当前时间 {}）", pid, pcb.deadline.unwrap_or(0), now));
+            }
+            self.scheduler.record_deadline_miss();
+        }
+    }
+
+    /// 彩票调度：在可在core上运行的就绪进程中按彩票数抽签，将中签进程移出就绪队列并返回其PID
+    ///
+    /// 这些进程的彩票总数为0时退化为取其中排在最前的
+    fn draw_lottery_winner(&mut self, core: usize) -> Option<u32> {
+        let candidates: Vec<(u32, u32)> = self.ready_queue.iter()
+            .filter(|&pid| self.runnable_on(pid, core))
+            .map(|pid| (pid, self.total_chain.get(&pid).map_or(0, |pcb| pcb.tickets)))
+            .collect();
+
+        match self.scheduler.draw_lottery(&candidates) {
+            Some(winner) => {
+                self.ready_queue.remove_by_pid(winner);
+                log(LogLevel::Info, format_args!("彩票调度: 进程 {} 中签", winner));
+                Some(winner)
+            }
+            None => {
+                let pid = candidates.first().map(|&(pid, _)| pid)?;
+                self.ready_queue.remove_by_pid(pid);
+                Some(pid)
+            }
+        }
+    }
+
+    /// 就绪进程老化（防止低优先级进程饥饿）
+    ///
+    /// 本周期仍留在就绪队列中的进程等待周期数加一；超过AGING_THRESHOLD后
+    /// 老化提升加一并重新计数。基础优先级不变，提升只计入有效优先级，
+    /// 且有效优先级不会因老化超过允许范围的上限；进程被调度运行时提升清零。
+    /// 只有优先级调度下优先级才影响顺序，因此仅在该策略下提升，提升后重新排序就绪队列
+    fn age_ready_processes(&mut self) {
+        let boost = self.scheduler.policy() == SchedulingPolicy::Priority;
+        let mut boosted = false;
+
+        for pid in self.ready_queue.iter() {
+            let Some(pcb) = self.total_chain.get_mut(&pid) else {
+                continue;
+            };
+            pcb.wait_ticks += 1;
+            pcb.ready_ticks += 1;
+            if boost && pcb.wait_ticks > AGING_THRESHOLD {
+                pcb.wait_ticks = 0;
+                if pcb.effective_priority() < self.priority_range.max {
+                    pcb.aging_boost += 1;
+                    boosted = true;
+                    log(LogLevel::Info, format_args!("进程 {} 等待过久，有效优先级提升为 {}", pid, pcb.effective_priority()));
+                }
+            }
+        }
+
+        if boosted {
+            self.ready_queue.reorder_by_priority(&self.total_chain);
+        }
+    }
+
+    /// 执行一次调度周期
+    ///
+    /// 步骤：
+    /// 0. 创建已到达的定时进程
+    /// 1. 推进等待中的I/O，完成I/O的进程回到就绪队列
+    /// 2. 空闲的CPU核从就绪队列调度进程
+    /// 3. 各核上的运行进程并行执行（系统时间前进一个单位）
+    /// 4. 就绪队列中的进程老化
+    /// 5. 逐核减少时间片，累计该进程的能耗
+    /// 6. 如果到达I/O点，进程阻塞等待I/O
+    /// 7. 如果时间片用完，执行时间片到原语
+    /// 8. 记录本周期结束时的队列长度
+    fn run_one_cycle(&mut self) {
+        self.logged(
+            Primitive::Cycle,
+            |pm| pm.run_one_cycle_unlogged(),
+            |_| "ok".to_string(),
+        )
+    }
+
+    fn run_one_cycle_unlogged(&mut self) {
+        self.execute_cycle();
+        self.record_queue_metrics();
+    }
+
+    /// 看门狗：撤销存活周期数超过其上限的进程（按PID顺序，连同子进程级联撤销）
+    fn enforce_max_lifetime(&mut self) {
+        let expired = self.matching_pids(|pcb| pcb.max_lifetime.is_some_and(|limit| pcb.lifetime > limit));
+        for pid in expired {
+            let Some(pcb) = self.total_chain.get(&pid) else {
+                continue; // 已随父进程级联撤销
+            };
+            log(LogLevel::Info, format_args!("⚠ 进程 {} 已存在 {} 个周期，超过上限 {}，超时终止",
+                                             pid, pcb.lifetime, pcb.max_lifetime.unwrap_or(0)));
+            if let Err(e) = self.terminate_process(pid) {
+                log(LogLevel::Error, format_args!("{}", e));
+            }
+        }
+    }
+
+    /// 设置进程的最长存活周期数（None表示不限制）
+    fn set_max_lifetime(&mut self, pid: u32, max_lifetime: Option<u32>) -> Result<(), ProcessError> {
+        self.logged(
+            Primitive::SetMaxLifetime { pid, max_lifetime },
+            |pm| pm.set_max_lifetime_unlogged(pid, max_lifetime),
+            done,
+        )
+    }
+
+    fn set_max_lifetime_unlogged(&mut self, pid: u32, max_lifetime: Option<u32>) -> Result<(), ProcessError> {
+        let pcb = self.total_chain.get_mut(&pid)
+            .ok_or(ProcessError::ProcessNotFound(pid))?;
+        pcb.max_lifetime = max_lifetime;
+        match max_lifetime {
+            Some(limit) => log(LogLevel::Info, format_args!("✓ 进程 {} 最多存活 {} 个周期", pid, limit)),
+            None => log(LogLevel::Info, format_args!("✓ 进程 {} 不再限制存活周期", pid)),
+        }
+        Ok(())
+    }
+
+    /// 执行一次调度周期的主体（见run_one_cycle）
+    fn execute_cycle(&mut self) {
+        log(LogLevel::Info, format_args!("\n=== 执行一次调度周期 ==="));
+
+        self.admit_arrivals();
+
+        // 所有存活进程（无论处于何种状态）都经历了这个周期
+        for pcb in self.total_chain.values_mut() {
+            pcb.lifetime += 1;
+        }
+        self.enforce_max_lifetime();
+
+        self.advance_io();
+
+        // 多核时定期做负载均衡，有核空闲时立即做
+        self.cycles_since_balance += 1;
+        if self.num_cpus() > 1 &&
+            (self.idle_core().is_some() || self.cycles_since_balance >= LOAD_BALANCE_INTERVAL) {
+            self.balance_load();
+            self.cycles_since_balance = 0;
+        }
+
+        // 调度（必要时从就绪队列分派）并执行当前运行进程一个时间单位
+        match self.schedule() {
+            Ok(_) => {}
+            Err(ProcessError::ReadyQueueEmpty) => {
+                log(LogLevel::Info, format_args!("CPU空闲：{}", self.schedule_diagnostics()));
+                self.scheduler.record_idle();
+                for _ in 1..self.num_cpus() {
+                    self.scheduler.record_idle_core();
+                }
+                self.check_deadlines();
+                return;
+            }
+            Err(e) => {
+                log(LogLevel::Error, format_args!("{}", e));
+                return;
+            }
+        }
+
+        self.age_ready_processes();
+        self.check_deadlines();
+
+        let energy = self.scheduler.energy_per_tick() as u64;
+
+        let uses_time_slice = self.scheduler.policy().uses_time_slice();
+
+        for pid in self.running_pids() {
+            // 可能已随前面核上进程的撤销被级联撤销
+            if self.is_running(pid) {
+                self.run_on_cpu(pid, uses_time_slice, energy);
+            }
+        }
+    }
+
+    /// 运行进程pid在所在CPU核上执行一个时间单位后的处理：
+    /// 递减时间片和剩余运行时间，并处理完成、到达I/O点和时间片用完
+    fn run_on_cpu(&mut self, pid: u32, uses_time_slice: bool, energy: u64) {
+        let Some(pcb) = self.total_chain.get_mut(&pid) else {
+            log(LogLevel::Error, format_args!("{}", ProcessError::RunningQueueCorrupted));
+            return;
+        };
+        log(LogLevel::Info, format_args!("正在执行进程 {} (优先级: {}, 剩余时间片: {}, 剩余运行时间: {})",
+                                         pid, pcb.priority, pcb.remaining_time, pcb.burst_time));
+
+        // 减少剩余时间片（先来先服务不使用时间片），累计能耗。
+        // 时间片已经为0时（例如在临界区内用完）不能再递减，
+        // 否则u32会下溢；这种情况交给下面的时间片到处理
+        if uses_time_slice && pcb.remaining_time > 0 {
+            pcb.remaining_time -= 1;
+        }
+        pcb.burst_time = pcb.burst_time.saturating_sub(1);
+        pcb.cpu_time_used += 1;
+        pcb.vruntime += cfs_vruntime_delta(pcb);
+        pcb.energy_used += energy;
+
+        // 所需运行时间已全部完成，自动撤销进程
+        if pcb.burst_time == 0 {
+            log(LogLevel::Info, format_args!("✓ 进程 {} 运行完成", pid));
+            if let Err(e) = self.terminate_process(pid) {
+                log(LogLevel::Error, format_args!("{}", e));
+            }
+            return;
+        }
+
+        // 到达I/O点，主动阻塞等待I/O
+        if pcb.io_due() {
+            if let Err(e) = self.start_io(pid) {
+                log(LogLevel::Error, format_args!("{}", e));
+            }
+            return;
+        }
+
+        // 如果时间片用完，执行时间片到原语（临界区内的进程继续运行）
+        if uses_time_slice && pcb.remaining_time == 0 {
+            if pcb.non_preemptible {
+                log(LogLevel::Info, format_args!("进程 {} 时间片已用完，但处于临界区，继续运行", pid));
+            } else if let Err(e) = self.expire_time_slice(pid) {
+                log(LogLevel::Error, format_args!("{}", e));
+            }
+        }
+    }
+
+    /// 连续执行n个调度周期，每个周期只输出一行摘要（n为0时不做任何事）
+    ///
+    /// 执行期间日志级别为信息时临时降为错误，以屏蔽每个周期的详细输出；
+    /// 调试级别保持不变。摘要行不受日志级别限制
+    fn run_cycles(&mut self, n: u32) {
+        let previous = logger::level();
+        if previous == LogLevel::Info {
+            logger::set_level(LogLevel::Error);
+        }
+
+        for _ in 0..n {
+            let before = self.scheduler.execution_count();
+            self.run_one_cycle();
+            let executed = self.scheduler.executions_since(before).to_vec();
+            if executed.is_empty() {
+                logger::output(format_args!("[时间 {:>4}] CPU空闲", self.scheduler.current_time()));
+            }
+            for (time, pid) in executed {
+                match self.total_chain.get(&pid) {
+                    Some(pcb) => logger::output(format_args!("[时间 {:>4}] 运行进程 {}，剩余时间片 {}", time, pid, pcb.remaining_time)),
+                    None => logger::output(format_args!("[时间 {:>4}] 运行进程 {}，已完成", time, pid)),
+                }
+            }
+        }
+
+        logger::set_level(previous);
+    }
+
+    /// 实时监控：共刷新frames帧，每帧先执行cycles_per_frame个调度周期，再清屏并显示系统状态
+    ///
+    /// 执行期间日志级别为信息时临时降为错误，避免调度输出打乱画面；
+    /// 已没有可运行的进程时提前结束。返回实际刷新的帧数
+    fn run_dashboard<W: Write>(&mut self, frames: u32, cycles_per_frame: u32, out: &mut W) -> io::Result<u32> {
+        let previous = logger::level();
+        if previous == LogLevel::Info {
+            logger::set_level(LogLevel::Error);
+        }
+        let shown = self.show_dashboard_frames(frames, cycles_per_frame, out);
+        logger::set_level(previous);
+        shown
+    }
+
+    fn show_dashboard_frames<W: Write>(&mut self, frames: u32, cycles_per_frame: u32, out: &mut W) -> io::Result<u32> {
+        let mut shown = 0;
+        while shown < frames {
+            for _ in 0..cycles_per_frame {
+                self.run_one_cycle();
+            }
+            shown += 1;
+
+            write!(out, "\x1B[2J\x1B[H")?;
+            writeln!(out, "=== 实时监控 第 {}/{} 帧（系统时间 {}）===", shown, frames, self.scheduler.current_time())?;
+            self.print_status(out)?;
+            out.flush()?;
+
+            if !self.has_runnable_work() {
+                writeln!(out, "\n已无可运行的进程，监控结束")?;
+                break;
+            }
+            if shown < frames {
+                thread::sleep(Duration::from_millis(DASHBOARD_FRAME_MS));
+            }
+        }
+        Ok(shown)
+    }
+
+    /// 连续执行调度周期，直到没有可运行的进程或达到max_cycles，返回实际执行的周期数
+    ///
+    /// 等待队列中仍有未完成I/O的进程时继续运行，它们会在I/O完成后自行回到就绪队列；
+    /// 被挂起或阻塞在事件、信号量上的进程需要外部操作才能继续，不计为可运行
+    fn run_until_idle(&mut self, max_cycles: u32) -> u32 {
+        let mut cycles = 0;
+        while cycles < max_cycles && self.has_runnable_work() {
+            self.run_one_cycle();
+            cycles += 1;
+        }
+        cycles
+    }
+
+    /// 是否还有进程会在不需要外部操作的情况下继续运行（含尚未到达的定时进程）
+    fn has_runnable_work(&self) -> bool {
+        !self.pending_arrivals.is_empty() ||
+            !self.ready_queue.is_empty() ||
+            self.current_running().is_some() ||
+            self.waiting_queue.iter().any(|pid| {
+                self.total_chain.get(&pid).is_some_and(|pcb| pcb.remaining_io > 0)
+            })
+    }
+}
+
+fn main() {
+    println!("╔══════════════════════════════════════════════════════╗");
+    println!("║    进程PCB组织、管理与调度模拟系统                    ║");
+    println!("║    操作系统实验2 - 完整实现版                        ║");
+    println!("╚══════════════════════════════════════════════════════╝\n");
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match cli::parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            eprintln!("{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let mut pm = ProcessManager::with_pool_size(options.pool_size.unwrap_or(MAX_PCB_COUNT));
+    color::set_enabled(pm.use_color);
+    if let Some(policy) = options.policy {
+        pm.set_policy(policy);
+    }
+    if let Some(slice) = options.time_slice {
+        if let Err(e) = pm.scheduler.set_default_time_slice(slice) {
+            eprintln!("✗ {}", e);
+            std::process::exit(2);
+        }
+    }
+    // 命令行配置属于起始状态，不作为事件记录
+    pm.event_log.clear();
+
+    // --script <路径>：按脚本执行命令后直接退出，不进入交互菜单
+    if let Some(path) = options.script {
+        let script = match fs::read_to_string(&path) {
+            Ok(script) => script,
+            Err(e) => {
+                eprintln!("✗ 无法读取脚本 {}: {}", path, e);
+                std::process::exit(2);
+            }
+        };
+        let commands: Vec<&str> = script.lines().collect();
+        let failures = menu::run_script(&mut pm, &commands);
+        println!("{}", pm.summary());
+        if failures > 0 {
+            eprintln!("✗ 脚本中有 {} 条命令执行失败", failures);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // 初始化快照
+    let init_snapshot = pm.get_snapshot("初始化快照");
+    println!("{}", init_snapshot);
+
+    // 启动菜单系统：--tui且标准输入输出都是终端时使用全屏菜单，否则使用文本菜单
+    if options.tui {
+        #[cfg(feature = "tui")]
+        {
+            use std::io::IsTerminal;
+            if io::stdin().is_terminal() && io::stdout().is_terminal() {
+                if let Err(e) = tui::run_tui(&mut pm) {
+                    eprintln!("✗ 菜单输入输出错误: {}", e);
+                }
+                return;
+            }
+            eprintln!("⚠ 标准输入输出不是终端，改用文本菜单");
+        }
+        #[cfg(not(feature = "tui"))]
+        eprintln!("⚠ 未启用tui特性（需使用 --features tui 编译），改用文本菜单");
+    }
+
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut output = io::stdout();
+    if let Err(e) = menu::run_menu(&mut pm, &mut input, &mut output) {
+        eprintln!("✗ 菜单输入输出错误: {}", e);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exhausted_time_slice_requeues_to_ready() {
+        let mut pm = ProcessManager::new();
+        let first = pm.create_process(1, 100).unwrap();
+        let second = pm.create_process(1, 100).unwrap();
+        let slice = pm.time_slice_for(&pm.total_chain[&first]);
+
+        for _ in 0..slice {
+            pm.run_one_cycle();
+        }
+        assert_eq!(pm.total_chain[&first].state, ProcessState::Ready);
+        assert!(pm.ready_queue.contains_pid(first));
+        assert_eq!(pm.total_chain[&first].cpu_time_used, slice);
+
+        pm.run_one_cycle();
+        assert_eq!(pm.current_running(), Some(second));
+    }
+
+    #[test]
+    fn time_slice_does_not_underflow_inside_critical_section() {
+        let mut pm = ProcessManager::new();
+        let pid = pm.create_process(1, 100).unwrap();
+        let other = pm.create_process(1, 100).unwrap();
+        pm.run_one_cycle();
+        pm.enter_critical(pid).unwrap();
+
+        let slice = pm.time_slice_for(&pm.total_chain[&pid]);
+        for _ in 0..slice + 3 {
+            pm.run_one_cycle();
+        }
+        assert!(pm.is_running(pid));
+        assert_eq!(pm.total_chain[&pid].remaining_time, 0);
+        assert_eq!(pm.total_chain[&pid].cpu_time_used, slice + 4);
+
+        // 离开临界区时时间片已用完，立即让出CPU
+        pm.leave_critical(pid).unwrap();
+        assert!(!pm.is_running(pid));
+        assert!(pm.ready_queue.contains_pid(pid));
+        pm.run_one_cycle();
+        assert_eq!(pm.current_running(), Some(other));
+    }
+
+    #[test]
+    fn aging_lets_low_priority_process_run() {
+        let mut pm = ProcessManager::new();
+        let hogs: Vec<u32> = (0..3).map(|_| pm.create_process(5, 1000).unwrap()).collect();
+        let low = pm.create_process(0, 1000).unwrap();
+
+        let mut cycles = 0;
+        while pm.total_chain[&low].cpu_time_used == 0 {
+            assert!(cycles < 200, "优先级为0的进程在 {} 个周期内始终没有运行", cycles);
+            pm.run_one_cycle();
+            cycles += 1;
+            for pcb in pm.total_chain.values() {
+                assert!(pcb.effective_priority() <= pm.priority_range.max);
+            }
+        }
+
+        assert!(hogs.iter().all(|pid| pm.total_chain.contains_key(pid)));
+        let pcb = &pm.total_chain[&low];
+        assert_eq!(pcb.priority, 0);
+        assert_eq!(pcb.aging_boost, 0);
+    }
+
+    #[test]
+    fn mutating_a_clone_leaves_the_original_unchanged() {
+        let mut original = ProcessManager::new();
+        let pid = original.create_process(3, 10).unwrap();
+        let blocks = original.pcb_pool.allocated_blocks();
+        let before = original.to_state();
+
+        let mut clone = original.clone();
+        clone.create_process(5, 10).unwrap();
+        clone.change_priority(pid, 7).unwrap();
+        clone.run_cycles(2);
+        clone.terminate_process(pid).unwrap();
+
+        assert_eq!(original.pcb_pool.allocated_blocks(), blocks);
+        assert_eq!(original.pcb_pool.get_used_count(), 1);
+        assert_eq!(original.total_chain.len(), 1);
+        assert_eq!(original.total_chain[&pid].priority, 3);
+        assert_eq!(original.total_chain[&pid].cpu_time_used, 0);
+        assert_eq!(serde_json::to_value(original.to_state()).unwrap(), serde_json::to_value(before).unwrap());
+        assert!(!clone.total_chain.contains_key(&pid));
+    }
+
+    #[test]
+    fn zero_time_slice_is_rejected_and_keeps_the_previous_quantum() {
+        let mut pm = ProcessManager::new();
+        pm.scheduler.set_default_time_slice(3).unwrap();
+        pm.scheduler.set_time_slice(7, 4).unwrap();
+
+        assert_eq!(pm.scheduler.set_default_time_slice(0), Err(ProcessError::InvalidTimeSlice(0)));
+        assert_eq!(pm.scheduler.set_time_slice(7, 0), Err(ProcessError::InvalidTimeSlice(0)));
+        assert_eq!(pm.scheduler.default_time_slice(), 3);
+        assert_eq!(pm.scheduler.time_slice_table(), vec![(7, 4)]);
+
+        let pid = pm.create_process(1, 10).unwrap();
+        assert_eq!(pm.total_chain[&pid].remaining_time, 3);
+    }
+
+    /// 一核系统：第一个进程运行，其余两个就绪
+    fn one_running_two_ready(policy: SuspendPolicy) -> (ProcessManager, u32, u32, u32) {
+        let mut pm = ProcessManager::new();
+        pm.suspend_policy = policy;
+        let running = pm.create_process(5, 10).unwrap();
+        let first = pm.create_process(4, 10).unwrap();
+        let second = pm.create_process(3, 10).unwrap();
+        pm.fill_idle_cores();
+        assert_eq!(pm.current_running(), Some(running));
+        (pm, running, first, second)
+    }
+
+    #[test]
+    fn suspending_a_ready_process_leaves_the_cpu_alone_under_both_policies() {
+        for policy in [SuspendPolicy::KeepIdle, SuspendPolicy::Reschedule] {
+            let (mut pm, running, first, second) = one_running_two_ready(policy);
+            let switches = pm.scheduler.get_stats().total_switches;
+
+            pm.suspend_process(first).unwrap();
+            assert_eq!(pm.total_chain[&first].state, ProcessState::SuspendedReady);
+            assert!(pm.waiting_queue.contains_pid(first));
+            assert_eq!(pm.current_running(), Some(running), "{:?}", policy);
+            assert_eq!(pm.ready_queue.iter().collect::<Vec<_>>(), vec![second]);
+            assert_eq!(pm.scheduler.get_stats().total_switches, switches);
+        }
+    }
+
+    #[test]
+    fn suspending_the_running_process_follows_the_policy() {
+        let (mut pm, running, first, _) = one_running_two_ready(SuspendPolicy::KeepIdle);
+        let switches = pm.scheduler.get_stats().total_switches;
+        pm.suspend_process(running).unwrap();
+        assert_eq!(pm.total_chain[&running].state, ProcessState::SuspendedReady);
+        assert_eq!(pm.current_running(), None);
+        assert_eq!(pm.total_chain[&first].state, ProcessState::Ready);
+        assert_eq!(pm.scheduler.get_stats().total_switches, switches + 1);
+
+        let (mut pm, running, first, _) = one_running_two_ready(SuspendPolicy::Reschedule);
+        let switches = pm.scheduler.get_stats().total_switches;
+        pm.suspend_process(running).unwrap();
+        assert_eq!(pm.total_chain[&running].state, ProcessState::SuspendedReady);
+        assert_eq!(pm.current_running(), Some(first));
+        // 让出CPU与分派下一个进程各记一次切换
+        assert_eq!(pm.scheduler.get_stats().total_switches, switches + 2);
+    }
+
+    #[test]
+    fn suspending_a_waiting_process_keeps_its_event_under_both_policies() {
+        for policy in [SuspendPolicy::KeepIdle, SuspendPolicy::Reschedule] {
+            let (mut pm, running, first, second) = one_running_two_ready(policy);
+            pm.block_on(first, 7).unwrap();
+
+            pm.suspend_process(first).unwrap();
+            assert_eq!(pm.total_chain[&first].state, ProcessState::SuspendedBlocked);
+            assert_eq!(pm.total_chain[&first].blocked_event, Some(7));
+            assert_eq!(pm.current_running(), Some(running), "{:?}", policy);
+            assert_eq!(pm.total_chain[&second].state, ProcessState::Ready);
+            assert_eq!(pm.suspend_process(first), Err(ProcessError::AlreadySuspended(first)));
+        }
+    }
+
+    #[test]
+    fn reschedule_policy_fills_an_idle_core_on_any_suspend() {
+        let (mut pm, running, first, second) = one_running_two_ready(SuspendPolicy::KeepIdle);
+        pm.suspend_process(running).unwrap();
+        assert_eq!(pm.current_running(), None);
+
+        pm.suspend_policy = SuspendPolicy::Reschedule;
+        pm.suspend_process(second).unwrap();
+        assert_eq!(pm.current_running(), Some(first));
+    }
+
+    #[test]
+    fn energy_charges_busy_and_idle_ticks_at_their_own_cost() {
+        let mut pm = ProcessManager::new();
+        pm.set_num_cpus(2);
+        pm.scheduler.set_energy_per_tick(3);
+        pm.scheduler.set_idle_energy_per_tick(1);
+        let pid = pm.create_process(5, 2).unwrap();
+
+        // 前两个周期一核执行、一核空闲，后两个周期两核都空闲
+        for _ in 0..4 {
+            pm.run_one_cycle();
+        }
+        assert!(!pm.total_chain.contains_key(&pid));
+
+        let stats = pm.scheduler.get_stats();
+        assert_eq!(stats.total_executed, 2);
+        assert_eq!(stats.idle_cycles, 2 + 2 * 2);
+        let expected = stats.total_executed as u64 * 3 + stats.idle_cycles as u64;
+        assert_eq!(stats.total_energy, expected);
+        assert_eq!(stats.total_energy, 12);
+    }
+
+    #[test]
+    fn lifo_dispatches_the_most_recently_ready_process_first() {
+        let mut pm = ProcessManager::new();
+        pm.set_policy(SchedulingPolicy::Lifo);
+        let pids: Vec<u32> = (0..3).map(|_| pm.create_process(5, 1).unwrap()).collect();
+        assert_eq!(pids, vec![1, 2, 3]);
+
+        for _ in 0..3 {
+            pm.run_one_cycle();
+        }
+        let executed: Vec<u32> = pm.scheduler.executions_since(0).iter().map(|&(_, pid)| pid).collect();
+        assert_eq!(executed, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn sjf_completes_the_shortest_burst_first() {
+        let mut pm = ProcessManager::new();
+        pm.set_policy(SchedulingPolicy::Sjf);
+        let pids: Vec<u32> = [3, 1, 2].iter().map(|&burst| pm.create_process(5, burst).unwrap()).collect();
+        assert_eq!(pids, vec![1, 2, 3]);
+
+        let mut completed = Vec::new();
+        for _ in 0..6 {
+            pm.run_one_cycle();
+            for &pid in &pids {
+                if !pm.total_chain.contains_key(&pid) && !completed.contains(&pid) {
+                    completed.push(pid);
+                }
+            }
+        }
+        assert_eq!(completed, vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn create_then_terminate_records_alloc_then_free_for_the_same_index() {
+        let mut pm = ProcessManager::new();
+        let pid = pm.create_process(3, 10).unwrap();
+        let index = pm.total_chain[&pid].pool_index;
+        pm.run_one_cycle();
+        pm.terminate_process(pid).unwrap();
+
+        let history = pm.pcb_pool.alloc_history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].kind, buddy_system::AllocEventKind::Alloc);
+        assert_eq!(history[1].kind, buddy_system::AllocEventKind::Free);
+        assert!(history.iter().all(|event| event.index == index));
+        assert!(history[0].tick < history[1].tick);
+    }
+
+    #[test]
+    fn fair_activation_dispatches_the_longer_waiting_process_first() {
+        let mut pm = ProcessManager::new();
+        pm.set_policy(SchedulingPolicy::Priority);
+        pm.fair_activate = true;
+        let running = pm.create_process(5, 100).unwrap();
+        let early = pm.create_process(3, 100).unwrap();
+        let late = pm.create_process(3, 100).unwrap();
+        pm.fill_idle_cores();
+        assert_eq!(pm.current_running(), Some(running));
+
+        pm.suspend_process(early).unwrap();
+        pm.run_cycles(3);
+        pm.suspend_process(late).unwrap();
+        pm.run_cycles(1);
+
+        // 激活顺序与挂起时长相反：较晚挂起的进程先被激活
+        pm.activate_process(late).unwrap();
+        pm.activate_process(early).unwrap();
+        assert!(pm.total_chain[&early].waited_time > pm.total_chain[&late].waited_time);
+        assert_eq!(pm.ready_queue.pids(), vec![early, late]);
+
+        pm.terminate_process(running).unwrap();
+        pm.fill_idle_cores();
+        assert_eq!(pm.current_running(), Some(early));
+    }
+
+    #[test]
+    fn critical_section_blocks_preemption_until_leave_critical() {
+        let mut pm = ProcessManager::new();
+        pm.set_policy(SchedulingPolicy::Priority);
+        pm.scheduler.set_preemptive(true);
+        let low = pm.create_process(1, 100).unwrap();
+        pm.run_one_cycle();
+        assert!(pm.is_running(low));
+        pm.enter_critical(low).unwrap();
+
+        let high = pm.create_process(9, 100).unwrap();
+        assert!(pm.is_running(low));
+        let slice = pm.time_slice_for(&pm.total_chain[&low]);
+        pm.run_cycles(slice + 2);
+        assert!(pm.is_running(low));
+        assert_eq!(pm.total_chain[&high].cpu_time_used, 0);
+        assert!(pm.ready_queue.contains_pid(high));
+
+        // 离开临界区时时间片已用完，立即让出CPU，高优先级进程随后运行
+        pm.leave_critical(low).unwrap();
+        assert!(pm.ready_queue.contains_pid(low));
+        pm.run_one_cycle();
+        assert_eq!(pm.current_running(), Some(high));
+
+        // 对照：不在临界区时，新建的高优先级进程立即抢占
+        let mut pm = ProcessManager::new();
+        pm.set_policy(SchedulingPolicy::Priority);
+        pm.scheduler.set_preemptive(true);
+        let low = pm.create_process(1, 100).unwrap();
+        pm.run_one_cycle();
+        pm.create_process(9, 100).unwrap();
+        assert!(!pm.is_running(low));
+    }
+
+    #[test]
+    fn switching_policies_mid_run_keeps_the_queues_consistent() {
+        let mut pm = ProcessManager::new();
+        pm.set_policy(SchedulingPolicy::Priority);
+        let pids: Vec<u32> = (1..=3).map(|priority| pm.create_process(priority, 100).unwrap()).collect();
+        let (low, mid, high) = (pids[0], pids[1], pids[2]);
+        pm.run_one_cycle();
+        assert_eq!(pm.current_running(), Some(high));
+        assert_eq!(pm.ready_queue.pids(), vec![mid, low]);
+
+        // 切换到轮转：沿用现有顺序，时间片到的进程排到队尾
+        pm.set_policy(SchedulingPolicy::RoundRobin);
+        assert_eq!(pm.ready_queue.pids(), vec![mid, low]);
+        pm.expire_time_slice(high).unwrap();
+        assert_eq!(pm.ready_queue.pids(), vec![mid, low, high]);
+        assert!(pm.verify_consistency().is_empty(), "{:?}", pm.verify_consistency());
+
+        pm.run_one_cycle();
+        assert_eq!(pm.current_running(), Some(mid));
+        pm.set_policy(SchedulingPolicy::Fcfs);
+        assert_eq!(pm.ready_queue.pids(), vec![low, high]);
+
+        // 切回优先级调度：就绪队列按优先级重新排序
+        pm.set_policy(SchedulingPolicy::Priority);
+        assert_eq!(pm.ready_queue.pids(), vec![high, low]);
+        assert!(pm.verify_consistency().is_empty(), "{:?}", pm.verify_consistency());
+        let mut all: Vec<u32> = pm.ready_queue.pids();
+        all.extend(pm.running_pids());
+        all.sort_unstable();
+        assert_eq!(all, pids);
+    }
+
+    #[test]
+    fn processes_pass_through_new_and_end_in_terminated() {
+        let mut pm = ProcessManager::new();
+        let pid = pm.create_process(2, 10).unwrap();
+        let index = pm.total_chain[&pid].pool_index;
+
+        // PCB块中保存的是新建时的快照，加入总链后已转为就绪
+        assert_eq!(pm.pcb_pool.get_pcb(index).unwrap().state, ProcessState::New);
+        assert_eq!(pm.total_chain[&pid].state, ProcessState::Ready);
+
+        pm.run_one_cycle();
+        pm.terminate_process(pid).unwrap();
+        assert!(!pm.total_chain.contains_key(&pid));
+        assert!(pm.pcb_pool.get_pcb(index).is_none());
+        let record = pm.terminated.iter().find(|pcb| pcb.pid == pid).expect("应保留终止记录");
+        assert_eq!(record.state, ProcessState::Terminated);
+        assert_eq!(record.completion_time, pm.scheduler.current_time());
+        assert_eq!(ProcessState::New.to_string(), "新建");
+        assert_eq!(ProcessState::Terminated.to_string(), "终止");
+    }
+
+    #[test]
+    fn average_turnaround_and_waiting_cover_completed_processes() {
+        let mut pm = ProcessManager::new();
+        pm.set_policy(SchedulingPolicy::Fcfs);
+        let first = pm.create_process(1, 2).unwrap();
+        let second = pm.create_process(1, 3).unwrap();
+        assert_eq!(pm.scheduler.get_stats().avg_turnaround, None);
+        pm.run_until_idle(MAX_RUN_CYCLES);
+
+        // 先来先服务：第一个进程在时间2完成，第二个等待2个周期后在时间5完成
+        let record = |pid: u32| pm.terminated.iter().find(|pcb| pcb.pid == pid).unwrap();
+        assert_eq!((record(first).turnaround_time(), record(first).waiting_time()), (2, 0));
+        assert_eq!((record(second).turnaround_time(), record(second).waiting_time()), (5, 2));
+
+        let stats = pm.scheduler.get_stats();
+        assert_eq!(stats.completed_count, 2);
+        assert_eq!(stats.avg_turnaround, Some(3.5));
+        assert_eq!(stats.avg_waiting, Some(1.0));
+        let mut out = Vec::new();
+        stats.display(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("平均周转时间: 3.50"), "{}", text);
+        assert!(text.contains("平均等待时间: 1.00"), "{}", text);
+    }
+
+    #[test]
+    fn primitives_report_failures_as_distinct_error_variants() {
+        let mut pm = ProcessManager::with_pool_size(2);
+        assert_eq!(pm.time_slice_expired(), Err(ProcessError::NoRunningProcess));
+        assert_eq!(pm.schedule(), Err(ProcessError::ReadyQueueEmpty));
+        assert_eq!(pm.terminate_process(42), Err(ProcessError::ProcessNotFound(42)));
+
+        let pid = pm.create_process(1, 10).unwrap();
+        pm.create_process(1, 10).unwrap();
+        assert_eq!(pm.create_process(1, 10), Err(ProcessError::PcbPoolFull));
+        assert_eq!(pm.activate_process(pid), Err(ProcessError::NotInWaiting(pid)));
+        pm.suspend_process(pid).unwrap();
+        assert_eq!(pm.suspend_process(pid), Err(ProcessError::AlreadySuspended(pid)));
+
+        // 实现std::error::Error，可以作为通用错误对象传递
+        let error: Box<dyn std::error::Error> = Box::new(ProcessError::ProcessNotFound(42));
+        assert_eq!(error.to_string(), "进程 42 不存在");
+    }
+
+    #[test]
+    fn changing_priority_reorders_the_ready_queue() {
+        let mut pm = ProcessManager::new();
+        pm.set_policy(SchedulingPolicy::Priority);
+        let pids: Vec<u32> = (1..=3).map(|priority| pm.create_process(priority, 10).unwrap()).collect();
+        assert_eq!(pm.ready_queue.pids(), vec![pids[2], pids[1], pids[0]]);
+
+        pm.change_priority(pids[0], 5).unwrap();
+        assert_eq!(pm.total_chain[&pids[0]].priority, 5);
+        assert_eq!(pm.ready_queue.pids(), vec![pids[0], pids[2], pids[1]]);
+
+        pm.change_priority(pids[2], 0).unwrap();
+        assert_eq!(pm.ready_queue.pids(), vec![pids[0], pids[1], pids[2]]);
+        pm.run_one_cycle();
+        assert_eq!(pm.current_running(), Some(pids[0]));
+    }
+
+    #[test]
+    fn mlfq_demotes_after_each_full_slice_down_to_the_bottom_level() {
+        let mut pm = ProcessManager::new();
+        pm.set_policy(SchedulingPolicy::Mlfq);
+        let hog = pm.create_process(1, 100).unwrap();
+        assert_eq!((pm.total_chain[&hog].mlfq_level, pm.total_chain[&hog].remaining_time), (0, mlfq_time_slice(0)));
+
+        pm.run_cycles(mlfq_time_slice(0));
+        assert_eq!(pm.total_chain[&hog].mlfq_level, 1);
+        assert!(pm.ready_queue.contains_pid(hog));
+
+        // 新进程进入最高级，排在被降级的进程之前
+        let newcomer = pm.create_process(1, 100).unwrap();
+        assert_eq!(pm.ready_queue.pids(), vec![newcomer, hog]);
+        pm.terminate_process(newcomer).unwrap();
+
+        pm.run_cycles(mlfq_time_slice(1));
+        assert_eq!(pm.total_chain[&hog].mlfq_level, 2);
+        pm.run_cycles(mlfq_time_slice(2));
+        assert_eq!(pm.total_chain[&hog].mlfq_level, MLFQ_LEVELS - 1);
+        assert_eq!(pm.total_chain[&hog].cpu_time_used, mlfq_time_slice(0) + mlfq_time_slice(1) + mlfq_time_slice(2));
+    }
+
+    #[test]
+    fn idle_and_busy_cycles_determine_cpu_utilization() {
+        let mut pm = ProcessManager::new();
+        assert_eq!(pm.scheduler.get_stats().cpu_utilization, None);
+
+        pm.run_cycles(2);
+        pm.create_process(1, 3).unwrap();
+        pm.run_cycles(3);
+        pm.run_cycles(1);
+
+        let stats = pm.scheduler.get_stats();
+        assert_eq!((stats.idle_cycles, stats.total_executed, stats.current_time), (3, 3, 6));
+        assert_eq!(stats.cpu_utilization, Some(0.5));
+        let mut out = Vec::new();
+        stats.display(&mut out).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("CPU利用率: 50.00%"));
+    }
+
+    #[test]
+    fn higher_priority_arrival_preempts_only_in_preemptive_mode() {
+        let mut pm = ProcessManager::new();
+        pm.set_policy(SchedulingPolicy::Priority);
+        let low = pm.create_process(1, 100).unwrap();
+        pm.run_one_cycle();
+        let high = pm.create_process(9, 100).unwrap();
+        assert!(pm.is_running(low), "非抢占模式下新进程等待运行进程让出CPU");
+
+        let mut pm = ProcessManager::new();
+        pm.set_policy(SchedulingPolicy::Priority);
+        pm.scheduler.set_preemptive(true);
+        let low = pm.create_process(1, 100).unwrap();
+        pm.run_one_cycle();
+        let switches = pm.scheduler.get_stats().total_switches;
+        let high_created = pm.create_process(9, 100).unwrap();
+        assert_eq!(high_created, high);
+        assert_eq!(pm.total_chain[&low].state, ProcessState::Ready);
+        assert_eq!(pm.ready_queue.pids(), vec![high, low]);
+        assert_eq!(pm.scheduler.get_stats().total_switches, switches + 1);
+        pm.run_one_cycle();
+        assert_eq!(pm.current_running(), Some(high));
+
+        // 被激活的高优先级进程同样立即抢占
+        let mid = pm.create_process(5, 100).unwrap();
+        pm.suspend_process(mid).unwrap();
+        pm.terminate_process(high).unwrap();
+        pm.run_one_cycle();
+        assert_eq!(pm.current_running(), Some(low));
+        pm.activate_process(mid).unwrap();
+        assert!(!pm.is_running(low));
+        pm.run_one_cycle();
+        assert_eq!(pm.current_running(), Some(mid));
+    }
+
+    #[test]
+    fn renice_changes_dequeue_order() {
+        let mut pm = ProcessManager::new();
+        pm.set_policy(SchedulingPolicy::Priority);
+        let first = pm.create_process(3, 10).unwrap();
+        let second = pm.create_process(5, 10).unwrap();
+        assert_eq!(pm.ready_queue.pids(), vec![second, first]);
+
+        // nice -12 使有效优先级提升 12 / 4 = 3，超过基础优先级更高的进程
+        pm.renice(first, -12).unwrap();
+        let pcb = &pm.total_chain[&first];
+        assert_eq!((pcb.priority, pcb.effective_priority()), (3, 6));
+        assert!(pcb.display().contains("优先级: 3, nice: -12, 有效优先级: 6"));
+        assert_eq!(pm.ready_queue.pids(), vec![first, second]);
+
+        pm.renice(first, 19).unwrap();
+        assert_eq!(pm.total_chain[&first].effective_priority(), 0);
+        assert_eq!(pm.renice(first, 20), Err(ProcessError::InvalidNice(20)));
+        pm.run_one_cycle();
+        assert_eq!(pm.current_running(), Some(second));
+    }
+
+    #[test]
+    fn scheduled_io_point_blocks_then_wakes_automatically() {
+        let mut pm = ProcessManager::new();
+        pm.set_policy(SchedulingPolicy::Priority);
+        pm.scheduler.set_preemptive(true);
+        let io_bound = pm.create_process(5, 4).unwrap();
+        let cpu_bound = pm.create_process(1, 20).unwrap();
+        pm.set_io_profile(io_bound, 2, 3).unwrap();
+
+        pm.run_cycles(2);
+        let pcb = &pm.total_chain[&io_bound];
+        assert_eq!((pcb.state.clone(), pcb.remaining_io), (ProcessState::Waiting, 3));
+        assert_eq!(pm.waiting_queue.pids(), vec![io_bound]);
+
+        // I/O期间CPU密集型进程运行，恰好3个周期后I/O进程自动回到就绪并抢占CPU
+        pm.run_cycles(8);
+        let order: Vec<u32> = pm.scheduler.executions_since(0).iter().map(|&(_, pid)| pid).collect();
+        assert_eq!(order, vec![io_bound, io_bound, cpu_bound, cpu_bound, cpu_bound, io_bound, io_bound,
+                               cpu_bound, cpu_bound, cpu_bound]);
+        assert!(!pm.total_chain.contains_key(&io_bound));
+        assert!(pm.waiting_queue.is_empty());
+    }
+
+    #[test]
+    fn edf_runs_the_nearest_deadline_first_and_counts_a_miss() {
+        let mut pm = ProcessManager::new();
+        pm.set_policy(SchedulingPolicy::Edf);
+        let relaxed = pm.create_process_with_deadline(5, 3, Some(10)).unwrap();
+        let urgent = pm.create_process_with_deadline(1, 2, Some(3)).unwrap();
+        let tight = pm.create_process_with_deadline(1, 3, Some(4)).unwrap();
+        pm.run_until_idle(MAX_RUN_CYCLES);
+
+        // 按截止时间3、4、10依次运行：截止时间为4的进程在时间5才完成，记一次错过
+        let order: Vec<u32> = pm.scheduler.executions_since(0).iter().map(|&(_, pid)| pid).collect();
+        assert_eq!(order, vec![urgent, urgent, tight, tight, tight, relaxed, relaxed, relaxed]);
+        assert_eq!(pm.scheduler.get_stats().missed_deadlines, 1);
+        let record = |pid: u32| pm.terminated.iter().find(|pcb| pcb.pid == pid).unwrap();
+        assert!(record(tight).deadline_missed);
+        assert!(!record(urgent).deadline_missed && !record(relaxed).deadline_missed);
+    }
+
+    #[test]
+    fn current_time_includes_execution_and_switch_costs() {
+        let mut pm = ProcessManager::new();
+        pm.set_policy(SchedulingPolicy::RoundRobin);
+        pm.scheduler.set_default_time_slice(2).unwrap();
+        pm.scheduler.set_context_switch_cost(3);
+        pm.create_process(1, 2).unwrap();
+        pm.create_process(1, 4).unwrap();
+        pm.run_until_idle(MAX_RUN_CYCLES);
+
+        // 第一个进程上CPU、第二个进程上CPU、时间片到换下、再次上CPU：4次切换各耗费3个时间单位
+        let stats = pm.scheduler.get_stats();
+        assert_eq!((stats.total_executed, stats.total_switches), (6, 4));
+        assert_eq!(stats.switch_overhead, 12);
+        assert_eq!(stats.current_time, 6 + 12);
+        let mut out = Vec::new();
+        stats.display(&mut out).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("切换开销: 12"));
+    }
+
+    #[test]
+    fn bulk_termination_removes_every_priority_0_process() {
+        let mut pm = ProcessManager::new();
+        let keep: Vec<u32> = [3, 5].iter().map(|&priority| pm.create_process(priority, 10).unwrap()).collect();
+        let zero: Vec<u32> = (0..3).map(|_| pm.create_process(0, 10).unwrap()).collect();
+        pm.fill_idle_cores();
+        pm.block_on(zero[1], 4).unwrap();
+        // 优先级为0的进程的子进程即使改了优先级也随父进程级联撤销
+        let child = pm.fork(zero[2]).unwrap();
+        pm.change_priority(child, 4).unwrap();
+
+        assert_eq!(pm.terminate_by_priority(0), 4);
+        let mut remaining: Vec<u32> = pm.total_chain.keys().copied().collect();
+        remaining.sort_unstable();
+        assert_eq!(remaining, keep);
+        assert_eq!(pm.pcb_pool.get_used_count(), keep.len());
+        assert!(pm.waiting_queue.is_empty());
+        assert!(pm.verify_consistency().is_empty(), "{:?}", pm.verify_consistency());
+        assert_eq!(pm.terminate_by_priority(0), 0);
+    }
+
+    #[test]
+    fn suspending_a_ready_process_and_activating_it_restores_ready() {
+        let mut pm = ProcessManager::new();
+        let pid = pm.create_process(1, 10).unwrap();
+        pm.suspend_process(pid).unwrap();
+        assert_eq!(pm.total_chain[&pid].state, ProcessState::SuspendedReady);
+        assert_eq!(pm.waiting_queue.pids(), vec![pid]);
+
+        pm.activate_process(pid).unwrap();
+        assert_eq!(pm.total_chain[&pid].state, ProcessState::Ready);
+        assert_eq!(pm.ready_queue.pids(), vec![pid]);
+        assert!(pm.waiting_queue.is_empty());
+    }
+
+    #[test]
+    fn suspending_a_blocked_process_and_activating_it_restores_waiting() {
+        let mut pm = ProcessManager::new();
+        let pid = pm.create_process(1, 10).unwrap();
+        let other = pm.create_process(1, 10).unwrap();
+        pm.block_on(pid, 3).unwrap();
+        pm.block_on(other, 3).unwrap();
+        pm.suspend_process(pid).unwrap();
+        pm.suspend_process(other).unwrap();
+        assert_eq!(pm.total_chain[&pid].state, ProcessState::SuspendedBlocked);
+
+        // 激活后仍在等待事件，事件发生才就绪
+        pm.activate_process(pid).unwrap();
+        assert_eq!(pm.total_chain[&pid].state, ProcessState::Waiting);
+        assert_eq!(pm.total_chain[&pid].blocked_event, Some(3));
+        assert!(pm.waiting_queue.contains_pid(pid));
+
+        // 挂起阻塞的进程等到事件后转为挂起就绪，仍需激活
+        assert_eq!(pm.wakeup_event(3), vec![pid, other]);
+        assert_eq!(pm.total_chain[&pid].state, ProcessState::Ready);
+        assert_eq!(pm.total_chain[&other].state, ProcessState::SuspendedReady);
+        pm.activate_process(other).unwrap();
+        assert_eq!(pm.ready_queue.pids(), vec![pid, other]);
+    }
+
+    #[test]
+    fn run_until_idle_completes_a_small_workload() {
+        let mut pm = ProcessManager::new();
+        let mut capped = ProcessManager::new();
+        for (priority, burst) in [(3, 3), (2, 2), (1, 4)] {
+            pm.create_process(priority, burst).unwrap();
+            capped.create_process(priority, burst).unwrap();
+        }
+
+        // 每个周期执行一个时间单位，按优先级依次完成于时间3、5、9
+        assert_eq!(pm.run_until_idle(MAX_RUN_CYCLES), 9);
+        assert!(pm.ready_queue.is_empty() && pm.current_running().is_none());
+        let stats = pm.scheduler.get_stats();
+        assert_eq!(stats.total_executed, 9);
+        assert_eq!(stats.completed_count, 3);
+        assert_eq!(stats.current_time, 9);
+        assert_eq!(stats.avg_turnaround, Some(17.0 / 3.0));
+        assert_eq!(stats.avg_waiting, Some(8.0 / 3.0));
+        assert_eq!(pm.run_until_idle(MAX_RUN_CYCLES), 0);
+
+        // 达到上限时提前停止
+        assert_eq!(capped.run_until_idle(4), 4);
+        assert_eq!(capped.scheduler.get_stats().completed_count, 1);
+    }
+
+    #[test]
+    fn run_cycles_prints_one_line_per_cycle() {
+        let mut pm = ProcessManager::new();
+        let first = pm.create_process(2, 4).unwrap();
+        let second = pm.create_process(1, 3).unwrap();
+
+        logger::start_capture();
+        pm.run_cycles(0);
+        assert_eq!(logger::take_captured(), "");
+        assert_eq!(pm.scheduler.current_time(), 0);
+
+        pm.run_cycles(10);
+        let output = logger::stop_capture();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 10, "{}", output);
+        assert_eq!(lines[0], format!("[时间    1] 运行进程 {}，剩余时间片 4", first));
+        assert_eq!(lines[3], format!("[时间    4] 运行进程 {}，已完成", first));
+        assert_eq!(lines[6], format!("[时间    7] 运行进程 {}，已完成", second));
+        assert!(lines[7..].iter().all(|line| line.ends_with("CPU空闲")), "{}", output);
+        assert_eq!(pm.scheduler.current_time(), 10);
+        assert_eq!(logger::level(), LogLevel::Info);
+    }
+
+    #[test]
+    fn processes_get_blocks_sized_by_their_memory_and_coalesce_on_exit() {
+        let mut pm = ProcessManager::with_pool_size(16);
+        let small = pm.create_process_with_memory(1, 10, None, 4).unwrap();
+        let medium = pm.create_process_with_memory(1, 10, None, 5).unwrap();
+        let large = pm.create_process_with_memory(1, 10, None, 10).unwrap();
+
+        // 4KB一个槽位，5KB向上取整为2个槽位，10KB向上取整为4个槽位
+        let order_of = |pm: &ProcessManager, pid: u32| {
+            let index = pm.total_chain[&pid].pool_index;
+            pm.pcb_pool.allocated_blocks().into_iter().find(|&(start, _)| start == index).unwrap().1
+        };
+        assert_eq!([order_of(&pm, small), order_of(&pm, medium), order_of(&pm, large)], [0, 1, 2]);
+        assert_eq!(pm.pcb_pool.get_used_count(), 7);
+        assert!(pm.total_chain[&large].display().contains("内存: 10 KB"), "{}", pm.total_chain[&large].display());
+
+        // 剩余9个槽位，但没有连续16个槽位的空闲块
+        assert!(matches!(pm.create_process_with_memory(1, 10, None, 64), Err(ProcessError::OutOfMemory(64))));
+        assert_eq!(pm.total_chain.len(), 3);
+
+        for pid in [medium, small, large] {
+            pm.terminate_process(pid).unwrap();
+        }
+        assert_eq!(pm.pcb_pool.get_free_count(), 16);
+        assert!(pm.pcb_pool.allocated_blocks().is_empty());
+        let whole = pm.create_process_with_memory(1, 10, None, 64).unwrap();
+        assert_eq!(order_of(&pm, whole), 4);
+    }
+
+    #[test]
+    fn priorities_outside_the_configured_range_are_rejected() {
+        let mut pm = ProcessManager::new();
+        assert!(pm.create_process(0, 10).is_ok());
+        assert!(pm.create_process(10, 10).is_ok());
+        assert!(matches!(pm.create_process(11, 10), Err(ProcessError::InvalidPriority(11, _))));
+        assert!(matches!(pm.create_process(999999, 10), Err(ProcessError::InvalidPriority(999999, _))));
+        assert_eq!(pm.total_chain.len(), 2);
+        assert_eq!(pm.pcb_pool.get_used_count(), 2);
+
+        pm.priority_range = PriorityRange::new(3, 5).unwrap();
+        assert!(matches!(pm.create_process(2, 10), Err(ProcessError::InvalidPriority(2, _))));
+        assert!(pm.create_process(3, 10).is_ok());
+        assert!(pm.create_process(5, 10).is_ok());
+        assert!(matches!(pm.create_process(6, 10), Err(ProcessError::InvalidPriority(6, _))));
+        assert!(PriorityRange::new(5, 3).is_none());
+    }
+
+    #[test]
+    fn recycling_reuses_a_terminated_pid() {
+        let mut monotonic = ProcessManager::new();
+        let mut recycling = ProcessManager::new();
+        recycling.set_recycle_pids(true);
+        for pm in [&mut monotonic, &mut recycling] {
+            for _ in 0..3 {
+                pm.create_process(1, 10).unwrap();
+            }
+            pm.terminate_process(2).unwrap();
+        }
+
+        assert_eq!(monotonic.create_process(1, 10).unwrap(), 4);
+        assert_eq!(recycling.create_process(1, 10).unwrap(), 2);
+        assert_eq!(recycling.create_process(1, 10).unwrap(), 4);
+
+        // 关闭复用后已撤销的PID不再被使用
+        recycling.terminate_process(3).unwrap();
+        recycling.set_recycle_pids(false);
+        assert_eq!(recycling.create_process(1, 10).unwrap(), 5);
+    }
+
+    #[test]
+    fn lifetime_counts_cycles_since_creation() {
+        let mut pm = ProcessManager::new();
+        let first = pm.create_process(5, 20).unwrap();
+        assert_eq!((pm.total_chain[&first].created_at, pm.total_chain[&first].lifetime), (0, 0));
+
+        pm.run_cycles(2);
+        let second = pm.create_process(1, 20).unwrap();
+        pm.block_on(second, 1).unwrap();
+        assert_eq!(pm.total_chain[&second].created_at, pm.scheduler.current_time());
+
+        let mut previous = pm.total_chain[&second].lifetime;
+        for _ in 0..3 {
+            pm.run_one_cycle();
+            let lifetime = pm.total_chain[&second].lifetime;
+            assert_eq!(lifetime, previous + 1, "阻塞的进程也在变老");
+            previous = lifetime;
+        }
+        assert_eq!(pm.total_chain[&first].lifetime, 5);
+        assert!(pm.total_chain[&first].display().ends_with("创建时间: 0, 已存在: 5 个周期"));
+    }
+
+    #[test]
+    fn dashboard_refreshes_fixed_frames_and_stops_when_idle() {
+        let mut pm = ProcessManager::new();
+        pm.create_process(1, 5).unwrap();
+
+        let mut out = Vec::new();
+        logger::start_capture();
+        let shown = pm.run_dashboard(10, 2, &mut out).unwrap();
+        let chatter = logger::stop_capture();
+        let screen = String::from_utf8(out).unwrap();
+
+        // 每帧先执行2个周期：第3帧时进程已完成，提前结束
+        assert_eq!(shown, 3);
+        assert_eq!(screen.matches("\x1B[2J\x1B[H").count(), 3);
+        for (frame, time) in [(1, 2), (2, 4), (3, 6)] {
+            assert!(screen.contains(&format!("=== 实时监控 第 {}/10 帧（系统时间 {}）===", frame, time)), "{}", screen);
+        }
+        assert_eq!(screen.matches("========== 系统状态 ==========").count(), 3);
+        assert!(screen.ends_with("已无可运行的进程，监控结束\n"), "{}", screen);
+        assert!(chatter.is_empty(), "{}", chatter);
+        assert_eq!(logger::level(), LogLevel::Info);
+    }
+
+    #[test]
+    fn activation_boost_moves_a_woken_process_ahead_of_equal_priorities() {
+        let run = |boost: u32| {
+            let mut pm = ProcessManager::new();
+            pm.activation_boost = boost;
+            pm.scheduler.set_default_time_slice(2).unwrap();
+            let pids: Vec<u32> = (0..4).map(|_| pm.create_process(3, 10).unwrap()).collect();
+            pm.fill_idle_cores();
+            pm.block_on(pids[1], 1).unwrap();
+            pm.wakeup_event(1);
+            (pm, pids)
+        };
+
+        let (plain, pids) = run(0);
+        assert_eq!(plain.ready_queue.pids(), vec![pids[2], pids[3], pids[1]]);
+
+        let (mut boosted, pids) = run(2);
+        assert_eq!(boosted.ready_queue.pids(), vec![pids[1], pids[2], pids[3]]);
+        assert_eq!(boosted.total_chain[&pids[1]].boost, 2);
+
+        // 提升随每个用完的时间片衰减：第一个进程用完时间片后，被唤醒的进程连续运行两个时间片
+        boosted.run_cycles(4);
+        assert_eq!(boosted.total_chain[&pids[1]].boost, 1);
+        boosted.run_cycles(2);
+        assert_eq!(boosted.total_chain[&pids[1]].boost, 0);
+        let runs = boosted.scheduler.executions_since(0).iter().filter(|&&(_, pid)| pid == pids[1]).count();
+        assert_eq!(runs, 4);
+    }
+
+    #[test]
+    fn terminate_returns_the_whole_subtree_in_preorder() {
+        let mut pm = ProcessManager::new();
+        let root = pm.create_process(2, 10).unwrap();
+        let a = pm.fork(root).unwrap();
+        let b = pm.fork(root).unwrap();
+        let a1 = pm.fork(a).unwrap();
+        let b1 = pm.fork(b).unwrap();
+        let a2 = pm.fork(a).unwrap();
+        let loner = pm.create_process(1, 10).unwrap();
+
+        assert_eq!(pm.terminate_process(loner).unwrap(), vec![loner]);
+        // 先序：父进程在前，子进程按fork顺序展开
+        assert_eq!(pm.terminate_process(root).unwrap(), vec![root, a, a1, a2, b, b1]);
+        assert!(pm.total_chain.is_empty());
+        assert_eq!(pm.terminated_count, 7);
+    }
+
+    #[test]
+    fn dry_run_plans_leave_the_total_chain_unchanged() {
+        let mut pm = ProcessManager::new();
+        let parent = pm.create_process(4, 10).unwrap();
+        let child = pm.fork(parent).unwrap();
+        let low = pm.create_process(0, 10).unwrap();
+        let other = pm.create_process(2, 10).unwrap();
+        pm.fill_idle_cores();
+        pm.block_on(other, 1).unwrap();
+
+        let pids = |pm: &ProcessManager| {
+            let mut pids: Vec<u32> = pm.total_chain.keys().copied().collect();
+            pids.sort_unstable();
+            pids
+        };
+        let before = pids(&pm);
+        let used = pm.pcb_pool.get_used_count();
+
+        assert_eq!(pm.plan_terminate(parent).unwrap(), vec![parent, child]);
+        assert!(matches!(pm.plan_terminate(99), Err(ProcessError::ProcessNotFound(99))));
+        assert_eq!(pm.plan_by_priority(4), vec![parent, child]);
+        assert_eq!(pm.plan_by_state(ProcessState::Ready), vec![child, low]);
+        assert_eq!(pm.plan_by_state(ProcessState::Waiting), vec![other]);
+        assert_eq!(pids(&pm), before);
+        assert_eq!(pm.pcb_pool.get_used_count(), used);
+        assert!(pm.verify_consistency().is_empty());
+
+        // 预演结果与实际撤销的范围一致
+        let plan = pm.plan_by_state(ProcessState::Running);
+        assert_eq!(plan, vec![parent, child]);
+        assert_eq!(pm.terminate_by_state(ProcessState::Running), plan.len());
+        assert_eq!(pids(&pm), vec![low, other]);
+    }
+
+    #[test]
+    fn cfs_splits_cpu_time_in_proportion_to_weight() {
+        let mut pm = ProcessManager::new();
+        pm.set_policy(SchedulingPolicy::Cfs);
+        let heavy = pm.create_process(3, 1000).unwrap();
+        let light = pm.create_process(0, 1000).unwrap();
+        pm.run_cycles(200);
+        let cpu = |pid: u32| pm.total_chain[&pid].cpu_time_used;
+
+        // 权重4比1：CPU时间约为4比1，虚拟运行时间保持接近
+        assert_eq!(cpu(heavy) + cpu(light), 200);
+        assert!(cpu(heavy).abs_diff(4 * cpu(light)) <= 10, "{} : {}", cpu(heavy), cpu(light));
+        let vruntime = |pid: u32| pm.total_chain[&pid].vruntime;
+        let tolerance = 5 * cfs_vruntime_delta(&pm.total_chain[&light]);
+        assert!(vruntime(heavy).abs_diff(vruntime(light)) <= tolerance, "{} : {}", vruntime(heavy), vruntime(light));
+    }
+
+    #[test]
+    fn block_applies_only_to_the_running_process() {
+        let mut pm = ProcessManager::new();
+        let running = pm.create_process(5, 10).unwrap();
+        let ready = pm.create_process(3, 10).unwrap();
+        pm.fill_idle_cores();
+
+        assert!(matches!(pm.block(ready), Err(ProcessError::NotRunning(pid)) if pid == ready));
+        assert!(matches!(pm.block(99), Err(ProcessError::ProcessNotFound(99))));
+        assert_eq!(pm.total_chain[&ready].state, ProcessState::Ready);
+
+        pm.block(running).unwrap();
+        assert_eq!(pm.total_chain[&running].state, ProcessState::Waiting);
+        assert_eq!(pm.total_chain[&running].blocked_event, None);
+        assert_eq!(pm.waiting_queue.pids(), vec![running]);
+        assert!(!pm.is_running(running));
+        assert!(pm.verify_consistency().is_empty());
+    }
+
+    #[test]
+    fn wakeup_moves_a_blocked_process_into_ready() {
+        let mut pm = ProcessManager::new();
+        let first = pm.create_process(5, 10).unwrap();
+        let second = pm.create_process(3, 10).unwrap();
+        let third = pm.create_process(1, 10).unwrap();
+        pm.fill_idle_cores();
+        pm.block(first).unwrap();
+        pm.fill_idle_cores();
+        pm.block(second).unwrap();
+
+        assert!(matches!(pm.wakeup(third), Err(ProcessError::NotInWaiting(pid)) if pid == third));
+        pm.wakeup(first).unwrap();
+        assert_eq!(pm.total_chain[&first].state, ProcessState::Ready);
+        assert_eq!(pm.waiting_queue.pids(), vec![second]);
+        assert!(pm.ready_queue.contains_pid(first));
+        assert!(matches!(pm.wakeup(first), Err(ProcessError::NotInWaiting(_))));
+        assert!(pm.verify_consistency().is_empty());
+    }
+
+    #[test]
+    fn a_process_over_its_max_lifetime_is_terminated_on_the_next_cycle() {
+        let mut pm = ProcessManager::new();
+        let limited = pm.create_process(5, 20).unwrap();
+        let unlimited = pm.create_process(1, 20).unwrap();
+        pm.set_max_lifetime(limited, Some(3)).unwrap();
+        let index = pm.total_chain[&limited].pool_index;
+        let used = pm.pcb_pool.get_used_count();
+
+        pm.run_cycles(3);
+        assert!(pm.total_chain.contains_key(&limited));
+
+        logger::start_capture();
+        pm.run_one_cycle();
+        let output = logger::stop_capture();
+        assert!(!pm.total_chain.contains_key(&limited));
+        assert!(output.contains(&format!("进程 {} 已存在 4 个周期，超过上限 3，超时终止", limited)), "{}", output);
+        assert!(pm.pcb_pool.get_pcb(index).is_none());
+        assert_eq!(pm.pcb_pool.get_used_count(), used - 1);
+
+        // 默认不限制存活周期
+        assert_eq!(pm.total_chain[&unlimited].max_lifetime, None);
+        pm.run_cycles(10);
+        assert!(pm.total_chain.contains_key(&unlimited));
+    }
+
+    #[test]
+    fn clone_copies_queues_scheduler_and_next_pid_independently() {
+        let mut original = ProcessManager::new();
+        original.set_policy(SchedulingPolicy::RoundRobin);
+        let pids: Vec<u32> = (0..4).map(|_| original.create_process(2, 10).unwrap()).collect();
+        original.run_cycles(3);
+        original.block_on(pids[3], 1).unwrap();
+
+        let mut clone = original.clone();
+        assert_eq!(clone.ready_queue.pids(), original.ready_queue.pids());
+        assert_eq!(clone.waiting_queue.pids(), vec![pids[3]]);
+        assert_eq!(clone.running_pids(), original.running_pids());
+        assert_eq!(clone.scheduler.policy(), SchedulingPolicy::RoundRobin);
+        assert_eq!(clone.scheduler.current_time(), 3);
+        assert_eq!(clone.pcb_pool.allocated_blocks(), original.pcb_pool.allocated_blocks());
+
+        // 两边各自分配下一个PID，互不影响
+        assert_eq!(clone.create_process(1, 10).unwrap(), 5);
+        assert_eq!(original.create_process(1, 10).unwrap(), 5);
+
+        clone.wakeup_event(1);
+        clone.run_cycles(5);
+        assert_eq!(original.waiting_queue.pids(), vec![pids[3]]);
+        assert_eq!(original.scheduler.current_time(), 3);
+        assert!(clone.pcb_pool.check_invariants().is_empty());
+        assert!(clone.verify_consistency().is_empty());
+        assert!(original.verify_consistency().is_empty());
+    }
+
+    #[test]
+    fn a_default_slice_of_3_applies_to_new_and_requeued_processes() {
+        let mut pm = ProcessManager::new();
+        pm.scheduler.set_default_time_slice(3).unwrap();
+        let first = pm.create_process(1, 100).unwrap();
+        let second = pm.create_process(1, 100).unwrap();
+        assert_eq!(pm.total_chain[&first].remaining_time, 3);
+        assert_eq!(pm.total_chain[&second].remaining_time, 3);
+
+        // 手动时间片到后同样重置为3
+        pm.fill_idle_cores();
+        pm.run_one_cycle();
+        assert_eq!(pm.total_chain[&first].remaining_time, 2);
+        pm.time_slice_expired().unwrap();
+        assert_eq!(pm.total_chain[&first].remaining_time, 3);
+
+        // 运行满3个时间单位后自动让出CPU
+        pm.run_cycles(4);
+        assert_eq!(pm.total_chain[&second].cpu_time_used, 3);
+        assert_eq!(pm.total_chain[&second].state, ProcessState::Ready);
+    }
+}
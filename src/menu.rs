@@ -1,152 +1,1895 @@
-use crate::ProcessManager;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
 
-pub fn run_menu(pm: &mut ProcessManager) {
+use crate::error::ProcessError;
+use crate::logger;
+use crate::pcb::{PriorityRange, ProcessState, DEFAULT_MEMORY_KB};
+use crate::scheduler::{QuantumPolicy, SchedulingPolicy};
+use crate::workload::{random_specs, ProcessSpec};
+use crate::snapshot::Snapshot;
+use crate::{ProcessManager, MAX_RUN_CYCLES, STARVATION_THRESHOLD};
+
+/// 菜单框内部宽度（按终端显示宽度计算，中文字符占两格）
+const MENU_WIDTH: usize = 54;
+
+/// 主菜单选项：(按键, 说明)
+pub const MENU_ITEMS: &[(&str, &str)] = &[
+    ("1", "创建进程"),
+    ("2", "撤销进程（级联撤销子进程）"),
+    ("3", "时间片到"),
+    ("4", "挂起进程"),
+    ("5", "激活进程"),
+    ("t", "阻塞进程等待事件"),
+    ("u", "发出事件唤醒等待进程"),
+    ("m", "信号量操作（创建/P/V）"),
+    ("d", "死锁检测"),
+    ("6", "执行一次调度周期"),
+    ("a", "修改进程优先级"),
+    ("7", "显示系统状态"),
+    ("8", "生成快照"),
+    ("j", "以JSON格式输出当前快照"),
+    ("k", "保存当前快照到文件"),
+    ("r", "从快照文件恢复"),
+    ("9", "批量创建测试进程"),
+    ("y", "fork子进程"),
+    ("z", "显示进程树"),
+    ("c", "进入临界区"),
+    ("v", "离开临界区"),
+    ("s", "显示调度器统计信息"),
+    ("g", "显示甘特图"),
+    ("p", "切换调度策略"),
+    ("b", "显示伙伴系统状态与碎片报告"),
+    ("h", "显示PCB分配历史"),
+    ("o", "显示就绪队列排序依据"),
+    ("f", "切换公平激活模式"),
+    ("x", "切换挂起策略"),
+    ("e", "设置执行/空闲能耗"),
+    ("q", "配置时间片（默认/各优先级）"),
+    ("w", "保存系统状态到文件"),
+    ("l", "从文件加载系统状态"),
+    ("i", "撤销上一步"),
+    ("n", "查询与诊断"),
+    ("10", "调度设置"),
+    ("11", "调整进程nice值"),
+    ("12", "设置进程I/O模式"),
+    ("13", "按种子生成随机负载"),
+    ("14", "PCB池压力测试"),
+    ("15", "重置模拟"),
+    ("16", "批量撤销进程（按状态/优先级）"),
+    ("17", "导出进程表为CSV"),
+    ("18", "切换日志级别"),
+    ("19", "连续运行直到空闲"),
+    ("20", "比较两个快照文件"),
+    ("21", "自动快照（查看/恢复/设置容量）"),
+    ("22", "显示伙伴树"),
+    ("23", "消息通信（发送/接收）"),
+    ("24", "实时监控（自动运行并刷新状态）"),
+    ("25", "切换彩色状态显示"),
+    ("26", "队列长度历史（查看/导出/清空）"),
+    ("27", "阻塞运行进程（block）"),
+    ("28", "唤醒指定阻塞进程（wakeup）"),
+    ("29", "比较各调度策略（同一负载）"),
+    ("30", "定时创建进程"),
+    ("31", "切换伙伴系统分配策略"),
+    ("32", "事件日志（查看/导出/重放）"),
+    ("33", "进程组（创建/加入/挂起/激活/撤销）"),
+    ("0", "退出"),
+];
+
+/// 会修改系统状态的菜单选项：执行前记录撤销点
+const UNDOABLE_KEYS: &[&str] = &[
+    "1", "2", "3", "4", "5", "t", "u", "m", "6", "a", "9", "y", "c", "v",
+    "p", "f", "x", "e", "q", "l", "r", "10", "11", "12", "13", "14", "15", "16", "19", "23", "24", "27", "28", "30", "31", "32", "33",
+];
+
+/// 计算字符串在终端中的显示宽度
+fn display_width(text: &str) -> usize {
+    text.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum()
+}
+
+/// 输出一行菜单框内容，右侧补齐空格
+fn write_boxed<W: Write>(out: &mut W, text: &str) -> io::Result<()> {
+    let padding = MENU_WIDTH.saturating_sub(display_width(text));
+    writeln!(out, "║{}{}║", text, " ".repeat(padding))
+}
+
+fn print_menu<W: Write>(out: &mut W) -> io::Result<()> {
+    writeln!(out, "\n╔{}╗", "═".repeat(MENU_WIDTH))?;
+    write_boxed(out, "                    主菜单")?;
+    writeln!(out, "╠{}╣", "═".repeat(MENU_WIDTH))?;
+    for (key, label) in MENU_ITEMS {
+        write_boxed(out, &format!("  {}. {}", key, label))?;
+    }
+    writeln!(out, "╚{}╝", "═".repeat(MENU_WIDTH))
+}
+
+/// 输出提示并读取一行输入（已去除首尾空白）
+///
+/// 输入已结束（EOF）时返回UnexpectedEof错误，由run_menu当作退出处理
+fn prompt<R: BufRead, W: Write>(input: &mut R, out: &mut W, message: &str) -> io::Result<String> {
+    write!(out, "{}", message)?;
+    out.flush()?;
+
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+    }
+    Ok(line.trim().to_string())
+}
+
+/// 将输入解析为非负整数，失败时返回具体原因（name为该数值的名称，用于提示）
+fn parse_u32(text: &str, name: &str) -> Result<u32, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(format!("{}不能为空", name));
+    }
+    if let Ok(value) = text.parse::<u32>() {
+        return Ok(value);
+    }
+
+    let is_digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    match text.strip_prefix('-') {
+        Some(digits) if is_digits(digits) => Err(format!("{}不能为负", name)),
+        _ if is_digits(text.strip_prefix('+').unwrap_or(text)) => Err(format!("{}超出范围（最大为 {}）", name, u32::MAX)),
+        _ => Err(format!("无效的{}：请输入非负整数", name)),
+    }
+}
+
+/// 提示输入一个非负整数
+///
+/// 空行时重新提示；负数、超出范围或非数字时输出具体原因并返回None
+fn read_u32<R: BufRead, W: Write>(input: &mut R, out: &mut W, message: &str, name: &str) -> io::Result<Option<u32>> {
+    loop {
+        let line = prompt(input, out, message)?;
+        if line.is_empty() {
+            continue;
+        }
+        return match parse_u32(&line, name) {
+            Ok(value) => Ok(Some(value)),
+            Err(reason) => {
+                writeln!(out, "✗ {}", reason)?;
+                Ok(None)
+            }
+        };
+    }
+}
+
+/// 提示输入PID，解析失败时输出提示并返回None
+fn prompt_pid<R: BufRead, W: Write>(input: &mut R, out: &mut W, message: &str) -> io::Result<Option<u32>> {
+    read_u32(input, out, message, "PID")
+}
+
+/// 提示输入事件号，解析失败时输出提示并返回None
+fn prompt_event<R: BufRead, W: Write>(input: &mut R, out: &mut W) -> io::Result<Option<u32>> {
+    read_u32(input, out, "请输入事件号: ", "事件号")
+}
+
+/// 提示输入进程组号，解析失败时输出提示并返回None
+fn prompt_group<R: BufRead, W: Write>(input: &mut R, out: &mut W) -> io::Result<Option<u32>> {
+    read_u32(input, out, "请输入进程组号: ", "进程组号")
+}
+
+/// 提示输入信号量编号，解析失败时输出提示并返回None
+fn prompt_sem<R: BufRead, W: Write>(input: &mut R, out: &mut W) -> io::Result<Option<u32>> {
+    read_u32(input, out, "请输入信号量编号: ", "信号量编号")
+}
+
+/// 列出将被撤销的进程并请求确认，输入y时返回true，否则输出"已取消"并返回false；
+/// 计划为空时直接返回false
+fn confirm_plan<R: BufRead, W: Write>(input: &mut R, out: &mut W, plan: &[u32]) -> io::Result<bool> {
+    if plan.is_empty() {
+        writeln!(out, "没有符合条件的进程")?;
+        return Ok(false);
+    }
+    let pids: Vec<String> = plan.iter().map(|pid| pid.to_string()).collect();
+    writeln!(out, "将撤销以下 {} 个进程: {}", plan.len(), pids.join(", "))?;
+    let answer = prompt(input, out, "确定撤销？(y/n): ")?;
+    if answer.eq_ignore_ascii_case("y") {
+        Ok(true)
+    } else {
+        writeln!(out, "已取消")?;
+        Ok(false)
+    }
+}
+
+/// 提示选择进程状态，无效时输出提示并返回None
+fn prompt_state<R: BufRead, W: Write>(input: &mut R, out: &mut W) -> io::Result<Option<ProcessState>> {
+    let line = prompt(input, out, "请选择状态 (1.新建 2.就绪 3.运行 4.等待 5.终止 6.挂起就绪 7.挂起阻塞): ")?;
+    let state = match line.as_str() {
+        "1" => ProcessState::New,
+        "2" => ProcessState::Ready,
+        "3" => ProcessState::Running,
+        "4" => ProcessState::Waiting,
+        "5" => ProcessState::Terminated,
+        "6" => ProcessState::SuspendedReady,
+        "7" => ProcessState::SuspendedBlocked,
+        _ => {
+            writeln!(out, "✗ 无效的状态")?;
+            return Ok(None);
+        }
+    };
+    Ok(Some(state))
+}
+
+/// 运行交互式菜单
+///
+/// 输入输出通过参数传入，main中传入标准输入输出，
+/// 也可以传入内存缓冲区以便嵌入或自动化驱动。
+/// 输入在任何提示处结束（管道输入读完或按下Ctrl-D）时与选择退出相同
+pub fn run_menu<R: BufRead, W: Write>(pm: &mut ProcessManager, input: &mut R, out: &mut W) -> io::Result<()> {
+    match menu_loop(pm, input, out) {
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+            writeln!(out, "\n输入已结束，退出程序")?;
+            writeln!(out, "{}", pm.summary())?;
+            writeln!(out, "感谢使用！再见！")
+        }
+        result => result,
+    }
+}
+
+/// 菜单主循环：选择退出时返回Ok，输入结束或输入输出出错时返回错误
+fn menu_loop<R: BufRead, W: Write>(pm: &mut ProcessManager, input: &mut R, out: &mut W) -> io::Result<()> {
     loop {
-        println!("\n╔══════════════════════════════════════════════════════╗");
-        println!("║                    主菜单                            ║");
-        println!("╠══════════════════════════════════════════════════════╣");
-        println!("║  1. 创建进程                                         ║");
-        println!("║  2. 撤销进程                                         ║");
-        println!("║  3. 时间片到                                         ║");
-        println!("║  4. 挂起进程                                         ║");
-        println!("║  5. 激活进程                                         ║");
-        println!("║  6. 执行一次调度周期                                  ║");
-        println!("║  7. 显示系统状态                                      ║");
-        println!("║  8. 生成快照                                         ║");
-        println!("║  9. 批量创建测试进程                                  ║");
-        println!("║  s. 显示调度器统计信息                                ║");
-        println!("║  0. 退出                                             ║");
-        println!("╚══════════════════════════════════════════════════════╝");
-        print!("请选择操作 (0-9): ");
-
-        use std::io::{self, Write};
-        io::stdout().flush().unwrap();
-
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
-        
-        let choice = input.trim();
-
-        match choice {
-            "1" => {
-                print!("请输入优先级 (0-10): ");
-                io::stdout().flush().unwrap();
-                let mut priority_input = String::new();
-                io::stdin().read_line(&mut priority_input).unwrap();
-                
-                match priority_input.trim().parse::<u32>() {
+        print_menu(out)?;
+        let choice = prompt(input, out, "请选择操作 (0-9): ")?;
+        if !execute_choice(pm, &choice, input, out)? {
+            return Ok(());
+        }
+    }
+}
+
+/// 把原语通过logger输出的消息转写到菜单的输出流
+///
+/// 每次写入前先转写已捕获的消息，因此原语的消息与菜单自身的提示按实际发生的顺序出现
+struct LogForwarder<'a, W: Write> {
+    out: &'a mut W,
+}
+
+impl<W: Write> LogForwarder<'_, W> {
+    fn forward(&mut self) -> io::Result<()> {
+        let captured = logger::take_captured();
+        self.out.write_all(captured.as_bytes())
+    }
+}
+
+impl<W: Write> Write for LogForwarder<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.forward()?;
+        self.out.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.forward()?;
+        self.out.flush()
+    }
+}
+
+/// 执行一个主菜单选项（会修改状态的选项先记录撤销点），返回是否继续运行（选择退出时为false）
+///
+/// 选项需要的参数从input读取，供文本菜单和TUI共用；执行期间原语的输出也写到out
+pub fn execute_choice<R: BufRead, W: Write>(pm: &mut ProcessManager, choice: &str, input: &mut R, out: &mut W) -> io::Result<bool> {
+    logger::start_capture();
+    let mut forwarder = LogForwarder { out };
+    let result = dispatch_choice(pm, choice, input, &mut forwarder);
+    let flushed = forwarder.flush();
+    logger::stop_capture();
+    let keep_running = result?;
+    flushed?;
+    Ok(keep_running)
+}
+
+fn dispatch_choice<R: BufRead, W: Write>(pm: &mut ProcessManager, choice: &str, input: &mut R, out: &mut W) -> io::Result<bool> {
+    if UNDOABLE_KEYS.contains(&choice.to_ascii_lowercase().as_str()) {
+        pm.checkpoint();
+    }
+
+    match choice {
+        "1" => {
+            let priority_message = format!("请输入优先级 ({}): ", pm.priority_range);
+            let Some(priority) = read_u32(input, out, &priority_message, "优先级")? else {
+                return Ok(true);
+            };
+
+            let Some(burst) = read_u32(input, out, "请输入运行时间: ", "运行时间")? else {
+                return Ok(true);
+            };
+            if burst == 0 {
+                writeln!(out, "✗ 运行时间必须大于0")?;
+                return Ok(true);
+            }
+
+            let deadline_input = prompt(
+                input,
+                out,
+                &format!("请输入截止时间（绝对系统时间，当前为 {}，直接回车表示无）: ", pm.scheduler.current_time()),
+            )?;
+            let deadline = if deadline_input.is_empty() {
+                None
+            } else {
+                match deadline_input.parse::<u32>() {
+                    Ok(deadline) => Some(deadline),
+                    Err(_) => {
+                        writeln!(out, "✗ 无效的截止时间")?;
+                        return Ok(true);
+                    }
+                }
+            };
+
+            let memory_input = prompt(
+                input,
+                out,
+                &format!("请输入内存需求KB（直接回车为 {}）: ", DEFAULT_MEMORY_KB),
+            )?;
+            let memory_kb = if memory_input.is_empty() {
+                DEFAULT_MEMORY_KB
+            } else {
+                match memory_input.parse::<usize>() {
+                    Ok(memory_kb) if memory_kb > 0 => memory_kb,
+                    _ => {
+                        writeln!(out, "✗ 无效的内存需求")?;
+                        return Ok(true);
+                    }
+                }
+            };
+
+            match pm.create_process_with_memory(priority, burst, deadline, memory_kb) {
+                Ok(pid) => writeln!(out, "✓ 进程 {} 创建成功", pid)?,
+                Err(ProcessError::PcbPoolFull) => {
+                    writeln!(out, "✗ 错误: PCB池已满，请先撤销部分进程")?
+                }
+                Err(e) => writeln!(out, "✗ 错误: {}", e)?,
+            }
+        }
+        "2" => {
+            if let Some(pid) = prompt_pid(input, out, "请输入要撤销的进程PID: ")? {
+                // 会级联撤销子进程时先列出范围并确认
+                if let Ok(plan) = pm.plan_terminate(pid) {
+                    if plan.len() > 1 && !confirm_plan(input, out, &plan)? {
+                        return Ok(true);
+                    }
+                }
+                match pm.terminate_process(pid) {
+                    Ok(ended) if ended.len() > 1 => {
+                        let children: Vec<String> = ended[1..].iter().map(|pid| pid.to_string()).collect();
+                        writeln!(out, "✓ 撤销进程 {} 及其子进程 {}", pid, children.join(", "))?;
+                    }
+                    Ok(_) => {}
+                    Err(e) => writeln!(out, "✗ 错误: {}", e)?,
+                }
+            }
+        }
+        "3" => {
+            match pm.time_slice_expired() {
+                Ok(_) => {}
+                Err(ProcessError::NoRunningProcess) => {
+                    writeln!(out, "✗ 错误: 没有正在运行的进程，可先执行调度周期（选项6）")?
+                }
+                Err(e) => writeln!(out, "✗ 错误: {}", e)?,
+            }
+        }
+        "4" => {
+            if let Some(pid) = prompt_pid(input, out, "请输入要挂起的进程PID: ")? {
+                match pm.suspend_process(pid) {
+                    Ok(_) => {}
+                    Err(ProcessError::AlreadySuspended(pid)) => {
+                        writeln!(out, "✗ 错误: 进程 {} 已被挂起，可使用选项5激活", pid)?
+                    }
+                    Err(e) => writeln!(out, "✗ 错误: {}", e)?,
+                }
+            }
+        }
+        "5" => {
+            if let Some(pid) = prompt_pid(input, out, "请输入要激活的进程PID: ")? {
+                match pm.activate_process(pid) {
+                    Ok(_) => {}
+                    Err(ProcessError::NotInWaiting(pid)) => {
+                        writeln!(out, "✗ 错误: 进程 {} 不在等待队列中，无需激活", pid)?
+                    }
+                    Err(e) => writeln!(out, "✗ 错误: {}", e)?,
+                }
+            }
+        }
+        "t" | "T" => {
+            if let Some(pid) = prompt_pid(input, out, "请输入要阻塞的进程PID: ")? {
+                if let Some(event_id) = prompt_event(input, out)? {
+                    if let Err(e) = pm.block_on(pid, event_id) {
+                        writeln!(out, "✗ 错误: {}", e)?;
+                    }
+                }
+            }
+        }
+        "u" | "U" => {
+            if let Some(event_id) = prompt_event(input, out)? {
+                let woken = pm.wakeup_event(event_id);
+                if woken.is_empty() {
+                    writeln!(out, "没有进程在等待事件 {}", event_id)?;
+                } else {
+                    writeln!(out, "✓ 事件 {} 唤醒了进程: {:?}", event_id, woken)?;
+                }
+            }
+        }
+        "m" | "M" => {
+            writeln!(out, "\n请选择信号量操作:")?;
+            writeln!(out, "  1. 创建信号量")?;
+            writeln!(out, "  2. P操作（wait）")?;
+            writeln!(out, "  3. V操作（signal）")?;
+            writeln!(out, "  4. 设置优先级天花板")?;
+            let sem_choice = prompt(input, out, "选择 (1-4): ")?;
+
+            match sem_choice.as_str() {
+                "1" => {
+                    let initial_input = prompt(input, out, "请输入信号量初值: ")?;
+                    match initial_input.parse::<i32>() {
+                        Ok(initial) => {
+                            pm.sem_create(initial);
+                        }
+                        Err(_) => writeln!(out, "✗ 无效的初值")?,
+                    }
+                }
+                "2" => {
+                    if let Some(sem_id) = prompt_sem(input, out)? {
+                        if let Some(pid) = prompt_pid(input, out, "请输入执行P操作的进程PID: ")? {
+                            if let Err(e) = pm.sem_wait(sem_id, pid) {
+                                writeln!(out, "✗ 错误: {}", e)?;
+                            }
+                        }
+                    }
+                }
+                "3" => {
+                    if let Some(sem_id) = prompt_sem(input, out)? {
+                        if let Err(e) = pm.sem_signal(sem_id) {
+                            writeln!(out, "✗ 错误: {}", e)?;
+                        }
+                    }
+                }
+                "4" => {
+                    if let Some(sem_id) = prompt_sem(input, out)? {
+                        let ceiling_input = prompt(input, out, &format!("请输入天花板优先级 ({}，直接回车取消): ", pm.priority_range))?;
+                        let ceiling = match ceiling_input.as_str() {
+                            "" => Ok(None),
+                            text => parse_u32(text, "天花板优先级").map(Some),
+                        };
+                        match ceiling {
+                            Ok(ceiling) => {
+                                if let Err(e) = pm.set_sem_ceiling(sem_id, ceiling) {
+                                    writeln!(out, "✗ 错误: {}", e)?;
+                                }
+                            }
+                            Err(message) => writeln!(out, "✗ {}", message)?,
+                        }
+                    }
+                }
+                _ => writeln!(out, "✗ 无效选择")?,
+            }
+        }
+        "d" | "D" => {
+            let deadlocked = pm.detect_deadlock();
+            if deadlocked.is_empty() {
+                writeln!(out, "无死锁")?;
+            } else {
+                writeln!(out, "检测到死锁，涉及进程: {:?}", deadlocked)?;
+            }
+        }
+        "6" => {
+            let count_input = prompt(input, out, "请输入周期数（直接回车执行1次）: ")?;
+            match count_input.as_str() {
+                "" | "1" => pm.run_one_cycle(),
+                _ => match count_input.parse::<u32>() {
+                    Ok(count) => pm.run_cycles(count),
+                    Err(_) => writeln!(out, "✗ 无效的周期数")?,
+                },
+            }
+        }
+        "a" | "A" => {
+            if let Some(pid) = prompt_pid(input, out, "请输入要修改优先级的进程PID: ")? {
+                let priority_input = prompt(input, out, &format!("请输入新的优先级 ({}): ", pm.priority_range))?;
+                match priority_input.parse::<u32>() {
                     Ok(priority) => {
-                        match pm.create_process(priority) {
-                            Ok(pid) => println!("✓ 进程 {} 创建成功", pid),
-                            Err(e) => println!("✗ 错误: {}", e),
+                        if let Err(e) = pm.change_priority(pid, priority) {
+                            writeln!(out, "✗ 错误: {}", e)?;
                         }
                     }
-                    Err(_) => println!("✗ 无效的优先级"),
+                    Err(_) => writeln!(out, "✗ 无效的优先级")?,
+                }
+            }
+        }
+        "19" => {
+            let limit_input = prompt(input, out, &format!("请输入最大周期数（直接回车为 {}）: ", MAX_RUN_CYCLES))?;
+            let max_cycles = if limit_input.is_empty() {
+                MAX_RUN_CYCLES
+            } else {
+                match limit_input.parse::<u32>() {
+                    Ok(limit) if limit > 0 => limit,
+                    _ => {
+                        writeln!(out, "✗ 无效的周期数")?;
+                        return Ok(true);
+                    }
+                }
+            };
+            let cycles = pm.run_until_idle(max_cycles);
+            if pm.has_runnable_work() {
+                writeln!(out, "⚠ 已达到最大周期数 {}，仍有进程未完成", max_cycles)?;
+            } else {
+                writeln!(out, "✓ 共执行 {} 个调度周期，已无可运行的进程", cycles)?;
+            }
+            writeln!(out, "{}", pm.summary())?;
+        }
+        "24" => {
+            let frames_input = prompt(input, out, "请输入刷新帧数（直接回车为 10）: ")?;
+            let frames = match frames_input.as_str() {
+                "" => 10,
+                text => match text.parse::<u32>() {
+                    Ok(frames) if frames > 0 => frames,
+                    _ => {
+                        writeln!(out, "✗ 无效的帧数")?;
+                        return Ok(true);
+                    }
+                },
+            };
+            let cycles_input = prompt(input, out, "请输入每帧执行的调度周期数（直接回车为 1）: ")?;
+            let cycles_per_frame = match cycles_input.as_str() {
+                "" => 1,
+                text => match text.parse::<u32>() {
+                    Ok(cycles) if cycles > 0 => cycles,
+                    _ => {
+                        writeln!(out, "✗ 无效的周期数")?;
+                        return Ok(true);
+                    }
+                },
+            };
+            let shown = pm.run_dashboard(frames, cycles_per_frame, out)?;
+            writeln!(out, "✓ 监控结束，共刷新 {} 帧", shown)?;
+        }
+        "25" => {
+            let enabled = !pm.use_color;
+            pm.set_use_color(enabled);
+            writeln!(out, "✓ 彩色状态显示已{}", if enabled { "开启" } else { "关闭" })?;
+        }
+        "26" => {
+            writeln!(out, "\n请选择操作:")?;
+            writeln!(out, "  1. 查看队列长度历史")?;
+            writeln!(out, "  2. 导出为CSV")?;
+            writeln!(out, "  3. 清空历史")?;
+            match prompt(input, out, "选择 (1-3): ")?.as_str() {
+                "1" => pm.print_queue_history(out)?,
+                "2" => {
+                    let path = prompt(input, out, "请输入CSV保存路径: ")?;
+                    match pm.export_queue_history(&path) {
+                        Ok(_) => writeln!(out, "✓ 队列长度历史已导出到 {}", path)?,
+                        Err(e) => writeln!(out, "✗ 导出失败: {}", e)?,
+                    }
+                }
+                "3" => {
+                    pm.queue_history.clear();
+                    writeln!(out, "✓ 队列长度历史已清空")?;
+                }
+                _ => writeln!(out, "✗ 无效选择")?,
+            }
+        }
+        "27" => {
+            if let Some(pid) = prompt_pid(input, out, "请输入要阻塞的运行进程PID: ")? {
+                match pm.block(pid) {
+                    Ok(_) => {}
+                    Err(ProcessError::NotRunning(pid)) => {
+                        writeln!(out, "✗ 错误: 进程 {} 不是当前运行进程，block只能作用于运行进程", pid)?
+                    }
+                    Err(e) => writeln!(out, "✗ 错误: {}", e)?,
+                }
+            }
+        }
+        "28" => {
+            if let Some(pid) = prompt_pid(input, out, "请输入要唤醒的进程PID: ")? {
+                if let Err(e) = pm.wakeup(pid) {
+                    writeln!(out, "✗ 错误: {}", e)?;
                 }
             }
-            "2" => {
-                print!("请输入要撤销的进程PID: ");
-                io::stdout().flush().unwrap();
-                let mut pid_input = String::new();
-                io::stdin().read_line(&mut pid_input).unwrap();
-                
-                match pid_input.trim().parse::<u32>() {
-                    Ok(pid) => {
-                        match pm.terminate_process(pid) {
-                            Ok(_) => {},
-                            Err(e) => println!("✗ 错误: {}", e),
+        }
+        "29" => {
+            writeln!(out, "\n请选择比较使用的负载:")?;
+            writeln!(out, "  1. 当前总链中的进程（运行时间取剩余运行时间）")?;
+            writeln!(out, "  2. 按种子生成随机负载")?;
+            writeln!(out, "  3. 从当前状态分叉（完整复制当前系统后运行）")?;
+            let workload = match prompt(input, out, "选择 (1-3): ")?.as_str() {
+                "1" => pm.current_workload(),
+                "3" => {
+                    writeln!(out, "各策略均在当前系统的副本上从系统时间 {} 继续运行至空闲（统计包含分叉前的部分）",
+                             pm.scheduler.current_time())?;
+                    let results = pm.compare_policies_forked(&SchedulingPolicy::all());
+                    ProcessManager::print_policy_comparison(&results, out)?;
+                    return Ok(true);
+                }
+                "2" => {
+                    let count_input = prompt(input, out, "请输入进程数量: ")?;
+                    let seed_input = prompt(input, out, "请输入随机种子: ")?;
+                    match (count_input.parse::<usize>(), seed_input.parse::<u64>()) {
+                        (Ok(count), Ok(seed)) => random_specs(count, seed, pm.priority_range),
+                        _ => {
+                            writeln!(out, "✗ 无效的数量或种子")?;
+                            return Ok(true);
                         }
                     }
-                    Err(_) => println!("✗ 无效的PID"),
                 }
+                _ => {
+                    writeln!(out, "✗ 无效选择")?;
+                    return Ok(true);
+                }
+            };
+            if workload.is_empty() {
+                writeln!(out, "✗ 负载为空，没有可比较的进程")?;
+                return Ok(true);
+            }
+            writeln!(out, "负载共 {} 个进程，各策略均从系统时间0开始运行至空闲", workload.len())?;
+            let results = pm.compare_policies(&workload, &SchedulingPolicy::all());
+            ProcessManager::print_policy_comparison(&results, out)?;
+        }
+        "30" => {
+            let priority_message = format!("请输入优先级 ({}): ", pm.priority_range);
+            let Some(priority) = read_u32(input, out, &priority_message, "优先级")? else {
+                return Ok(true);
+            };
+            let Some(burst) = read_u32(input, out, "请输入运行时间: ", "运行时间")? else {
+                return Ok(true);
+            };
+            if burst == 0 {
+                writeln!(out, "✗ 运行时间必须大于0")?;
+                return Ok(true);
+            }
+            let arrival_message = format!("请输入到达时间（绝对系统时间，当前为 {}）: ", pm.scheduler.current_time());
+            let Some(arrival_time) = read_u32(input, out, &arrival_message, "到达时间")? else {
+                return Ok(true);
+            };
+            match pm.schedule_arrival(ProcessSpec { priority, burst, arrival_time }) {
+                Ok(Some(pid)) => writeln!(out, "✓ 到达时间已过，进程 {} 已立即创建", pid)?,
+                Ok(None) => {}
+                Err(e) => writeln!(out, "✗ 错误: {}", e)?,
             }
-            "3" => {
-                match pm.time_slice_expired() {
-                    Ok(_) => {},
-                    Err(e) => println!("✗ 错误: {}", e),
+        }
+        "7" => {
+            pm.print_status(out)?;
+        }
+        "8" => {
+            writeln!(out, "\n请选择快照类型:")?;
+            writeln!(out, "  1. 初始化快照")?;
+            writeln!(out, "  2. 运行前快照")?;
+            writeln!(out, "  3. 运行后快照")?;
+            let snapshot_choice = prompt(input, out, "选择 (1-3): ")?;
+
+            let snapshot = match snapshot_choice.as_str() {
+                "1" => pm.get_snapshot("初始化快照"),
+                "2" => pm.get_snapshot("运行前快照"),
+                "3" => pm.get_snapshot("运行后快照"),
+                _ => {
+                    writeln!(out, "✗ 无效选择")?;
+                    return Ok(true);
                 }
+            };
+
+            writeln!(out, "{}", snapshot)?;
+        }
+        "j" | "J" => {
+            writeln!(out, "{}", pm.get_snapshot("当前快照").to_json())?;
+        }
+        "k" | "K" => {
+            let path = prompt(input, out, "请输入快照保存路径: ")?;
+            match pm.get_snapshot("保存的快照").save(&path) {
+                Ok(_) => writeln!(out, "✓ 快照已保存到 {}", path)?,
+                Err(e) => writeln!(out, "✗ 保存失败: {}", e)?,
             }
-            "4" => {
-                print!("请输入要挂起的进程PID: ");
-                io::stdout().flush().unwrap();
-                let mut pid_input = String::new();
-                io::stdin().read_line(&mut pid_input).unwrap();
-                
-                match pid_input.trim().parse::<u32>() {
-                    Ok(pid) => {
-                        match pm.suspend_process(pid) {
-                            Ok(_) => {},
-                            Err(e) => println!("✗ 错误: {}", e),
+        }
+        "r" | "R" => {
+            let path = prompt(input, out, "请输入快照文件路径: ")?;
+            match Snapshot::load(&path) {
+                Ok(snapshot) => match pm.restore_from_snapshot(snapshot) {
+                    Ok(_) => writeln!(out, "✓ 已从快照 {} 恢复", path)?,
+                    Err(e) => writeln!(out, "✗ 错误: {}", e)?,
+                },
+                Err(e) => writeln!(out, "✗ 读取快照失败: {}", e)?,
+            }
+        }
+        "20" => {
+            let before_path = prompt(input, out, "请输入之前的快照文件路径: ")?;
+            let after_path = prompt(input, out, "请输入之后的快照文件路径（直接回车表示当前状态）: ")?;
+            let before = match Snapshot::load(&before_path) {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    writeln!(out, "✗ 读取快照失败: {}", e)?;
+                    return Ok(true);
+                }
+            };
+            let after = if after_path.is_empty() {
+                pm.get_snapshot("当前状态")
+            } else {
+                match Snapshot::load(&after_path) {
+                    Ok(snapshot) => snapshot,
+                    Err(e) => {
+                        writeln!(out, "✗ 读取快照失败: {}", e)?;
+                        return Ok(true);
+                    }
+                }
+            };
+            writeln!(out, "{}", before.diff(&after))?;
+        }
+        "21" => {
+            writeln!(out, "\n自动快照（共 {} 个，容量 {}）:", pm.auto_snapshots.len(), pm.auto_snapshot_capacity)?;
+            for (i, snapshot) in pm.auto_snapshots.iter().enumerate() {
+                writeln!(out, "  [{}] {}", i + 1, snapshot.name())?;
+            }
+            writeln!(out, "  1. 查看快照")?;
+            writeln!(out, "  2. 从快照恢复")?;
+            writeln!(out, "  3. 设置容量")?;
+            let auto_choice = prompt(input, out, "选择: ")?;
+
+            if auto_choice == "3" {
+                match prompt(input, out, "请输入新的容量（0表示关闭）: ")?.parse::<usize>() {
+                    Ok(capacity) => {
+                        pm.set_auto_snapshot_capacity(capacity);
+                        writeln!(out, "✓ 自动快照容量已设置为 {}", capacity)?;
+                    }
+                    Err(_) => writeln!(out, "✗ 无效的容量")?,
+                }
+                return Ok(true);
+            }
+            if auto_choice != "1" && auto_choice != "2" {
+                writeln!(out, "✗ 无效选择")?;
+                return Ok(true);
+            }
+
+            let number = prompt(input, out, "请输入快照编号: ")?.parse::<usize>().ok();
+            let Some(snapshot) = number
+                .and_then(|n| n.checked_sub(1))
+                .and_then(|index| pm.auto_snapshots.get(index))
+                .cloned() else {
+                writeln!(out, "✗ 无效的快照编号")?;
+                return Ok(true);
+            };
+            if auto_choice == "1" {
+                writeln!(out, "{}", snapshot)?;
+            } else {
+                pm.checkpoint();
+                match pm.restore_from_snapshot(snapshot) {
+                    Ok(_) => writeln!(out, "✓ 已从自动快照恢复")?,
+                    Err(e) => writeln!(out, "✗ 错误: {}", e)?,
+                }
+            }
+        }
+        "23" => {
+            writeln!(out, "\n请选择消息操作:")?;
+            writeln!(out, "  1. 发送消息")?;
+            writeln!(out, "  2. 接收消息（信箱为空时直接返回）")?;
+            writeln!(out, "  3. 接收消息（信箱为空时阻塞等待）")?;
+            let message_choice = prompt(input, out, "选择 (1-3): ")?;
+
+            match message_choice.as_str() {
+                "1" => {
+                    let Some(from) = prompt_pid(input, out, "请输入发送进程PID: ")? else {
+                        return Ok(true);
+                    };
+                    let Some(to) = prompt_pid(input, out, "请输入接收进程PID: ")? else {
+                        return Ok(true);
+                    };
+                    let payload = prompt(input, out, "请输入消息内容: ")?;
+                    if let Err(e) = pm.send_message(from, to, payload) {
+                        writeln!(out, "✗ 错误: {}", e)?;
+                    }
+                }
+                "2" | "3" => {
+                    let Some(pid) = prompt_pid(input, out, "请输入接收进程PID: ")? else {
+                        return Ok(true);
+                    };
+                    let received = if message_choice == "2" {
+                        if pm.total_chain.contains_key(&pid) {
+                            Ok(pm.receive_message(pid))
+                        } else {
+                            Err(ProcessError::ProcessNotFound(pid))
                         }
+                    } else {
+                        pm.receive_or_block(pid)
+                    };
+                    match received {
+                        Ok(Some(message)) => writeln!(out, "✓ {}", message)?,
+                        Ok(None) if message_choice == "2" => writeln!(out, "进程 {} 的信箱为空", pid)?,
+                        Ok(None) => {}
+                        Err(e) => writeln!(out, "✗ 错误: {}", e)?,
                     }
-                    Err(_) => println!("✗ 无效的PID"),
                 }
+                _ => writeln!(out, "✗ 无效选择")?,
             }
-            "5" => {
-                print!("请输入要激活的进程PID: ");
-                io::stdout().flush().unwrap();
-                let mut pid_input = String::new();
-                io::stdin().read_line(&mut pid_input).unwrap();
-                
-                match pid_input.trim().parse::<u32>() {
-                    Ok(pid) => {
-                        match pm.activate_process(pid) {
-                            Ok(_) => {},
-                            Err(e) => println!("✗ 错误: {}", e),
+        }
+        "22" => {
+            writeln!(out, "\n=== 伙伴树 (F: 空闲, A: 已分配) ===")?;
+            writeln!(out, "{}", pm.pcb_pool.render_tree())?;
+        }
+        "9" => {
+            writeln!(out, "创建5个测试进程...")?;
+            for i in 1..=5 {
+                let priority = i as u32;
+                let burst = priority * 2;
+                match pm.create_process(priority, burst) {
+                    Ok(pid) => writeln!(out, "  ✓ 进程 {} 创建成功 (优先级: {}, 运行时间: {})", pid, priority, burst)?,
+                    Err(e) => writeln!(out, "  ✗ 创建进程失败: {}", e)?,
+                }
+            }
+        }
+        "y" | "Y" => {
+            if let Some(pid) = prompt_pid(input, out, "请输入父进程PID: ")? {
+                if let Err(e) = pm.fork(pid) {
+                    writeln!(out, "✗ 错误: {}", e)?;
+                }
+            }
+        }
+        "z" | "Z" => {
+            pm.print_process_tree(out)?;
+        }
+        "c" | "C" => {
+            if let Some(pid) = prompt_pid(input, out, "请输入进入临界区的进程PID: ")? {
+                if let Err(e) = pm.enter_critical(pid) {
+                    writeln!(out, "✗ 错误: {}", e)?;
+                }
+            }
+        }
+        "v" | "V" => {
+            if let Some(pid) = prompt_pid(input, out, "请输入离开临界区的进程PID: ")? {
+                if let Err(e) = pm.leave_critical(pid) {
+                    writeln!(out, "✗ 错误: {}", e)?;
+                }
+            }
+        }
+        "s" | "S" => {
+            pm.show_scheduler_stats(out)?;
+        }
+        "g" | "G" => {
+            pm.print_gantt_chart(out)?;
+        }
+        "b" | "B" => {
+            pm.print_buddy_status(out)?;
+        }
+        "h" | "H" => {
+            pm.print_alloc_history(out)?;
+        }
+        "o" | "O" => {
+            pm.print_ready_order(out)?;
+        }
+        "f" | "F" => {
+            pm.fair_activate = !pm.fair_activate;
+            writeln!(out, "✓ 公平激活模式已{}", if pm.fair_activate { "开启" } else { "关闭" })?;
+        }
+        "x" | "X" => {
+            pm.suspend_policy = pm.suspend_policy.next();
+            writeln!(out, "✓ 挂起策略已切换为: {}", pm.suspend_policy.name())?;
+        }
+        "31" => {
+            let strategy = pm.pcb_pool.strategy().next();
+            pm.pcb_pool.set_strategy(strategy);
+            writeln!(out, "✓ 伙伴系统分配策略已切换为: {}", strategy.name())?;
+        }
+        "32" => {
+            writeln!(out, "\n事件日志（共 {} 个事件）:", pm.event_log.len())?;
+            writeln!(out, "  1. 查看日志")?;
+            writeln!(out, "  2. 导出日志到文件")?;
+            writeln!(out, "  3. 重放日志文件并与当前状态比较")?;
+            writeln!(out, "  4. 重放日志文件并替换当前系统")?;
+            let log_choice = prompt(input, out, "选择: ")?;
+
+            match log_choice.as_str() {
+                "1" => pm.print_event_log(out)?,
+                "2" => {
+                    let path = prompt(input, out, "请输入导出路径: ")?;
+                    match pm.export_event_log(&path) {
+                        Ok(_) => writeln!(out, "✓ 已导出 {} 个事件到 {}", pm.event_log.len(), path)?,
+                        Err(e) => writeln!(out, "✗ 导出失败: {}", e)?,
+                    }
+                }
+                "3" | "4" => {
+                    let path = prompt(input, out, "请输入日志文件路径: ")?;
+                    let (replayed, report) = match ProcessManager::replay(&path) {
+                        Ok(result) => result,
+                        Err(e) => {
+                            writeln!(out, "✗ 重放失败: {}", e)?;
+                            return Ok(true);
+                        }
+                    };
+                    writeln!(out, "{}", report)?;
+                    if log_choice == "3" {
+                        if replayed.same_state(pm) {
+                            writeln!(out, "✓ 重放后的状态与当前状态完全相同")?;
+                        } else {
+                            writeln!(out, "✗ 重放后的状态与当前状态不同")?;
+                            let diff = pm.get_snapshot("当前状态").diff(&replayed.get_snapshot("重放结果"));
+                            writeln!(out, "{}", diff)?;
                         }
+                    } else {
+                        let use_color = pm.use_color;
+                        let undo_stack = std::mem::take(&mut pm.undo_stack);
+                        *pm = replayed;
+                        pm.undo_stack = undo_stack;
+                        pm.set_use_color(use_color);
+                        writeln!(out, "✓ 已用重放结果替换当前系统")?;
                     }
-                    Err(_) => println!("✗ 无效的PID"),
                 }
+                _ => writeln!(out, "✗ 无效选择")?,
             }
-            "6" => {
-                pm.run_one_cycle();
+        }
+        "33" => {
+            pm.print_groups(out)?;
+            writeln!(out, "\n请选择进程组操作:")?;
+            writeln!(out, "  1. 创建进程组（指定组长）")?;
+            writeln!(out, "  2. 加入进程组")?;
+            writeln!(out, "  3. 离开进程组")?;
+            writeln!(out, "  4. 挂起整组")?;
+            writeln!(out, "  5. 激活整组")?;
+            writeln!(out, "  6. 撤销整组")?;
+            let group_choice = prompt(input, out, "选择 (1-6): ")?;
+
+            match group_choice.as_str() {
+                "1" => {
+                    if let Some(leader) = prompt_pid(input, out, "请输入组长PID: ")? {
+                        if let Err(e) = pm.create_group(leader) {
+                            writeln!(out, "✗ 错误: {}", e)?;
+                        }
+                    }
+                }
+                "2" => {
+                    if let Some(pid) = prompt_pid(input, out, "请输入要加入的进程PID: ")? {
+                        if let Some(gid) = prompt_group(input, out)? {
+                            if let Err(e) = pm.join_group(pid, gid) {
+                                writeln!(out, "✗ 错误: {}", e)?;
+                            }
+                        }
+                    }
+                }
+                "3" => {
+                    if let Some(pid) = prompt_pid(input, out, "请输入要离开的进程PID: ")? {
+                        if let Err(e) = pm.leave_group(pid) {
+                            writeln!(out, "✗ 错误: {}", e)?;
+                        }
+                    }
+                }
+                "4" | "5" | "6" => {
+                    if let Some(gid) = prompt_group(input, out)? {
+                        let result = match group_choice.as_str() {
+                            "4" => pm.suspend_group(gid),
+                            "5" => pm.activate_group(gid),
+                            _ => pm.terminate_group(gid),
+                        };
+                        if let Err(e) = result {
+                            writeln!(out, "✗ 错误: {}（整组未做任何修改）", e)?;
+                        }
+                    }
+                }
+                _ => writeln!(out, "✗ 无效选择")?,
             }
-            "7" => {
-                pm.print_status();
-            }
-            "8" => {
-                println!("\n请选择快照类型:");
-                println!("  1. 初始化快照");
-                println!("  2. 运行前快照");
-                println!("  3. 运行后快照");
-                print!("选择 (1-3): ");
-                io::stdout().flush().unwrap();
-                
-                let mut snapshot_choice = String::new();
-                io::stdin().read_line(&mut snapshot_choice).unwrap();
-                
-                let snapshot = match snapshot_choice.trim() {
-                    "1" => pm.get_snapshot("初始化快照"),
-                    "2" => pm.get_snapshot("运行前快照"),
-                    "3" => pm.get_snapshot("运行后快照"),
-                    _ => {
-                        println!("✗ 无效选择");
-                        continue;
+        }
+        "e" | "E" => {
+            let cost_input = prompt(input, out, "请输入每个时间单位的能耗: ")?;
+            match cost_input.parse::<u32>() {
+                Ok(cost) => {
+                    pm.scheduler.set_energy_per_tick(cost);
+                    writeln!(out, "✓ 每时间单位能耗已设置为 {}", cost)?;
+                }
+                Err(_) => {
+                    writeln!(out, "✗ 无效的能耗值")?;
+                    return Ok(true);
+                }
+            }
+            let message = format!("请输入每个空闲时间单位的能耗（直接回车保持 {}）: ", pm.scheduler.idle_energy_per_tick());
+            let idle_input = prompt(input, out, &message)?;
+            if idle_input.is_empty() {
+                return Ok(true);
+            }
+            match idle_input.parse::<u32>() {
+                Ok(cost) => {
+                    pm.scheduler.set_idle_energy_per_tick(cost);
+                    writeln!(out, "✓ 每空闲时间单位能耗已设置为 {}", cost)?;
+                }
+                Err(_) => writeln!(out, "✗ 无效的能耗值")?,
+            }
+        }
+        "q" | "Q" => {
+            let message = format!("请输入优先级（直接回车设置默认时间片，当前: {}）: ", pm.scheduler.default_time_slice());
+            let priority_input = prompt(input, out, &message)?;
+            if priority_input.is_empty() {
+                let slice_input = prompt(input, out, "请输入默认时间片: ")?;
+                match slice_input.parse::<u32>() {
+                    Ok(slice) => match pm.scheduler.set_default_time_slice(slice) {
+                        Ok(()) => writeln!(out, "✓ 默认时间片已设置为 {}，之后新建或重新排队的进程使用该时间片", slice)?,
+                        Err(e) => writeln!(out, "✗ {}", e)?,
+                    },
+                    Err(_) => writeln!(out, "✗ 无效的时间片（必须为正整数）")?,
+                }
+                return Ok(true);
+            }
+            let priority = match priority_input.parse::<u32>() {
+                Ok(priority) => priority,
+                Err(_) => {
+                    writeln!(out, "✗ 无效的优先级")?;
+                    return Ok(true);
+                }
+            };
+
+            let slice_input = prompt(input, out, "请输入该优先级的时间片: ")?;
+            match slice_input.parse::<u32>() {
+                Ok(slice) => match pm.scheduler.set_time_slice(priority, slice) {
+                    Ok(()) => {
+                        writeln!(out, "✓ 优先级 {} 的时间片已设置为 {}", priority, slice)?;
+                        writeln!(out, "当前时间片表（未列出的优先级使用默认时间片）:")?;
+                        for (priority, slice) in pm.scheduler.time_slice_table() {
+                            writeln!(out, "  优先级 {}: {}", priority, slice)?;
+                        }
                     }
-                };
-                
-                println!("{}", snapshot);
+                    Err(e) => writeln!(out, "✗ {}", e)?,
+                },
+                Err(_) => writeln!(out, "✗ 无效的时间片（必须为正整数）")?,
+            }
+        }
+        "w" | "W" => {
+            let path = prompt(input, out, "请输入保存路径: ")?;
+            match pm.save_state(&path) {
+                Ok(_) => writeln!(out, "✓ 系统状态已保存到 {}", path)?,
+                Err(e) => writeln!(out, "✗ 保存失败: {}", e)?,
+            }
+        }
+        "17" => {
+            let path = prompt(input, out, "请输入CSV保存路径: ")?;
+            match pm.export_csv(&path) {
+                Ok(_) => writeln!(out, "✓ 进程表已导出到 {}", path)?,
+                Err(e) => writeln!(out, "✗ 导出失败: {}", e)?,
             }
-            "9" => {
-                println!("创建5个测试进程...");
-                for i in 1..=5 {
-                    let priority = i as u32;
-                    match pm.create_process(priority) {
-                        Ok(pid) => println!("  ✓ 进程 {} 创建成功 (优先级: {})", pid, priority),
-                        Err(e) => println!("  ✗ 创建进程失败: {}", e),
+        }
+        "18" => {
+            let level = logger::level().next();
+            logger::set_level(level);
+            writeln!(out, "✓ 日志级别已切换为: {}", level.name())?;
+        }
+        "l" | "L" => {
+            let path = prompt(input, out, "请输入状态文件路径: ")?;
+            match pm.load_state(&path) {
+                Ok(_) => writeln!(out, "✓ 已从 {} 恢复系统状态", path)?,
+                Err(e) => writeln!(out, "✗ 加载失败: {}", e)?,
+            }
+        }
+        "p" | "P" => {
+            let next = pm.scheduler.policy().next();
+            pm.set_policy(next);
+        }
+        "n" | "N" => {
+            writeln!(out, "\n请选择查询类型:")?;
+            writeln!(out, "  1. 按PID查询进程")?;
+            writeln!(out, "  2. 按状态列出进程")?;
+            writeln!(out, "  3. 检查总链与队列的一致性")?;
+            writeln!(out, "  4. 预览就绪队列前N个进程")?;
+            writeln!(out, "  5. 检查泄漏的PCB块")?;
+            writeln!(out, "  6. 饥饿检测")?;
+            writeln!(out, "  7. 调度诊断（为什么无法调度）")?;
+            writeln!(out, "  8. 列出阻塞在某资源上的进程")?;
+            let query_choice = prompt(input, out, "选择: ")?;
+
+            match query_choice.as_str() {
+                "1" => {
+                    if let Some(pid) = prompt_pid(input, out, "请输入要查询的进程PID: ")? {
+                        match pm.query_process(pid) {
+                            Some(view) => writeln!(out, "{}", view)?,
+                            None => writeln!(out, "✗ 进程 {} 不存在", pid)?,
+                        }
+                    }
+                }
+                "2" => {
+                    let Some(state) = prompt_state(input, out)? else {
+                        return Ok(true);
+                    };
+                    let name = state.to_string().to_string();
+                    let pids = pm.list_by_state(state);
+                    if pids.is_empty() {
+                        writeln!(out, "没有处于{}状态的进程", name)?;
+                    } else {
+                        writeln!(out, "处于{}状态的进程: {:?}", name, pids)?;
+                    }
+                }
+                "3" => {
+                    let problems = pm.verify_consistency();
+                    if problems.is_empty() {
+                        writeln!(out, "✓ 总链与各队列一致")?;
+                        return Ok(true);
+                    }
+                    writeln!(out, "发现 {} 处不一致:", problems.len())?;
+                    for problem in &problems {
+                        writeln!(out, "  - {}", problem)?;
+                    }
+                    let answer = prompt(input, out, "是否以总链为准修复？(y/n): ")?;
+                    if answer.eq_ignore_ascii_case("y") {
+                        pm.checkpoint();
+                        let moved = pm.repair_consistency();
+                        writeln!(out, "✓ 已修复，重新放置了 {} 个进程", moved)?;
+                    }
+                }
+                "4" => {
+                    let count = match prompt(input, out, "请输入预览数量: ")?.parse::<usize>() {
+                        Ok(count) if count > 0 => count,
+                        _ => {
+                            writeln!(out, "✗ 无效的数量")?;
+                            return Ok(true);
+                        }
+                    };
+                    let upcoming = pm.preview_ready(count);
+                    if upcoming.is_empty() {
+                        writeln!(out, "就绪队列为空")?;
+                    }
+                    for (position, pcb) in upcoming.iter().enumerate() {
+                        writeln!(out, "  {}. {}", position + 1, pcb.display())?;
                     }
                 }
+                "5" => {
+                    let orphaned = pm.find_orphaned_blocks();
+                    if orphaned.is_empty() {
+                        writeln!(out, "✓ 没有泄漏的PCB块")?;
+                    } else {
+                        writeln!(out, "⚠ 发现 {} 个未被任何进程引用的PCB块: {:?}", orphaned.len(), orphaned)?;
+                    }
+                }
+                "6" => {
+                    let message = format!("请输入阈值周期数（直接回车为 {}）: ", STARVATION_THRESHOLD);
+                    let threshold = match prompt(input, out, &message)?.as_str() {
+                        "" => STARVATION_THRESHOLD,
+                        text => match parse_u32(text, "阈值") {
+                            Ok(threshold) => threshold,
+                            Err(e) => {
+                                writeln!(out, "✗ {}", e)?;
+                                return Ok(true);
+                            }
+                        },
+                    };
+                    write_starvation(pm, threshold, out)?;
+                }
+                "7" => writeln!(out, "{}", pm.schedule_diagnostics())?,
+                "8" => {
+                    let resource = prompt(input, out, "请输入资源（信号量如 sem1，事件如 event2）: ")?;
+                    let pids = pm.blocked_on(&resource);
+                    if pids.is_empty() {
+                        writeln!(out, "没有进程阻塞在 {} 上", resource)?;
+                    } else {
+                        writeln!(out, "阻塞在 {} 上的进程（按阻塞顺序）: {:?}", resource, pids)?;
+                    }
+                }
+                _ => writeln!(out, "✗ 无效选择")?,
             }
-            "s" | "S" => {
-                pm.show_scheduler_stats();
+        }
+        "10" => {
+            writeln!(out, "\n请选择调度设置:")?;
+            writeln!(out, "  1. 切换抢占式优先级调度（当前: {}）",
+                     if pm.scheduler.preemptive() { "开启" } else { "关闭" })?;
+            writeln!(out, "  2. 设置进程切换开销（当前: {}）", pm.scheduler.context_switch_cost())?;
+            writeln!(out, "  3. 设置允许的优先级范围（当前: {}）", pm.priority_range)?;
+            writeln!(out, "  4. 切换PID复用（当前: {}）", if pm.recycle_pids { "开启" } else { "关闭" })?;
+            let quantum_desc = match pm.scheduler.quantum_policy() {
+                Some(policy) => format!("{}，第 {} 轮", policy, pm.scheduler.quantum_round()),
+                None => "按优先级配置".to_string(),
+            };
+            writeln!(out, "  5. 设置时间片变化策略（当前: {}）", quantum_desc)?;
+            writeln!(out, "  6. 设置激活优先级提升（当前: {}）", pm.activation_boost)?;
+            writeln!(out, "  7. 设置最长存活周期（新建进程默认: {}）",
+                     pm.default_max_lifetime.map_or("不限制".to_string(), |limit| limit.to_string()))?;
+            writeln!(out, "  8. 设置CPU核数（当前: {}）", pm.num_cpus())?;
+            let setting_choice = prompt(input, out, "选择: ")?;
+
+            match setting_choice.as_str() {
+                "1" => {
+                    let preemptive = !pm.scheduler.preemptive();
+                    pm.scheduler.set_preemptive(preemptive);
+                    writeln!(out, "✓ 抢占式优先级调度已{}", if preemptive { "开启" } else { "关闭" })?;
+                }
+                "2" => {
+                    let cost_input = prompt(input, out, "请输入每次进程切换消耗的时间: ")?;
+                    match cost_input.parse::<u32>() {
+                        Ok(cost) => {
+                            pm.scheduler.set_context_switch_cost(cost);
+                            writeln!(out, "✓ 进程切换开销已设置为 {}", cost)?;
+                        }
+                        Err(_) => writeln!(out, "✗ 无效的切换开销")?,
+                    }
+                }
+                "3" => {
+                    let min = prompt(input, out, "请输入最低优先级: ")?.parse::<u32>();
+                    let max = prompt(input, out, "请输入最高优先级: ")?.parse::<u32>();
+                    match (min, max) {
+                        (Ok(min), Ok(max)) => match PriorityRange::new(min, max) {
+                            Some(range) => {
+                                pm.priority_range = range;
+                                writeln!(out, "✓ 优先级范围已设置为 {}", range)?;
+                            }
+                            None => writeln!(out, "✗ 最低优先级不能大于最高优先级")?,
+                        },
+                        _ => writeln!(out, "✗ 无效的优先级")?,
+                    }
+                }
+                "4" => {
+                    let enabled = !pm.recycle_pids;
+                    pm.set_recycle_pids(enabled);
+                    writeln!(out, "✓ PID复用已{}", if enabled { "开启" } else { "关闭" })?;
+                }
+                "5" => {
+                    writeln!(out, "  1. 固定时间片")?;
+                    writeln!(out, "  2. 自适应时间片（每轮按步长增减，直到界限）")?;
+                    writeln!(out, "  3. 恢复按优先级配置")?;
+                    let policy = match prompt(input, out, "选择 (1-3): ")?.as_str() {
+                        "1" => {
+                            let Some(quantum) = read_u32(input, out, "请输入时间片: ", "时间片")? else {
+                                return Ok(true);
+                            };
+                            Some(QuantumPolicy::Fixed(quantum))
+                        }
+                        "2" => {
+                            let Some(start) = read_u32(input, out, "请输入初始时间片: ", "初始时间片")? else {
+                                return Ok(true);
+                            };
+                            let Ok(step) = prompt(input, out, "请输入每轮步长（可为负）: ")?.parse::<i32>() else {
+                                writeln!(out, "✗ 无效的步长")?;
+                                return Ok(true);
+                            };
+                            let Some(cap) = read_u32(input, out, "请输入界限（增长时为上限，缩小时为下限）: ", "界限")? else {
+                                return Ok(true);
+                            };
+                            Some(QuantumPolicy::Adaptive { start, step, cap })
+                        }
+                        "3" => None,
+                        _ => {
+                            writeln!(out, "✗ 无效选择")?;
+                            return Ok(true);
+                        }
+                    };
+                    if matches!(policy, Some(QuantumPolicy::Fixed(0))) {
+                        writeln!(out, "✗ 时间片必须大于0")?;
+                        return Ok(true);
+                    }
+                    pm.scheduler.set_quantum_policy(policy);
+                    match policy {
+                        Some(policy) => writeln!(out, "✓ 时间片变化策略已设置为: {}", policy)?,
+                        None => writeln!(out, "✓ 已恢复按优先级配置的时间片")?,
+                    }
+                }
+                "6" => {
+                    let message = "请输入进程从等待转为就绪时的优先级提升（0表示不提升）: ";
+                    if let Some(boost) = read_u32(input, out, message, "优先级提升")? {
+                        pm.activation_boost = boost;
+                        writeln!(out, "✓ 激活优先级提升已设置为 {}，每用完一个时间片衰减1", boost)?;
+                    }
+                }
+                "7" => {
+                    let pid_input = prompt(input, out, "请输入进程PID（直接回车设置新建进程的默认值）: ")?;
+                    let Some(limit) = read_u32(input, out, "请输入最长存活周期数（0表示不限制）: ", "周期数")? else {
+                        return Ok(true);
+                    };
+                    let max_lifetime = (limit > 0).then_some(limit);
+                    if pid_input.is_empty() {
+                        pm.default_max_lifetime = max_lifetime;
+                        match max_lifetime {
+                            Some(limit) => writeln!(out, "✓ 新建进程最多存活 {} 个周期，超过后超时终止", limit)?,
+                            None => writeln!(out, "✓ 新建进程不再限制存活周期")?,
+                        }
+                    } else {
+                        match parse_u32(&pid_input, "PID") {
+                            Ok(pid) => {
+                                if let Err(e) = pm.set_max_lifetime(pid, max_lifetime) {
+                                    writeln!(out, "✗ 错误: {}", e)?;
+                                }
+                            }
+                            Err(reason) => writeln!(out, "✗ {}", reason)?,
+                        }
+                    }
+                }
+                "8" => {
+                    let Some(num_cpus) = read_u32(input, out, "请输入CPU核数: ", "CPU核数")? else {
+                        return Ok(true);
+                    };
+                    if num_cpus == 0 {
+                        writeln!(out, "✗ CPU核数必须大于0")?;
+                        return Ok(true);
+                    }
+                    pm.set_num_cpus(num_cpus as usize);
+                }
+                _ => writeln!(out, "✗ 无效选择")?,
             }
-            "0" => {
-                println!("感谢使用！再见！");
-                break;
+        }
+        "11" => {
+            if let Some(pid) = prompt_pid(input, out, "请输入要调整nice值的进程PID: ")? {
+                let nice_input = prompt(input, out, "请输入新的nice值 (-20~19): ")?;
+                match nice_input.parse::<i32>() {
+                    Ok(nice) => {
+                        if let Err(e) = pm.renice(pid, nice) {
+                            writeln!(out, "✗ 错误: {}", e)?;
+                        }
+                    }
+                    Err(_) => writeln!(out, "✗ 无效的nice值")?,
+                }
             }
-            _ => {
-                println!("✗ 无效的选择，请重新输入");
+        }
+        "12" => {
+            if let Some(pid) = prompt_pid(input, out, "请输入进程PID: ")? {
+                let interval_input = prompt(input, out, "每运行多少个时间单位发起一次I/O (0表示不做I/O): ")?;
+                let burst_input = prompt(input, out, "每次I/O阻塞的周期数: ")?;
+                match (interval_input.parse::<u32>(), burst_input.parse::<u32>()) {
+                    (Ok(interval), Ok(burst)) => {
+                        if let Err(e) = pm.set_io_profile(pid, interval, burst) {
+                            writeln!(out, "✗ 错误: {}", e)?;
+                        }
+                    }
+                    _ => writeln!(out, "✗ 无效的I/O参数")?,
+                }
+            }
+        }
+        "13" => {
+            let count_input = prompt(input, out, "请输入进程数量: ")?;
+            let seed_input = prompt(input, out, "请输入随机种子: ")?;
+            match (count_input.parse::<usize>(), seed_input.parse::<u64>()) {
+                (Ok(count), Ok(seed)) => {
+                    let created = pm.generate_workload(count, seed);
+                    writeln!(out, "✓ 共创建 {} 个进程: {:?}", created.len(), created)?;
+                }
+                _ => writeln!(out, "✗ 无效的数量或种子")?,
             }
         }
+        "14" => {
+            let rounds_input = prompt(input, out, "请输入压力测试轮数: ")?;
+            match rounds_input.parse::<u32>() {
+                Ok(rounds) if rounds > 0 => writeln!(out, "{}", pm.stress_test(rounds))?,
+                _ => writeln!(out, "✗ 无效的轮数")?,
+            }
+        }
+        "15" => {
+            let answer = prompt(input, out, "将撤销所有进程并清空全部记录，确定重置？(y/n): ")?;
+            if answer.eq_ignore_ascii_case("y") {
+                pm.reset();
+            } else {
+                writeln!(out, "已取消")?;
+            }
+        }
+        "16" => {
+            writeln!(out, "\n请选择批量撤销方式:")?;
+            writeln!(out, "  1. 按状态")?;
+            writeln!(out, "  2. 按优先级")?;
+            let bulk_choice = prompt(input, out, "选择: ")?;
+
+            // 先预演，列出将被撤销的进程并确认后再执行
+            let count = match bulk_choice.as_str() {
+                "1" => match prompt_state(input, out)? {
+                    Some(state) => {
+                        let plan = pm.plan_by_state(state.clone());
+                        if !confirm_plan(input, out, &plan)? {
+                            return Ok(true);
+                        }
+                        pm.terminate_by_state(state)
+                    }
+                    None => return Ok(true),
+                },
+                "2" => match prompt(input, out, "请输入优先级: ")?.parse::<u32>() {
+                    Ok(priority) => {
+                        let plan = pm.plan_by_priority(priority);
+                        if !confirm_plan(input, out, &plan)? {
+                            return Ok(true);
+                        }
+                        pm.terminate_by_priority(priority)
+                    }
+                    Err(_) => {
+                        writeln!(out, "✗ 无效的优先级")?;
+                        return Ok(true);
+                    }
+                },
+                _ => {
+                    writeln!(out, "✗ 无效选择")?;
+                    return Ok(true);
+                }
+            };
+            writeln!(out, "✓ 共撤销 {} 个进程", count)?;
+        }
+        "i" | "I" => {
+            match pm.undo_last() {
+                Ok(_) => writeln!(out, "✓ 已撤销上一步操作")?,
+                Err(e) => writeln!(out, "✗ {}", e)?,
+            }
+        }
+        "0" => {
+            writeln!(out, "{}", pm.summary())?;
+            writeln!(out, "感谢使用！再见！")?;
+            return Ok(false);
+        }
+        _ => {
+            writeln!(out, "✗ 无效的选择，请重新输入")?;
+        }
+    }
+    Ok(true)
+}
+
+/// 脚本中create命令省略运行时间时使用的默认值
+const DEFAULT_SCRIPT_BURST: u32 = 5;
+
+/// 输出饥饿检测结果：超过阈值仍未运行的就绪进程及其已等待的周期数
+fn write_starvation<W: Write>(pm: &ProcessManager, threshold: u32, out: &mut W) -> io::Result<()> {
+    let starving = pm.detect_starvation(threshold);
+    if starving.is_empty() {
+        return writeln!(out, "✓ 没有就绪进程等待超过 {} 个周期", threshold);
+    }
+    writeln!(out, "⚠ {} 个就绪进程已等待超过 {} 个周期仍未运行，可能处于饥饿状态:", starving.len(), threshold)?;
+    for pid in starving {
+        let pcb = &pm.total_chain[&pid];
+        writeln!(out, "  PID {}: 优先级 {}，有效优先级 {}，已等待 {} 个周期",
+                 pid, pcb.priority, pcb.effective_priority(), pcb.ready_ticks)?;
+    }
+    Ok(())
+}
+
+/// 以脚本方式批量执行命令（非交互）
+///
+/// 每行一条命令，空行和以#开头的注释行被忽略。支持的命令：
+///
+/// | 命令 | 作用 |
+/// |------|------|
+/// | `create <优先级> [运行时间] [截止时间\|-] [内存KB]` | 创建进程（截止时间为绝对系统时间，`-`表示无） |
+/// | `terminate <pid>` | 撤销进程 |
+/// | `suspend <pid>` / `activate <pid>` | 挂起/激活进程 |
+/// | `block <pid> <事件号>` / `wakeup <事件号>` | 阻塞在事件上/唤醒事件 |
+/// | `fork <pid>` | 创建子进程 |
+/// | `send <发送PID> <接收PID> <内容...>` | 发送消息 |
+/// | `receive <pid>` | 接收消息（信箱为空时阻塞等待） |
+/// | `priority <pid> <优先级>` | 修改优先级 |
+/// | `nice <pid> <nice值>` | 调整nice值 |
+/// | `io <pid> <间隔> <阻塞周期>` | 设置I/O模式 |
+/// | `lifetime <pid> <周期数\|->` | 设置最长存活周期（`-`表示不限制） |
+/// | `arrive <优先级> <运行时间> <到达时间>` | 定时创建进程（到达时间为绝对系统时间） |
+/// | `group <组长pid>` / `join <pid> <组号>` / `leave <pid>` | 创建进程组/加入进程组/离开进程组 |
+/// | `gsuspend <组号>` / `gactivate <组号>` / `gterminate <组号>` | 挂起/激活/撤销整个进程组 |
+/// | `workload <数量> <种子>` | 按种子生成随机负载 |
+/// | `cpus <核数>` | 设置CPU核数 |
+/// | `timeslice <时间片>` | 设置默认时间片（未单独配置的优先级使用） |
+/// | `schedule [次数]` | 执行调度周期（默认1次） |
+/// | `run [最大周期数]` | 连续执行调度周期直到空闲 |
+/// | `expire` | 时间片到 |
+/// | `exportlog <路径>` | 导出事件日志 |
+/// | `replay <路径>` | 重放事件日志文件并替换当前系统（有事件的结果与日志不一致时报错） |
+/// | `snapshot <名称>` | 生成并显示快照（init/run_before/run_after对应三种标准快照） |
+/// | `diagnose` | 调度诊断：说明当前能否调度及原因 |
+/// | `starvation [阈值]` | 饥饿检测（默认阈值为STARVATION_THRESHOLD） |
+/// | `status` / `stats` / `gantt` / `tree` / `groups` | 显示状态/统计/甘特图/进程树/进程组 |
+/// | `reset` | 重置模拟 |
+///
+/// 某行执行失败时输出行号和原因并继续执行后续命令，返回失败的命令数
+pub fn run_script(pm: &mut ProcessManager, commands: &[&str]) -> usize {
+    let mut failures = 0;
+    for (line_no, line) in commands.iter().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        logger::output(format_args!("\n> {}", line));
+        if let Err(e) = run_command(pm, line) {
+            logger::output(format_args!("✗ 第{}行: {}", line_no + 1, e));
+            failures += 1;
+        }
+    }
+    failures
+}
+
+/// 解析第index个参数
+fn parse_arg<T: FromStr>(args: &[&str], index: usize, name: &str) -> Result<T, String> {
+    let raw = args.get(index).ok_or(format!("缺少参数: {}", name))?;
+    raw.parse().map_err(|_| format!("无效的{}: {}", name, raw))
+}
+
+/// 执行一条脚本命令
+fn run_command(pm: &mut ProcessManager, line: &str) -> Result<(), String> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    let (command, args) = (parts[0], &parts[1..]);
+
+    match command {
+        "create" => {
+            let priority = parse_arg(args, 0, "优先级")?;
+            let burst = if args.len() > 1 { parse_arg(args, 1, "运行时间")? } else { DEFAULT_SCRIPT_BURST };
+            if burst == 0 {
+                return Err("运行时间必须大于0".to_string());
+            }
+            let deadline = match args.get(2) {
+                None | Some(&"-") => None,
+                Some(_) => Some(parse_arg(args, 2, "截止时间")?),
+            };
+            let memory_kb = if args.len() > 3 { parse_arg(args, 3, "内存需求")? } else { DEFAULT_MEMORY_KB };
+            if memory_kb == 0 {
+                return Err("内存需求必须大于0".to_string());
+            }
+            pm.create_process_with_memory(priority, burst, deadline, memory_kb).map_err(|e| e.to_string())?;
+        }
+        "terminate" => {
+            pm.terminate_process(parse_arg(args, 0, "PID")?).map_err(|e| e.to_string())?;
+        }
+        "suspend" => pm.suspend_process(parse_arg(args, 0, "PID")?).map_err(|e| e.to_string())?,
+        "activate" => pm.activate_process(parse_arg(args, 0, "PID")?).map_err(|e| e.to_string())?,
+        "block" => {
+            let pid = parse_arg(args, 0, "PID")?;
+            let event_id = parse_arg(args, 1, "事件号")?;
+            pm.block_on(pid, event_id).map_err(|e| e.to_string())?;
+        }
+        "wakeup" => {
+            let event_id = parse_arg(args, 0, "事件号")?;
+            let woken = pm.wakeup_event(event_id);
+            logger::output(format_args!("事件 {} 唤醒了进程: {:?}", event_id, woken));
+        }
+        "fork" => {
+            pm.fork(parse_arg(args, 0, "PID")?).map_err(|e| e.to_string())?;
+        }
+        "send" => {
+            let from = parse_arg(args, 0, "发送进程PID")?;
+            let to = parse_arg(args, 1, "接收进程PID")?;
+            let payload = args.get(2..).unwrap_or_default().join(" ");
+            pm.send_message(from, to, payload).map_err(|e| e.to_string())?;
+        }
+        "receive" => {
+            let pid = parse_arg(args, 0, "PID")?;
+            if let Some(message) = pm.receive_or_block(pid).map_err(|e| e.to_string())? {
+                logger::output(format_args!("进程 {} 收到{}", pid, message));
+            }
+        }
+        "priority" => {
+            let pid = parse_arg(args, 0, "PID")?;
+            let priority = parse_arg(args, 1, "优先级")?;
+            pm.change_priority(pid, priority).map_err(|e| e.to_string())?;
+        }
+        "nice" => {
+            let pid = parse_arg(args, 0, "PID")?;
+            let nice = parse_arg(args, 1, "nice值")?;
+            pm.renice(pid, nice).map_err(|e| e.to_string())?;
+        }
+        "io" => {
+            let pid = parse_arg(args, 0, "PID")?;
+            let interval = parse_arg(args, 1, "I/O间隔")?;
+            let burst = parse_arg(args, 2, "I/O阻塞周期")?;
+            pm.set_io_profile(pid, interval, burst).map_err(|e| e.to_string())?;
+        }
+        "lifetime" => {
+            let pid = parse_arg(args, 0, "PID")?;
+            let max_lifetime = match args.get(1) {
+                Some(&"-") => None,
+                _ => Some(parse_arg(args, 1, "周期数")?),
+            };
+            pm.set_max_lifetime(pid, max_lifetime).map_err(|e| e.to_string())?;
+        }
+        "arrive" => {
+            let spec = ProcessSpec {
+                priority: parse_arg(args, 0, "优先级")?,
+                burst: parse_arg(args, 1, "运行时间")?,
+                arrival_time: parse_arg(args, 2, "到达时间")?,
+            };
+            if spec.burst == 0 {
+                return Err("运行时间必须大于0".to_string());
+            }
+            pm.schedule_arrival(spec).map_err(|e| e.to_string())?;
+        }
+        "group" => {
+            pm.create_group(parse_arg(args, 0, "组长PID")?).map_err(|e| e.to_string())?;
+        }
+        "join" => {
+            let pid = parse_arg(args, 0, "PID")?;
+            let gid = parse_arg(args, 1, "进程组号")?;
+            pm.join_group(pid, gid).map_err(|e| e.to_string())?;
+        }
+        "leave" => pm.leave_group(parse_arg(args, 0, "PID")?).map_err(|e| e.to_string())?,
+        "gsuspend" => {
+            pm.suspend_group(parse_arg(args, 0, "进程组号")?).map_err(|e| e.to_string())?;
+        }
+        "gactivate" => {
+            pm.activate_group(parse_arg(args, 0, "进程组号")?).map_err(|e| e.to_string())?;
+        }
+        "gterminate" => {
+            pm.terminate_group(parse_arg(args, 0, "进程组号")?).map_err(|e| e.to_string())?;
+        }
+        "groups" => pm.print_groups(&mut io::stdout()).map_err(|e| e.to_string())?,
+        "diagnose" => logger::output(format_args!("{}", pm.schedule_diagnostics())),
+        "starvation" => {
+            let threshold = if args.is_empty() { STARVATION_THRESHOLD } else { parse_arg(args, 0, "阈值")? };
+            write_starvation(pm, threshold, &mut io::stdout()).map_err(|e| e.to_string())?;
+        }
+        "workload" => {
+            let count: usize = parse_arg(args, 0, "数量")?;
+            let seed = parse_arg(args, 1, "种子")?;
+            let created = pm.generate_workload(count, seed);
+            if created.len() < count {
+                return Err(format!("只创建了 {} / {} 个进程", created.len(), count));
+            }
+        }
+        "cpus" => {
+            let num_cpus: usize = parse_arg(args, 0, "CPU核数")?;
+            if num_cpus == 0 {
+                return Err("CPU核数必须大于0".to_string());
+            }
+            pm.set_num_cpus(num_cpus);
+        }
+        "timeslice" => {
+            let slice = parse_arg(args, 0, "时间片")?;
+            pm.scheduler.set_default_time_slice(slice).map_err(|e| e.to_string())?;
+        }
+        "schedule" => {
+            let times: u32 = if args.is_empty() { 1 } else { parse_arg(args, 0, "次数")? };
+            for _ in 0..times {
+                pm.run_one_cycle();
+            }
+        }
+        "run" => {
+            let max_cycles = if args.is_empty() { MAX_RUN_CYCLES } else { parse_arg(args, 0, "最大周期数")? };
+            let cycles = pm.run_until_idle(max_cycles);
+            logger::output(format_args!("共执行 {} 个调度周期", cycles));
+            if pm.has_runnable_work() {
+                return Err(format!("已达到最大周期数 {}，仍有进程未完成", max_cycles));
+            }
+        }
+        "expire" => pm.time_slice_expired().map_err(|e| e.to_string())?,
+        "snapshot" => {
+            let name = match args.first().copied() {
+                Some("init") => "初始化快照",
+                Some("run_before") => "运行前快照",
+                Some("run_after") => "运行后快照",
+                Some(name) => name,
+                None => return Err("缺少参数: 快照名称".to_string()),
+            };
+            logger::output(format_args!("{}", pm.get_snapshot(name)));
+        }
+        "exportlog" => {
+            let path = args.first().ok_or("缺少参数: 路径")?;
+            pm.export_event_log(path).map_err(|e| format!("导出事件日志失败: {}", e))?;
+        }
+        "replay" => {
+            let path = args.first().ok_or("缺少参数: 路径")?;
+            let (replayed, report) = ProcessManager::replay(path).map_err(|e| format!("重放失败: {}", e))?;
+            logger::output(format_args!("{}", report));
+            *pm = replayed;
+            if !report.divergences.is_empty() {
+                return Err(format!("{} 个事件的重放结果与日志不一致", report.divergences.len()));
+            }
+        }
+        "status" => pm.print_status(&mut io::stdout()).map_err(|e| e.to_string())?,
+        "stats" => pm.show_scheduler_stats(&mut io::stdout()).map_err(|e| e.to_string())?,
+        "gantt" => pm.print_gantt_chart(&mut io::stdout()).map_err(|e| e.to_string())?,
+        "tree" => pm.print_process_tree(&mut io::stdout()).map_err(|e| e.to_string())?,
+        "reset" => pm.reset(),
+        _ => return Err(format!("未知命令: {}", command)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UNDO_DEPTH;
+
+    #[test]
+    fn menu_creates_batch_then_shows_status() {
+        let mut pm = ProcessManager::new();
+        let mut input: &[u8] = b"9\n7\n0\n";
+        let mut out = Vec::new();
+        run_menu(&mut pm, &mut input, &mut out).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        let status = text.find("========== 系统状态 ==========").expect("应输出系统状态");
+        let (before, after) = text.split_at(status);
+        // 原语自身的创建消息经由logger转写到out，且出现在菜单的确认行之前
+        assert_eq!(before.matches("创建成功，优先级").count(), 5);
+        assert_eq!(before.matches("创建成功 (优先级").count(), 5);
+        let first_log = before.find("✓ 进程 1 创建成功，优先级").unwrap();
+        let first_echo = before.find("  ✓ 进程 1 创建成功 (优先级").unwrap();
+        assert!(first_log < first_echo);
+        assert!(after.contains("总链: 5 个进程"));
+        assert!(after.contains("就绪队列: 5 个进程"));
+        assert!(after.contains("感谢使用"));
+    }
+
+    #[test]
+    fn parse_u32_trims_and_explains_failures() {
+        assert_eq!(parse_u32("  3 ", "优先级"), Ok(3));
+        assert_eq!(parse_u32("-1", "优先级"), Err("优先级不能为负".to_string()));
+        assert_eq!(parse_u32("abc", "优先级"), Err("无效的优先级：请输入非负整数".to_string()));
+        assert_eq!(parse_u32("", "优先级"), Err("优先级不能为空".to_string()));
+        assert!(parse_u32("4294967296", "PID").unwrap_err().contains("超出范围"));
+    }
+
+    #[test]
+    fn read_u32_reprompts_on_empty_lines() {
+        let mut input: &[u8] = b"\n  \n 7\n";
+        let mut out = Vec::new();
+        assert_eq!(read_u32(&mut input, &mut out, "PID: ", "PID").unwrap(), Some(7));
+        assert_eq!(String::from_utf8(out).unwrap().matches("PID: ").count(), 3);
+
+        let mut input: &[u8] = b"-5\n";
+        let mut out = Vec::new();
+        assert_eq!(read_u32(&mut input, &mut out, "优先级: ", "优先级").unwrap(), None);
+        assert!(String::from_utf8(out).unwrap().contains("优先级不能为负"));
+    }
+
+    #[test]
+    fn every_menu_item_dispatches() {
+        for (key, label) in MENU_ITEMS {
+            let mut pm = ProcessManager::new();
+            for priority in 1..=3 {
+                pm.create_process(priority, 10).unwrap();
+            }
+            // 输入为空：需要参数的选项在提示时遇到输入结束
+            let mut input: &[u8] = b"";
+            let mut out = Vec::new();
+            match execute_choice(&mut pm, key, &mut input, &mut out) {
+                Ok(keep_running) => assert_eq!(keep_running, *key != "0", "选项 {} ({})", key, label),
+                Err(e) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof, "选项 {} ({})", key, label),
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_choice_is_reported() {
+        let mut pm = ProcessManager::new();
+        let mut input: &[u8] = b"";
+        let mut out = Vec::new();
+        assert!(execute_choice(&mut pm, "?", &mut input, &mut out).unwrap());
+        assert!(!String::from_utf8(out).unwrap().is_empty());
+    }
+
+    #[test]
+    fn primitive_errors_reach_the_menu_output() {
+        let mut pm = ProcessManager::with_pool_size(4);
+        let mut input: &[u8] = b"6\n1\n";
+        let mut out = Vec::new();
+        assert!(execute_choice(&mut pm, "13", &mut input, &mut out).unwrap());
+
+        let text = String::from_utf8(out).unwrap();
+        let error = text.find(&ProcessError::PcbPoolFull.to_string()).expect("应输出PCB池已满");
+        let summary = text.find("✓ 共创建 4 个进程").unwrap();
+        assert!(error < summary);
+        assert!(logger::stop_capture().is_empty());
+    }
+
+    #[test]
+    fn exit_prints_the_simulation_summary_with_session_totals() {
+        let mut pm = ProcessManager::new();
+        // 批量创建5个进程，撤销1号进程，运行3个周期后退出
+        let mut input: &[u8] = b"9\n2\n1\n6\n3\n0\n";
+        let mut out = Vec::new();
+        run_menu(&mut pm, &mut input, &mut out).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        let summary = text.find("========== 模拟总结 ==========").expect("退出时应输出模拟总结");
+        let report = &text[summary..];
+        for line in ["创建进程总数: 5", "撤销进程总数: 1", "剩余进程数: 4", "总执行次数: 3",
+                     "系统时间: 3", "PCB池使用峰值: 5 / 128"] {
+            assert!(report.contains(line), "缺少 {}:\n{}", line, report);
+        }
+        assert!(report.contains(&format!("总切换次数: {}", pm.scheduler.get_stats().total_switches)));
+        assert!(report.find("感谢使用").unwrap() > report.find("总能耗").unwrap());
+    }
+
+    #[test]
+    fn signalling_an_event_from_the_menu_wakes_only_its_waiters() {
+        let mut pm = ProcessManager::new();
+        let pids: Vec<u32> = (0..3).map(|_| pm.create_process(1, 10).unwrap()).collect();
+        // 进程1和3等待事件5，进程2等待事件6，然后发出事件5
+        let mut input: &[u8] = b"1\n5\n2\n6\n3\n5\n5\n";
+        let mut out = Vec::new();
+        for choice in ["t", "t", "t", "u"] {
+            assert!(execute_choice(&mut pm, choice, &mut input, &mut out).unwrap());
+        }
+
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains(&format!("✓ 事件 5 唤醒了进程: {:?}", [pids[0], pids[2]])), "{}", text);
+        assert!(pm.ready_queue.contains_pid(pids[0]) && pm.ready_queue.contains_pid(pids[2]));
+        assert_eq!(pm.waiting_queue.pids(), vec![pids[1]]);
+        assert_eq!(pm.total_chain[&pids[1]].blocked_event, Some(6));
+        assert_eq!(pm.total_chain[&pids[0]].blocked_event, None);
+
+        let mut out = Vec::new();
+        assert!(execute_choice(&mut pm, "u", &mut &b"7\n"[..], &mut out).unwrap());
+        assert!(String::from_utf8(out).unwrap().contains("没有进程在等待事件 7"));
+        assert_eq!(pm.waiting_queue.len(), 1);
+    }
+
+    #[test]
+    fn configured_slice_is_assigned_to_a_priority_3_process() {
+        let mut pm = ProcessManager::new();
+        let default = pm.scheduler.default_time_slice();
+        let mut out = Vec::new();
+        assert!(execute_choice(&mut pm, "q", &mut &b"3\n7\n"[..], &mut out).unwrap());
+        assert!(String::from_utf8(out).unwrap().contains("优先级 3 的时间片已设置为 7"));
+
+        let configured = pm.create_process(3, 100).unwrap();
+        let other = pm.create_process(2, 100).unwrap();
+        assert_eq!(pm.total_chain[&configured].remaining_time, 7);
+        assert_eq!(pm.total_chain[&other].remaining_time, default);
+
+        // 时间片用完重新排队时也按配置的时间片重置
+        pm.run_cycles(7);
+        assert!(pm.ready_queue.contains_pid(configured));
+        assert_eq!(pm.total_chain[&configured].cpu_time_used, 7);
+        assert_eq!(pm.total_chain[&configured].remaining_time, 7);
+    }
+
+    #[test]
+    fn script_drives_the_simulation_to_the_expected_state() {
+        let mut pm = ProcessManager::new();
+        let script = [
+            "# 两个进程，运行一个周期后挂起低优先级进程",
+            "create 5 4",
+            "create 3 6",
+            "",
+            "snapshot run_before",
+            "schedule",
+            "suspend 2",
+            "terminate 99",
+            "schedule 3",
+        ];
+        logger::start_capture();
+        let failures = run_script(&mut pm, &script);
+        let output = logger::stop_capture();
+
+        assert_eq!(failures, 1);
+        assert!(output.contains("> create 5 4"));
+        assert!(output.contains("✗ 第8行: 进程 99 不存在"), "{}", output);
+        assert!(output.contains("运行前快照"));
+        assert!(!pm.total_chain.contains_key(&1));
+        assert_eq!(pm.total_chain[&2].state, ProcessState::SuspendedReady);
+        assert_eq!(pm.waiting_queue.pids(), vec![2]);
+        assert_eq!(pm.scheduler.current_time(), 4);
+        assert_eq!(pm.terminated_count, 1);
+    }
+
+    #[test]
+    fn undo_brings_back_a_terminated_process() {
+        let mut pm = ProcessManager::new();
+        let mut input: &[u8] = b"3\n10\n\n\n1\n";
+        let mut out = Vec::new();
+        for choice in ["1", "2"] {
+            assert!(execute_choice(&mut pm, choice, &mut input, &mut out).unwrap());
+        }
+        assert!(pm.total_chain.is_empty());
+
+        assert!(execute_choice(&mut pm, "i", &mut &b""[..], &mut out).unwrap());
+        let pcb = &pm.total_chain[&1];
+        assert_eq!((pcb.priority, pcb.burst_time, pcb.state.clone()), (3, 10, ProcessState::Ready));
+        assert_eq!(pm.ready_queue.pids(), vec![1]);
+        assert_eq!(pm.pcb_pool.allocated_blocks(), vec![(pcb.pool_index, 0)]);
+
+        // 再撤销一步回到创建之前，之后没有可撤销的操作
+        assert!(execute_choice(&mut pm, "i", &mut &b""[..], &mut out).unwrap());
+        assert!(pm.total_chain.is_empty());
+        let mut out = Vec::new();
+        assert!(execute_choice(&mut pm, "i", &mut &b""[..], &mut out).unwrap());
+        assert!(String::from_utf8(out).unwrap().contains("没有可撤销的操作"));
+    }
+
+    #[test]
+    fn undo_is_limited_to_the_most_recent_operations() {
+        let mut pm = ProcessManager::new();
+        let mut out = Vec::new();
+        for _ in 0..UNDO_DEPTH + 2 {
+            assert!(execute_choice(&mut pm, "6", &mut &b"\n"[..], &mut out).unwrap());
+        }
+        for _ in 0..UNDO_DEPTH {
+            pm.undo_last().unwrap();
+        }
+        assert_eq!(pm.scheduler.current_time(), 2);
+        assert_eq!(pm.undo_last(), Err("没有可撤销的操作".to_string()));
+    }
+
+    #[test]
+    fn reset_clears_everything_after_confirmation() {
+        let mut pm = ProcessManager::with_pool_size(16);
+        for priority in 1..=4 {
+            pm.create_process(priority, 10).unwrap();
+        }
+        pm.run_cycles(3);
+        pm.suspend_process(1).unwrap();
+
+        let mut out = Vec::new();
+        assert!(execute_choice(&mut pm, "15", &mut &b"n\n"[..], &mut out).unwrap());
+        assert!(String::from_utf8(out).unwrap().contains("已取消"));
+        assert_eq!(pm.total_chain.len(), 4);
+
+        let mut out = Vec::new();
+        assert!(execute_choice(&mut pm, "15", &mut &b"y\n"[..], &mut out).unwrap());
+        assert!(String::from_utf8(out).unwrap().contains("撤销了 4 个进程"));
+        assert_eq!(pm.pcb_pool.get_free_count(), pm.pcb_pool.get_pool_size());
+        assert!(pm.ready_queue.is_empty() && pm.waiting_queue.is_empty() && pm.running_pids().is_empty());
+        assert!(pm.total_chain.is_empty());
+        assert_eq!(pm.scheduler.current_time(), 0);
+        assert_eq!(pm.create_process(1, 10).unwrap(), 1);
     }
 }
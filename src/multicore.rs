@@ -0,0 +1,144 @@
+use crate::event_log::Primitive;
+use crate::logger::{log, LogLevel};
+use crate::queue::ProcessQueue;
+use crate::ProcessManager;
+
+/// 多核：CPU核数的设置、各核运行进程的查询和核之间的负载均衡
+impl ProcessManager {
+    /// 就绪进程能否在core上运行：尚未归属任何核，或归属于该核
+    pub(crate) fn runnable_on(&self, pid: u32, core: usize) -> bool {
+        self.total_chain.get(&pid).is_some_and(|pcb| pcb.cpu.is_none_or(|home| home == core))
+    }
+
+    /// 负载均衡：把负载最重的核上的就绪进程迁移到负载最轻的核，直到两者相差不超过1，返回迁移的进程数
+    ///
+    /// 核的负载 = 正在运行的进程数 + 归属于该核的就绪进程数；尚未归属任何核的进程不计入。
+    /// 每次迁移排在就绪队列最后的进程（最晚才会被调度），并计入调度器的迁移次数
+    pub(crate) fn balance_load(&mut self) -> usize {
+        let mut migrated = 0;
+        loop {
+            let loads: Vec<usize> = (0..self.num_cpus())
+                .map(|core| self.cores[core].len() + self.ready_queue.iter()
+                    .filter(|pid| self.total_chain.get(pid).is_some_and(|pcb| pcb.cpu == Some(core)))
+                    .count())
+                .collect();
+            let busiest = (0..loads.len()).max_by_key(|&core| (loads[core], std::cmp::Reverse(core)));
+            let idlest = (0..loads.len()).min_by_key(|&core| (loads[core], core));
+            let (Some(from), Some(to)) = (busiest, idlest) else {
+                return migrated;
+            };
+            if loads[from] <= loads[to] + 1 {
+                return migrated;
+            }
+
+            let Some(pid) = self.ready_queue.iter()
+                .filter(|pid| self.total_chain.get(pid).is_some_and(|pcb| pcb.cpu == Some(from)))
+                .last()
+            else {
+                return migrated;
+            };
+            if let Some(pcb) = self.total_chain.get_mut(&pid) {
+                pcb.cpu = Some(to);
+            }
+            self.scheduler.record_migration();
+            migrated += 1;
+            log(LogLevel::Info, format_args!("负载均衡: 进程 {} 从CPU {} 迁移到CPU {}", pid, from, to));
+        }
+    }
+
+    /// CPU核数
+    pub(crate) fn num_cpus(&self) -> usize {
+        self.cores.len()
+    }
+
+    /// 设置CPU核数（至少为1）
+    ///
+    /// 增加的核初始空闲，下一个调度周期从就绪队列补上进程；
+    /// 减少时被移除的核上的进程放回就绪队列，归属于这些核的进程不再归属任何核
+    pub(crate) fn set_num_cpus(&mut self, num_cpus: usize) {
+        self.logged(
+            Primitive::SetNumCpus { num_cpus },
+            |pm| pm.set_num_cpus_unlogged(num_cpus),
+            |_| "ok".to_string(),
+        )
+    }
+
+    fn set_num_cpus_unlogged(&mut self, num_cpus: usize) {
+        let num_cpus = num_cpus.max(1);
+        if num_cpus > self.cores.len() {
+            self.cores.resize(num_cpus, ProcessQueue::new());
+        } else {
+            for core in self.cores.split_off(num_cpus) {
+                if let Some(pid) = core.front() {
+                    self.requeue_running(pid);
+                    log(LogLevel::Info, format_args!("进程 {} 所在的CPU核已移除，转为就绪状态", pid));
+                }
+            }
+            for pcb in self.total_chain.values_mut() {
+                if pcb.cpu.is_some_and(|core| core >= num_cpus) {
+                    pcb.cpu = None;
+                }
+            }
+        }
+        log(LogLevel::Info, format_args!("✓ CPU核数已设置为 {}", num_cpus));
+    }
+
+    /// 所有CPU核上正在运行的进程（按核编号）
+    pub(crate) fn running_pids(&self) -> Vec<u32> {
+        self.cores.iter().filter_map(|core| core.front()).collect()
+    }
+
+    /// 将各核的运行队列合并为一个（按核编号），供快照、显示等只读场合使用
+    pub(crate) fn running_queue(&self) -> ProcessQueue {
+        let mut queue = ProcessQueue::new();
+        for pid in self.running_pids() {
+            queue.enqueue(pid);
+        }
+        queue
+    }
+
+    /// 编号最小的忙碌CPU核上的进程（单核时即当前运行进程）
+    pub(crate) fn current_running(&self) -> Option<u32> {
+        self.cores.iter().find_map(|core| core.front())
+    }
+
+    /// 进程是否正在某个CPU核上运行
+    pub(crate) fn is_running(&self, pid: u32) -> bool {
+        self.cores.iter().any(|core| core.contains_pid(pid))
+    }
+
+    /// 将进程从所在CPU核的运行队列中移除，返回它是否正在运行
+    pub(crate) fn remove_running(&mut self, pid: u32) -> bool {
+        self.cores.iter_mut().any(|core| core.remove_by_pid(pid))
+    }
+
+    /// 编号最小的空闲CPU核，所有核都忙时返回None
+    pub(crate) fn idle_core(&self) -> Option<usize> {
+        self.cores.iter().position(|core| core.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn balancing_evens_out_four_ready_processes_against_an_idle_core() {
+        let mut pm = ProcessManager::new();
+        pm.set_num_cpus(2);
+        let pids: Vec<u32> = (0..4).map(|_| pm.create_process(1, 10).unwrap()).collect();
+        for pid in &pids {
+            pm.total_chain.get_mut(pid).unwrap().cpu = Some(0);
+        }
+
+        // 负载4比0：从排在最后的进程开始迁移，直到两核相差不超过1
+        assert_eq!(pm.balance_load(), 2);
+        let homes: Vec<Option<usize>> = pids.iter().map(|pid| pm.total_chain[pid].cpu).collect();
+        assert_eq!(homes, vec![Some(0), Some(0), Some(1), Some(1)]);
+        assert_eq!(pm.scheduler.get_stats().migrations, 2);
+        assert_eq!(pm.balance_load(), 0);
+
+        pm.fill_idle_cores();
+        assert_eq!(pm.running_pids(), vec![pids[0], pids[2]]);
+    }
+}
@@ -1,27 +1,163 @@
-#[derive(Debug, Clone, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+use crate::color;
+
+/// nice值的取值范围（与UNIX相同）
+pub const NICE_MIN: i32 = -20;
+pub const NICE_MAX: i32 = 19;
+
+/// nice值每变化该数值，有效优先级变化1
+const NICE_DIVISOR: i32 = 4;
+
+/// 允许用户指定的优先级范围（闭区间）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PriorityRange {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl PriorityRange {
+    /// min大于max时返回None
+    pub fn new(min: u32, max: u32) -> Option<Self> {
+        (min <= max).then_some(PriorityRange { min, max })
+    }
+
+    pub fn contains(&self, priority: u32) -> bool {
+        (self.min..=self.max).contains(&priority)
+    }
+}
+
+impl Default for PriorityRange {
+    fn default() -> Self {
+        PriorityRange { min: 0, max: 10 }
+    }
+}
+
+impl std::fmt::Display for PriorityRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.min, self.max)
+    }
+}
+
+/// PCB池中每个槽位代表的内存大小（KB）
+pub const BLOCK_KB: usize = 4;
+
+/// 未指定内存需求时进程占用的内存（恰好一个槽位）
+pub const DEFAULT_MEMORY_KB: usize = BLOCK_KB;
+
+/// 容纳memory_kb内存所需的伙伴块阶数：ceil(log2(ceil(memory_kb / BLOCK_KB)))，
+/// 不足一个槽位时按一个槽位计
+pub fn memory_order(memory_kb: usize) -> usize {
+    memory_kb.div_ceil(BLOCK_KB).max(1).next_power_of_two().trailing_zeros() as usize
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ProcessState {
+    New,        // 新建（已分配PCB，尚未进入就绪队列）
     Ready,      // 就绪
     Running,    // 运行
-    Waiting,    // 等待/挂起（进程被挂起后进入等待队列）
+    Waiting,    // 等待（阻塞在事件、I/O或信号量上）
+    Terminated, // 终止（已撤销，保留记录用于统计）
+    SuspendedReady,   // 挂起就绪（就绪或运行时被挂起，激活后回到就绪）
+    SuspendedBlocked, // 挂起阻塞（阻塞时被挂起，激活后回到等待）
 }
 
 impl ProcessState {
     pub fn to_string(&self) -> &str {
         match self {
+            ProcessState::New => "新建",
             ProcessState::Ready => "就绪",
             ProcessState::Running => "运行",
             ProcessState::Waiting => "等待",
+            ProcessState::Terminated => "终止",
+            ProcessState::SuspendedReady => "挂起就绪",
+            ProcessState::SuspendedBlocked => "挂起阻塞",
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PCB {
     pub pid: u32,                    // 进程ID
     pub priority: u32,               // 优先级
     pub state: ProcessState,         // 进程状态
     pub remaining_time: u32,         // 剩余时间片
+    #[serde(default)]
+    pub burst_time: u32,             // 剩余需要的CPU时间（为0时进程运行完成）
+    #[serde(default)]
+    pub total_burst: u32,            // 创建时申请的总CPU时间
+    #[serde(default)]
+    pub arrival_time: u32,           // 创建时的系统时间
+    #[serde(default)]
+    pub created_at: u32,             // 创建时的系统时间（PCB分配时刻）
+    #[serde(default)]
+    pub lifetime: u32,               // 创建以来经历的调度周期数（含挂起、阻塞期间）
+    #[serde(default)]
+    pub max_lifetime: Option<u32>,   // 最长存活周期数，超过后被强制撤销（None表示不限制）
+    #[serde(default)]
+    pub completion_time: u32,        // 撤销时的系统时间
     pub pool_index: usize,           // 在PCB池中的索引（用于伙伴系统）
+    pub suspended_at: u32,           // 最近一次被挂起时的系统时间
+    pub waited_time: u32,            // 在等待队列中累计等待的时间
+    #[serde(default)]
+    pub energy_used: u64,            // 执行累计消耗的能量
+    #[serde(default)]
+    pub non_preemptible: bool,       // 是否处于临界区（不可抢占）
+    #[serde(default)]
+    pub wait_ticks: u32,             // 自上次运行以来在就绪队列中等待的周期数（用于老化）
+    #[serde(default)]
+    pub ready_ticks: u32,            // 自上次运行以来在就绪队列中等待的总周期数（老化时不清零，用于饥饿检测）
+    #[serde(default)]
+    pub seq: u64,                    // 最近一次进入就绪队列时的序号（同优先级按先来先到）
+    #[serde(default)]
+    pub blocked_event: Option<u32>,  // 阻塞等待的事件号（手动挂起时为None）
+    #[serde(default)]
+    pub parent_pid: Option<u32>,     // 父进程PID（由fork创建时记录）
+    #[serde(default)]
+    pub children: Vec<u32>,          // 子进程PID
+    #[serde(default)]
+    pub mlfq_level: u8,              // 多级反馈队列中的级别（0为最高级）
+    #[serde(default)]
+    pub nice: i32,                   // nice值（NICE_MIN..=NICE_MAX，越小越优先）
+    #[serde(default)]
+    pub cpu_time_used: u32,          // 累计实际占用CPU的时间单位数
+    #[serde(default)]
+    pub io_interval: u32,            // 每运行该数量的时间单位发起一次I/O（0表示不做I/O）
+    #[serde(default)]
+    pub io_burst: u32,               // 每次I/O阻塞的周期数
+    #[serde(default)]
+    pub remaining_io: u32,           // 当前I/O还需等待的周期数（不在I/O中时为0）
+    #[serde(default = "default_tickets")]
+    pub tickets: u32,                // 彩票调度中持有的彩票数（创建时为优先级+1）
+    #[serde(default)]
+    pub deadline: Option<u32>,       // 截止时间（绝对系统时间，None表示非实时进程）
+    #[serde(default)]
+    pub deadline_missed: bool,       // 是否已错过截止时间（每个进程只记一次）
+    #[serde(default = "default_memory_kb")]
+    pub memory_kb: usize,            // 内存需求（KB），决定分配的伙伴块大小
+    #[serde(default)]
+    pub inherited_priority: Option<u32>, // 通过优先级继承临时获得的优先级（priority保持为原优先级）
+    #[serde(default)]
+    pub awaiting_message: bool,      // 是否因信箱为空而阻塞等待消息
+    #[serde(default)]
+    pub vruntime: u64,               // 虚拟运行时间（完全公平调度按此排序，运行时按权重反比增长）
+    #[serde(default)]
+    pub boost: u32,                  // 从等待转为就绪时获得的临时优先级提升（每用完一个时间片减1）
+    #[serde(default)]
+    pub aging_boost: u32,            // 在就绪队列中等待过久获得的老化提升（被调度运行时清零）
+    #[serde(default)]
+    pub cpu: Option<usize>,          // 所属CPU核（上次运行的核，None表示尚未运行、任何核都可选中）
+    #[serde(default)]
+    pub group_id: Option<u32>,       // 所属进程组（组号为组长的PID，None表示不属于任何组）
+}
+
+fn default_tickets() -> u32 {
+    1
+}
+
+fn default_memory_kb() -> usize {
+    DEFAULT_MEMORY_KB
 }
 
 impl PCB {
@@ -29,9 +165,43 @@ impl PCB {
         PCB {
             pid,
             priority: 0,
-            state: ProcessState::Ready,
+            state: ProcessState::New,
             remaining_time: 0,
+            burst_time: 0,
+            total_burst: 0,
+            arrival_time: 0,
+            created_at: 0,
+            lifetime: 0,
+            max_lifetime: None,
+            completion_time: 0,
             pool_index,
+            suspended_at: 0,
+            waited_time: 0,
+            energy_used: 0,
+            non_preemptible: false,
+            wait_ticks: 0,
+            ready_ticks: 0,
+            seq: 0,
+            blocked_event: None,
+            parent_pid: None,
+            children: Vec::new(),
+            mlfq_level: 0,
+            nice: 0,
+            cpu_time_used: 0,
+            io_interval: 0,
+            io_burst: 0,
+            remaining_io: 0,
+            tickets: default_tickets(),
+            deadline: None,
+            deadline_missed: false,
+            memory_kb: DEFAULT_MEMORY_KB,
+            inherited_priority: None,
+            awaiting_message: false,
+            vruntime: 0,
+            boost: 0,
+            aging_boost: 0,
+            cpu: None,
+            group_id: None,
         }
     }
 
@@ -39,17 +209,122 @@ impl PCB {
     pub fn reset(&mut self) {
         self.pid = 0;
         self.priority = 0;
-        self.state = ProcessState::Ready;
+        self.state = ProcessState::New;
         self.remaining_time = 0;
+        self.burst_time = 0;
+        self.total_burst = 0;
+        self.arrival_time = 0;
+        self.created_at = 0;
+        self.lifetime = 0;
+        self.max_lifetime = None;
+        self.completion_time = 0;
+        self.suspended_at = 0;
+        self.waited_time = 0;
+        self.energy_used = 0;
+        self.non_preemptible = false;
+        self.wait_ticks = 0;
+        self.ready_ticks = 0;
+        self.seq = 0;
+        self.blocked_event = None;
+        self.parent_pid = None;
+        self.children.clear();
+        self.mlfq_level = 0;
+        self.nice = 0;
+        self.cpu_time_used = 0;
+        self.io_interval = 0;
+        self.io_burst = 0;
+        self.remaining_io = 0;
+        self.tickets = default_tickets();
+        self.deadline = None;
+        self.deadline_missed = false;
+        self.memory_kb = DEFAULT_MEMORY_KB;
+        self.inherited_priority = None;
+        self.awaiting_message = false;
+        self.vruntime = 0;
+        self.boost = 0;
+        self.aging_boost = 0;
+        self.group_id = None;
+    }
+
+    /// 该进程占用的伙伴块阶数
+    pub fn block_order(&self) -> usize {
+        memory_order(self.memory_kb)
+    }
+
+    /// 刚运行完一个时间单位后是否到达I/O点
+    pub fn io_due(&self) -> bool {
+        self.io_interval > 0 && self.io_burst > 0 && self.cpu_time_used.is_multiple_of(self.io_interval)
+    }
+
+    /// 自身的调度优先级 = 基础优先级 - nice / NICE_DIVISOR + 激活提升 + 老化提升（最低为0），不含继承
+    pub fn own_priority(&self) -> u32 {
+        (self.priority as i64 - (self.nice / NICE_DIVISOR) as i64 + self.boost as i64 + self.aging_boost as i64).max(0) as u32
+    }
+
+    /// 有效调度优先级：自身优先级与继承优先级中的较高者
+    ///
+    /// 优先级调度按有效优先级排序；nice为负时提升，为正时降低
+    pub fn effective_priority(&self) -> u32 {
+        self.own_priority().max(self.inherited_priority.unwrap_or(0))
+    }
+
+    /// 周转时间 = 完成时间 - 到达时间
+    pub fn turnaround_time(&self) -> u32 {
+        self.completion_time.saturating_sub(self.arrival_time)
+    }
+
+    /// 等待时间 = 周转时间 - 实际获得的CPU时间
+    pub fn waiting_time(&self) -> u32 {
+        let executed = self.total_burst.saturating_sub(self.burst_time);
+        self.turnaround_time().saturating_sub(executed)
     }
 
     pub fn display(&self) -> String {
-        format!(
-            "PID: {}, 优先级: {}, 状态: {}, 剩余时间片: {}",
+        let mut text = format!(
+            "PID: {}, 优先级: {}, nice: {}, 有效优先级: {}, 状态: {}, 剩余时间片: {}, 剩余运行时间: {}, CPU时间: {}, 能耗: {}, 内存: {} KB, 创建时间: {}, 已存在: {} 个周期",
             self.pid,
             self.priority,
-            self.state.to_string(),
-            self.remaining_time
-        )
+            self.nice,
+            self.effective_priority(),
+            color::state_label(&self.state),
+            self.remaining_time,
+            self.burst_time,
+            self.cpu_time_used,
+            self.energy_used,
+            self.memory_kb,
+            self.created_at,
+            self.lifetime
+        );
+        if let Some(event_id) = self.blocked_event {
+            text.push_str(&format!(", 等待事件: {}", event_id));
+        }
+        if self.remaining_io > 0 {
+            text.push_str(&format!(", 等待I/O: {}", self.remaining_io));
+        }
+        if self.awaiting_message {
+            text.push_str(", 等待消息");
+        }
+        if self.boost > 0 {
+            text.push_str(&format!(", 激活提升: {}", self.boost));
+        }
+        if self.aging_boost > 0 {
+            text.push_str(&format!(", 老化提升: {}", self.aging_boost));
+        }
+        if let Some(group_id) = self.group_id {
+            text.push_str(&format!(", 进程组: {}", group_id));
+        }
+        if let Some(inherited) = self.inherited_priority {
+            text.push_str(&format!(", 继承优先级: {}", inherited));
+        }
+        if let Some(max_lifetime) = self.max_lifetime {
+            text.push_str(&format!(", 最长存活: {} 个周期", max_lifetime));
+        }
+        if let Some(deadline) = self.deadline {
+            text.push_str(&format!(", 截止时间: {}", deadline));
+            if self.deadline_missed {
+                text.push_str("（已错过）");
+            }
+        }
+        text
     }
 }
@@ -0,0 +1,465 @@
+use std::collections::HashSet;
+
+use crate::logger::{log, LogLevel};
+use crate::pcb::{ProcessState, PCB};
+use crate::queue::{state_matches_queue, PcbView, QueueKind, QueuesView};
+use crate::ProcessManager;
+
+/// 查询与一致性检查：进程所在队列、按状态列出、调度诊断、饥饿检测，以及总链与队列的一致性校验和修复
+impl ProcessManager {
+    /// 调度诊断：用一句话说明当前能否调度，不能时说明原因及如何解除
+    ///
+    /// 就绪队列为空时，按原因统计等待队列中的进程（被挂起的需激活，等待事件的需发出事件，
+    /// 等待I/O的会自动就绪等），并列出阻塞在信号量上、正在运行的进程和尚未到达的定时进程
+    pub(crate) fn schedule_diagnostics(&self) -> String {
+        if self.total_chain.is_empty() {
+            return match self.pending_arrivals.first() {
+                Some(next) => format!("系统中暂无进程，{} 个定时进程尚未到达（最早在系统时间 {}）",
+                                      self.pending_arrivals.len(), next.arrival_time),
+                None => "系统中没有任何进程，请先创建进程".to_string(),
+            };
+        }
+
+        let running = self.running_pids().len();
+        if !self.ready_queue.is_empty() {
+            return if running == self.num_cpus() {
+                format!("就绪队列中有 {} 个进程，所有CPU核都在运行进程，需等运行进程让出CPU（如时间片到）",
+                        self.ready_queue.len())
+            } else {
+                format!("就绪队列中有 {} 个进程，可以调度", self.ready_queue.len())
+            };
+        }
+
+        let (mut suspended, mut io, mut message, mut event, mut blocked) = (0, 0, 0, 0, 0);
+        for pid in self.waiting_queue.iter() {
+            let Some(pcb) = self.total_chain.get(&pid) else {
+                continue;
+            };
+            match pcb.state {
+                ProcessState::SuspendedReady | ProcessState::SuspendedBlocked => suspended += 1,
+                _ if pcb.remaining_io > 0 => io += 1,
+                _ if pcb.awaiting_message => message += 1,
+                _ if pcb.blocked_event.is_some() => event += 1,
+                _ => blocked += 1,
+            }
+        }
+        let reasons: Vec<String> = [
+            (suspended, "被挂起，需激活"),
+            (event, "等待事件，需发出事件唤醒"),
+            (message, "等待消息，需向其发送消息"),
+            (blocked, "被阻塞，需唤醒"),
+            (io, "等待I/O，完成后自动就绪"),
+        ]
+            .iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, reason)| format!("{} 个{}", count, reason))
+            .collect();
+
+        let mut text = "就绪队列为空".to_string();
+        if !self.waiting_queue.is_empty() {
+            text.push_str(&format!("，但有 {} 个进程在等待队列（{}）", self.waiting_queue.len(), reasons.join("；")));
+        }
+        let on_semaphore = self.total_chain.values()
+            .filter(|pcb| pcb.state == ProcessState::Waiting && !self.waiting_queue.contains_pid(pcb.pid))
+            .count();
+        if on_semaphore > 0 {
+            text.push_str(&format!("，{} 个进程阻塞在信号量上（需V操作）", on_semaphore));
+        }
+        if running > 0 {
+            text.push_str(&format!("，{} 个进程正在运行", running));
+        }
+        if let Some(next) = self.pending_arrivals.first() {
+            text.push_str(&format!("，{} 个定时进程尚未到达（最早在系统时间 {}）", self.pending_arrivals.len(), next.arrival_time));
+        }
+        text
+    }
+
+    /// 饥饿检测：返回自上次运行以来在就绪队列中等待超过threshold_cycles个周期的进程，按PID升序
+    ///
+    /// 等待周期数在老化提升优先级时不清零，因此老化不起作用的调度策略下、
+    /// 或老化提升后仍轮不到的进程，都能被发现
+    pub(crate) fn detect_starvation(&self, threshold_cycles: u32) -> Vec<u32> {
+        let mut starving: Vec<u32> = self.ready_queue.iter()
+            .filter(|pid| self.total_chain.get(pid).is_some_and(|pcb| pcb.ready_ticks > threshold_cycles))
+            .collect();
+        starving.sort_unstable();
+        starving
+    }
+
+    /// 查找进程当前所在的队列（依次检查三个队列和各信号量的阻塞队列）
+    fn locate_queue(&self, pid: u32) -> Option<QueueKind> {
+        if self.ready_queue.contains_pid(pid) {
+            Some(QueueKind::Ready)
+        } else if self.waiting_queue.contains_pid(pid) {
+            Some(QueueKind::Waiting)
+        } else if self.is_running(pid) {
+            Some(QueueKind::Running)
+        } else {
+            self.semaphores.iter()
+                .find(|(_, sem)| sem.contains_pid(pid))
+                .map(|(&id, _)| QueueKind::Semaphore(id))
+        }
+    }
+
+    /// 按PID查询进程：返回总链中的PCB及其所在队列，进程不存在时返回None
+    pub(crate) fn query_process(&self, pid: u32) -> Option<PcbView> {
+        let pcb = self.total_chain.get(&pid)?.clone();
+        Some(PcbView {
+            pcb,
+            queue: self.locate_queue(pid),
+        })
+    }
+
+    /// 预览就绪队列中排在最前的count个进程（按队列顺序，不出队）
+    ///
+    /// 彩票调度下实际被选中的进程由抽签决定，预览只反映队列顺序
+    pub(crate) fn preview_ready(&self, count: usize) -> Vec<PCB> {
+        (0..count)
+            .map_while(|n| self.ready_queue.peek(n))
+            .filter_map(|pid| self.total_chain.get(&pid).cloned())
+            .collect()
+    }
+
+    /// 列出总链中处于指定状态的所有进程PID（升序）
+    ///
+    /// 总链中的状态与队列归属可能因维护疏漏而不一致，发现时输出警告，
+    /// 但仍以总链中的状态为准
+    pub(crate) fn list_by_state(&self, state: ProcessState) -> Vec<u32> {
+        let mut pids: Vec<u32> = self.total_chain.values()
+            .filter(|pcb| pcb.state == state)
+            .map(|pcb| pcb.pid)
+            .collect();
+        pids.sort_unstable();
+
+        for &pid in &pids {
+            let queue = self.locate_queue(pid);
+            if !state_matches_queue(&state, queue) {
+                log(LogLevel::Info, format_args!("⚠ 进程 {} 的状态为{}，但所在队列为{}",
+                         pid, state.to_string(), queue.map_or("无".to_string(), |q| q.name())));
+            }
+        }
+        pids
+    }
+
+    /// 进程出现的所有队列（正常情况下至多一个）
+    fn queue_memberships(&self, pid: u32) -> Vec<QueueKind> {
+        let mut kinds = Vec::new();
+        let running = self.running_queue();
+        for (queue, kind) in [
+            (&self.ready_queue, QueueKind::Ready),
+            (&self.waiting_queue, QueueKind::Waiting),
+            (&running, QueueKind::Running),
+        ] {
+            if queue.contains_pid(pid) {
+                kinds.push(kind);
+            }
+        }
+        for (&id, sem) in &self.semaphores {
+            if sem.contains_pid(pid) {
+                kinds.push(QueueKind::Semaphore(id));
+            }
+        }
+        kinds
+    }
+
+    /// 查找泄漏的PCB块：池中存有PCB、但没有被总链中任何进程引用的索引（升序）
+    pub(crate) fn find_orphaned_blocks(&self) -> Vec<usize> {
+        let referenced: HashSet<usize> = self.total_chain.values()
+            .map(|pcb| pcb.pool_index)
+            .collect();
+        self.pcb_pool.allocated_indices()
+            .into_iter()
+            .filter(|index| !referenced.contains(index))
+            .collect()
+    }
+
+    /// 检查总链与各队列是否一致，返回发现的问题描述（一致时为空）
+    ///
+    /// 检查项：
+    /// 1. 总链中的每个进程恰好位于一个队列中，且该队列与其状态相符
+    /// 2. 队列中的每个PID都在总链中
+    pub(crate) fn verify_consistency(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        let mut pids: Vec<u32> = self.total_chain.keys().copied().collect();
+        pids.sort_unstable();
+        for pid in pids {
+            let state = &self.total_chain[&pid].state;
+            let kinds = self.queue_memberships(pid);
+            match kinds.as_slice() {
+                [] if state_matches_queue(state, None) => {}
+                [kind] if state_matches_queue(state, Some(*kind)) => {}
+                [] => problems.push(format!("进程 {} 状态为{}，但不在任何队列中", pid, state.to_string())),
+                [kind] => problems.push(format!("进程 {} 状态为{}，但位于{}", pid, state.to_string(), kind.name())),
+                _ => {
+                    let names: Vec<String> = kinds.iter().map(|kind| kind.name()).collect();
+                    problems.push(format!("进程 {} 同时位于多个队列: {}", pid, names.join("、")));
+                }
+            }
+        }
+
+        let mut queued: Vec<(QueueKind, u32)> = Vec::new();
+        let running = self.running_queue();
+        for (queue, kind) in [
+            (&self.ready_queue, QueueKind::Ready),
+            (&self.waiting_queue, QueueKind::Waiting),
+            (&running, QueueKind::Running),
+        ] {
+            queued.extend(queue.iter().map(|pid| (kind, pid)));
+        }
+        for (&id, sem) in &self.semaphores {
+            queued.extend(sem.waiters().into_iter().map(|pid| (QueueKind::Semaphore(id), pid)));
+        }
+        for (kind, pid) in queued {
+            if !self.total_chain.contains_key(&pid) {
+                problems.push(format!("{}中的进程 {} 不在总链中", kind.name(), pid));
+            }
+        }
+
+        problems
+    }
+
+    /// 以总链为准修复队列：移除不在总链中的队列项，按状态把进程放回应在的队列。
+    /// 返回被重新放置的进程数
+    ///
+    /// 处于运行状态的进程多于CPU核数时，多出的进程转为就绪
+    pub(crate) fn repair_consistency(&mut self) -> usize {
+        let mut stale: Vec<u32> = self.total_chain.values()
+            .filter(|pcb| match self.queue_memberships(pcb.pid).as_slice() {
+                [] => !state_matches_queue(&pcb.state, None),
+                [kind] => !state_matches_queue(&pcb.state, Some(*kind)),
+                _ => true,
+            })
+            .map(|pcb| pcb.pid)
+            .collect();
+        stale.sort_unstable();
+
+        // 移除不在总链中的队列项
+        for queue in [&mut self.ready_queue, &mut self.waiting_queue].into_iter().chain(&mut self.cores) {
+            for pid in queue.pids() {
+                if !self.total_chain.contains_key(&pid) {
+                    queue.remove_by_pid(pid);
+                }
+            }
+        }
+        for sem in self.semaphores.values_mut() {
+            for pid in sem.waiters() {
+                if !self.total_chain.contains_key(&pid) {
+                    sem.remove_waiter(pid);
+                }
+            }
+        }
+
+        // 按总链中的状态重新放置不一致的进程
+        for &pid in &stale {
+            self.ready_queue.remove_by_pid(pid);
+            self.waiting_queue.remove_by_pid(pid);
+            self.remove_running(pid);
+            for sem in self.semaphores.values_mut() {
+                sem.remove_waiter(pid);
+            }
+
+            match (&self.total_chain[&pid].state, self.idle_core()) {
+                (ProcessState::Running, Some(core)) => self.cores[core].enqueue(pid),
+                (ProcessState::Running | ProcessState::Ready, _) => {
+                    if let Some(pcb) = self.total_chain.get_mut(&pid) {
+                        pcb.state = ProcessState::Ready;
+                    }
+                    self.admit_ready(pid);
+                }
+                (ProcessState::Waiting | ProcessState::SuspendedReady | ProcessState::SuspendedBlocked, _) => {
+                    self.waiting_queue.enqueue(pid)
+                }
+                (ProcessState::New | ProcessState::Terminated, _) => {}
+            }
+        }
+
+        stale.len()
+    }
+
+    /// 获取所有队列的统一视图
+    pub(crate) fn queues_view(&self) -> QueuesView {
+        QueuesView {
+            ready: self.ready_queue.resolve(&self.total_chain),
+            waiting: self.waiting_queue.resolve(&self.total_chain),
+            running: self.running_queue().resolve(&self.total_chain),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color, logger};
+
+    #[test]
+    fn queues_view_places_each_process_in_exactly_one_queue_in_order() {
+        let mut pm = ProcessManager::new();
+        let low = pm.create_process(2, 10).unwrap();
+        let high = pm.create_process(6, 10).unwrap();
+        let blocked = pm.create_process(4, 10).unwrap();
+        let suspended = pm.create_process(1, 10).unwrap();
+        let extra = pm.create_process(3, 10).unwrap();
+        pm.fill_idle_cores();
+        pm.block_on(blocked, 1).unwrap();
+        pm.suspend_process(suspended).unwrap();
+
+        let view = pm.queues_view();
+        let pids = |pcbs: &[PCB]| pcbs.iter().map(|pcb| pcb.pid).collect::<Vec<_>>();
+        assert_eq!(pids(&view.running), vec![high]);
+        assert_eq!(pids(&view.waiting), vec![blocked, suspended]);
+        assert_eq!(pids(&view.ready), vec![extra, low]);
+
+        // 与实际队列一致，且每个进程只出现在一个队列中
+        assert_eq!(pids(&view.ready), pm.ready_queue.iter().collect::<Vec<_>>());
+        assert_eq!(pids(&view.waiting), pm.waiting_queue.iter().collect::<Vec<_>>());
+        assert_eq!(pids(&view.running), pm.running_pids());
+        let mut all = [pids(&view.ready), pids(&view.waiting), pids(&view.running)].concat();
+        all.sort();
+        assert_eq!(all, vec![low, high, blocked, suspended, extra]);
+    }
+
+    #[test]
+    fn query_process_reports_the_queue_in_each_of_the_three_queues() {
+        let mut pm = ProcessManager::new();
+        let running = pm.create_process(5, 10).unwrap();
+        let ready = pm.create_process(3, 10).unwrap();
+        let waiting = pm.create_process(2, 10).unwrap();
+        pm.fill_idle_cores();
+        pm.block_on(waiting, 1).unwrap();
+
+        let queue_of = |pid: u32| pm.query_process(pid).unwrap().queue;
+        assert_eq!(queue_of(running), Some(QueueKind::Running));
+        assert_eq!(queue_of(ready), Some(QueueKind::Ready));
+        assert_eq!(queue_of(waiting), Some(QueueKind::Waiting));
+        assert!(pm.query_process(99).is_none());
+
+        let view = pm.query_process(ready).unwrap().to_string();
+        let expected = format!("PID {}: 优先级=3, 状态={}, 所在队列=就绪队列", ready, color::state_label(&ProcessState::Ready));
+        assert!(view.starts_with(&expected), "{}", view);
+    }
+
+    #[test]
+    fn list_by_state_sorts_pids_and_warns_about_queue_drift() {
+        let mut pm = ProcessManager::new();
+        let pids: Vec<u32> = (0..4).map(|_| pm.create_process(1, 10).unwrap()).collect();
+        pm.fill_idle_cores();
+        pm.block_on(pids[3], 2).unwrap();
+        pm.block_on(pids[1], 2).unwrap();
+
+        assert_eq!(pm.list_by_state(ProcessState::Running), vec![pids[0]]);
+        assert_eq!(pm.list_by_state(ProcessState::Ready), vec![pids[2]]);
+        assert_eq!(pm.list_by_state(ProcessState::Waiting), vec![pids[1], pids[3]]);
+        assert!(pm.list_by_state(ProcessState::Terminated).is_empty());
+
+        // 总链中的状态与所在队列不一致时仍以总链为准，并输出警告
+        pm.total_chain.get_mut(&pids[2]).unwrap().state = ProcessState::Waiting;
+        logger::start_capture();
+        let waiting = pm.list_by_state(ProcessState::Waiting);
+        let warnings = logger::stop_capture();
+        assert_eq!(waiting, vec![pids[1], pids[2], pids[3]]);
+        assert_eq!(warnings.matches('⚠').count(), 1);
+        assert!(warnings.contains(&format!("进程 {} 的状态为等待，但所在队列为就绪队列", pids[2])), "{}", warnings);
+    }
+
+    #[test]
+    fn verify_consistency_detects_injected_inconsistencies() {
+        let mut pm = ProcessManager::new();
+        let pids: Vec<u32> = (0..3).map(|_| pm.create_process(1, 10).unwrap()).collect();
+        pm.fill_idle_cores();
+        assert!(pm.verify_consistency().is_empty());
+
+        pm.total_chain.get_mut(&pids[1]).unwrap().state = ProcessState::Waiting;
+        pm.waiting_queue.enqueue(pids[2]);
+        pm.ready_queue.enqueue(42);
+        assert_eq!(pm.verify_consistency(), vec![
+            format!("进程 {} 状态为等待，但位于就绪队列", pids[1]),
+            format!("进程 {} 同时位于多个队列: 就绪队列、等待队列", pids[2]),
+            "就绪队列中的进程 42 不在总链中".to_string(),
+        ]);
+
+        pm.total_chain.get_mut(&pids[1]).unwrap().state = ProcessState::Ready;
+        pm.waiting_queue.remove_by_pid(pids[2]);
+        pm.ready_queue.remove_by_pid(42);
+        assert!(pm.verify_consistency().is_empty());
+    }
+
+    #[test]
+    fn peek_and_preview_read_ahead_without_dequeuing() {
+        let mut pm = ProcessManager::new();
+        let pids: Vec<u32> = [4, 3, 2, 1].iter().map(|&priority| pm.create_process(priority, 10).unwrap()).collect();
+
+        assert_eq!(pm.ready_queue.peek(0), Some(pids[0]));
+        assert_eq!(pm.ready_queue.peek(2), Some(pids[2]));
+        assert_eq!(pm.ready_queue.peek(4), None);
+
+        let preview: Vec<u32> = pm.preview_ready(3).iter().map(|pcb| pcb.pid).collect();
+        assert_eq!(preview, pids[..3]);
+        assert_eq!(pm.preview_ready(10).len(), 4);
+        assert!(pm.preview_ready(0).is_empty());
+        assert_eq!(pm.ready_queue.pids(), pids);
+    }
+
+    #[test]
+    fn manually_leaked_blocks_are_reported_as_orphaned() {
+        let mut pm = ProcessManager::new();
+        let kept = pm.create_process(2, 10).unwrap();
+        let lost = pm.create_process(1, 10).unwrap();
+        assert!(pm.find_orphaned_blocks().is_empty());
+
+        // 模拟两种泄漏：进程从总链中丢失，以及绕过进程管理直接占用的块
+        let lost_index = pm.total_chain.remove(&lost).unwrap().pool_index;
+        pm.ready_queue.remove_by_pid(lost);
+        let stray_index = pm.pcb_pool.allocate(0).unwrap();
+        pm.pcb_pool.store_pcb(stray_index, PCB::new(stray_index, 99));
+
+        let mut expected = vec![lost_index, stray_index];
+        expected.sort_unstable();
+        assert_eq!(pm.find_orphaned_blocks(), expected);
+        assert!(!pm.find_orphaned_blocks().contains(&pm.total_chain[&kept].pool_index));
+    }
+
+    #[test]
+    fn a_low_priority_process_is_flagged_as_starving_after_the_threshold() {
+        let mut pm = ProcessManager::new();
+        let high = pm.create_process(10, 100).unwrap();
+        let low = pm.create_process(0, 10).unwrap();
+
+        // 优先级调度下高优先级进程一直占用CPU，低优先级进程每个周期都留在就绪队列
+        pm.run_cycles(8);
+        assert!(pm.detect_starvation(8).is_empty());
+        pm.run_one_cycle();
+        assert_eq!(pm.detect_starvation(8), vec![low]);
+        assert_eq!(pm.total_chain[&low].ready_ticks, 9);
+        assert!(!pm.detect_starvation(8).contains(&high));
+        assert!(pm.detect_starvation(20).is_empty());
+
+        // 被调度运行后不再算作饥饿
+        pm.terminate_process(high).unwrap();
+        pm.run_one_cycle();
+        assert!(pm.detect_starvation(0).is_empty());
+    }
+
+    #[test]
+    fn diagnostics_explain_an_empty_ready_queue_by_the_waiting_processes() {
+        let mut pm = ProcessManager::new();
+        let running = pm.create_process(5, 10).unwrap();
+        let suspended = pm.create_process(3, 10).unwrap();
+        let waiting = pm.create_process(2, 10).unwrap();
+        pm.fill_idle_cores();
+        pm.suspend_process(suspended).unwrap();
+        pm.block_on(waiting, 4).unwrap();
+
+        assert_eq!(pm.schedule_diagnostics(),
+                   "就绪队列为空，但有 2 个进程在等待队列（1 个被挂起，需激活；1 个等待事件，需发出事件唤醒），1 个进程正在运行");
+
+        // 无法调度时调度周期自动输出诊断
+        pm.block(running).unwrap();
+        logger::start_capture();
+        pm.run_one_cycle();
+        let output = logger::stop_capture();
+        assert!(output.contains(
+            "CPU空闲：就绪队列为空，但有 3 个进程在等待队列（1 个被挂起，需激活；1 个等待事件，需发出事件唤醒；1 个被阻塞，需唤醒）"
+        ), "{}", output);
+    }
+}
@@ -1,182 +1,365 @@
-use crate::pcb::PCB;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Write};
 
-/// 进程队列：使用链表实现
-pub struct ProcessQueue {
-    head: Option<Box<Node>>,
-    length: usize,
-}
+use crate::color;
+use crate::pcb::{ProcessState, PCB};
 
-struct Node {
-    pcb: PCB,
-    next: Option<Box<Node>>,
+/// 进程队列：使用双端队列实现，队首入队和队尾出队均为O(1)
+///
+/// 队列中只保存PID，PCB数据统一存放在总链中；
+/// 需要按PCB字段排序或显示时，由调用方传入总链查找
+#[derive(Clone)]
+pub struct ProcessQueue {
+    items: VecDeque<u32>,
 }
 
 impl ProcessQueue {
     pub fn new() -> Self {
         ProcessQueue {
-            head: None,
-            length: 0,
+            items: VecDeque::new(),
         }
     }
 
     /// 普通入队（FIFO）
-    pub fn enqueue(&mut self, pcb: PCB) {
-        let new_node = Box::new(Node {
-            pcb,
-            next: None,
-        });
-
-        if self.head.is_none() {
-            self.head = Some(new_node);
-        } else {
-            let mut current = self.head.as_mut().unwrap();
-            while current.next.is_some() {
-                current = current.next.as_mut().unwrap();
-            }
-            current.next = Some(new_node);
-        }
-
-        self.length += 1;
+    pub fn enqueue(&mut self, pid: u32) {
+        self.items.push_back(pid);
     }
 
-    /// 按优先级入队（优先级高的在前面）
+    /// 按有效优先级入队（优先级高的在前面）
     /// 用于就绪队列，实现优先级调度
-    pub fn enqueue_by_priority(&mut self, pcb: PCB) {
-        let mut new_node = Box::new(Node {
-            pcb,
-            next: None,
+    ///
+    /// 同优先级按入队序号seq排序，序号小的（先入队的）在前，
+    /// 因此无论队列如何重建或重排，同优先级进程都保持先来先到
+    pub fn enqueue_by_priority(&mut self, pid: u32, chain: &HashMap<u32, PCB>) {
+        self.enqueue_ordered(pid, chain, |new, old| {
+            let (new_priority, old_priority) = (new.effective_priority(), old.effective_priority());
+            new_priority > old_priority || (new_priority == old_priority && new.seq < old.seq)
         });
+    }
 
-        // 如果队列为空或新进程优先级高于队首
-        if self.head.is_none() || new_node.pcb.priority > self.head.as_ref().unwrap().pcb.priority {
-            new_node.next = self.head.take();
-            self.head = Some(new_node);
-        } else {
-            // 找到合适的插入位置
-            let mut current = self.head.as_mut().unwrap();
-            while current.next.is_some() &&
-                  current.next.as_ref().unwrap().pcb.priority >= new_node.pcb.priority {
-                current = current.next.as_mut().unwrap();
-            }
-            new_node.next = current.next.take();
-            current.next = Some(new_node);
-        }
+    /// 按自定义顺序入队
+    ///
+    /// 新进程插入到第一个满足 goes_before(新进程, 已有进程) 的元素之前，
+    /// 若没有这样的元素则插入队尾，因此判定相等的进程保持先来先到。
+    /// 新进程或已有进程不在总链中时无法比较，按不排在其前处理
+    pub fn enqueue_ordered<F>(&mut self, pid: u32, chain: &HashMap<u32, PCB>, goes_before: F)
+    where
+        F: Fn(&PCB, &PCB) -> bool,
+    {
+        let position = chain.get(&pid)
+            .and_then(|new| {
+                self.items.iter().position(|old| {
+                    chain.get(old).is_some_and(|old| goes_before(new, old))
+                })
+            })
+            .unwrap_or(self.items.len());
+        self.items.insert(position, pid);
+    }
 
-        self.length += 1;
+    /// 插入到队首（栈式入队，用于LIFO调度）
+    pub fn push_front(&mut self, pid: u32) {
+        self.items.push_front(pid);
     }
 
-    pub fn dequeue(&mut self) -> Option<PCB> {
-        if let Some(node) = self.head.take() {
-            self.head = node.next;
-            self.length -= 1;
-            Some(node.pcb)
-        } else {
-            None
-        }
+    pub fn dequeue(&mut self) -> Option<u32> {
+        self.items.pop_front()
     }
 
-    pub fn front(&self) -> Option<&PCB> {
-        self.head.as_ref().map(|node| &node.pcb)
+    pub fn front(&self) -> Option<u32> {
+        self.items.front().copied()
     }
 
-    pub fn front_mut(&mut self) -> Option<&mut PCB> {
-        self.head.as_mut().map(|node| &mut node.pcb)
+    /// 查看队列中第n个进程（从0开始）的PID，不出队；越界时返回None
+    pub fn peek(&self, n: usize) -> Option<u32> {
+        self.items.get(n).copied()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.head.is_none()
+        self.items.is_empty()
     }
 
     pub fn len(&self) -> usize {
-        self.length
-    }
-
-    /// 根据PID查找并移除进程
-    pub fn remove_by_pid(&mut self, pid: u32) -> Option<PCB> {
-        if let Some(node) = &mut self.head {
-            if node.pcb.pid == pid {
-                // 移除头节点
-                let mut node = self.head.take().unwrap();
-                self.head = node.next.take();
-                self.length -= 1;
-                return Some(node.pcb);
-            }
+        self.items.len()
+    }
+
+    /// 按优先级重新排序
+    ///
+    /// 总链中的优先级或nice值被修改后队列不再有序，逐个按优先级重新插入即可恢复；
+    /// 插入是稳定的，同优先级的进程保持原有的相对顺序
+    pub fn reorder_by_priority(&mut self, chain: &HashMap<u32, PCB>) {
+        let pids = std::mem::take(&mut self.items);
+        for pid in pids {
+            self.enqueue_by_priority(pid, chain);
         }
+    }
 
-        let mut current = self.head.as_mut()?;
-        while let Some(next) = &mut current.next {
-            if next.pcb.pid == pid {
-                let mut node = current.next.take().unwrap();
-                current.next = node.next.take();
-                self.length -= 1;
-                return Some(node.pcb);
+    /// 根据PID移除进程，返回队列中是否有该进程
+    pub fn remove_by_pid(&mut self, pid: u32) -> bool {
+        match self.items.iter().position(|&p| p == pid) {
+            Some(position) => {
+                self.items.remove(position);
+                true
             }
-            current = current.next.as_mut().unwrap();
+            None => false,
         }
-
-        None
     }
 
-    /// 根据PID查找进程（可变引用）
-    /// 注意：由于Rust的借用检查限制，此函数暂不使用
-    /// 改用remove_by_pid + enqueue的方式更新
-    #[allow(dead_code)]
-    pub fn find_mut_by_pid(&mut self, _pid: u32) -> Option<&mut PCB> {
-        // 此函数由于借用检查限制暂时不使用
-        // 实际使用中通过remove_by_pid + enqueue来更新
-        None
+    /// 队列中是否有指定PID的进程
+    pub fn contains_pid(&self, pid: u32) -> bool {
+        self.items.contains(&pid)
     }
 
     /// 获取队列中最后一个进程的PID
     #[allow(dead_code)]
     pub fn get_last_pid(&self) -> Option<u32> {
-        let mut current = self.head.as_ref()?;
-        while let Some(next) = &current.next {
-            current = next;
-        }
-        Some(current.pcb.pid)
+        self.items.back().copied()
     }
 
-    /// 打印队列中的所有进程
-    pub fn print_all(&self) {
+    /// 按队列顺序（从队首到队尾）遍历所有PID
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.items.iter().copied()
+    }
+
+    /// 按队列顺序导出所有PID（需要在遍历时修改队列的场合使用）
+    pub fn pids(&self) -> Vec<u32> {
+        self.iter().collect()
+    }
+
+    /// 按队列顺序从总链中取出所有进程的副本（不在总链中的PID被跳过）
+    pub fn resolve(&self, chain: &HashMap<u32, PCB>) -> Vec<PCB> {
+        self.iter()
+            .filter_map(|pid| chain.get(&pid).cloned())
+            .collect()
+    }
+
+    /// 打印队列中的所有进程（进程信息从总链中查找）
+    pub fn print_all<W: Write>(&self, chain: &HashMap<u32, PCB>, out: &mut W) -> io::Result<()> {
         if self.is_empty() {
-            println!("  (空)");
-            return;
+            writeln!(out, "  (空)")?;
+            return Ok(());
+        }
+
+        for (index, pid) in self.items.iter().enumerate() {
+            match chain.get(pid) {
+                Some(pcb) => writeln!(out, "  [{}] {}", index + 1, pcb.display())?,
+                None => writeln!(out, "  [{}] PID: {} (不在总链中)", index + 1, pid)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 所有队列的统一只读视图
+///
+/// 一次调用即可拿到就绪、等待、运行三个队列的内容（按队列顺序），
+/// 供状态显示和导出等需要同时读取多个队列的场景使用
+pub struct QueuesView {
+    pub ready: Vec<PCB>,
+    pub waiting: Vec<PCB>,
+    pub running: Vec<PCB>,
+}
+
+/// 进程所在的队列
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueueKind {
+    Ready,          // 就绪队列
+    Waiting,        // 等待队列
+    Running,        // 运行队列
+    Semaphore(u32), // 某个信号量的阻塞队列
+}
+
+impl QueueKind {
+    pub fn name(&self) -> String {
+        match self {
+            QueueKind::Ready => "就绪队列".to_string(),
+            QueueKind::Waiting => "等待队列".to_string(),
+            QueueKind::Running => "运行队列".to_string(),
+            QueueKind::Semaphore(id) => format!("信号量 {} 阻塞队列", id),
         }
+    }
+}
+
+/// 进程状态与其所在队列是否一致
+///
+/// 就绪、运行进程应分别位于就绪、运行队列；等待进程位于等待队列或某个信号量的阻塞队列；
+/// 挂起就绪和挂起阻塞的进程位于等待队列；新建和终止的进程不应出现在任何队列中
+pub fn state_matches_queue(state: &ProcessState, queue: Option<QueueKind>) -> bool {
+    matches!(
+        (state, queue),
+        (ProcessState::Ready, Some(QueueKind::Ready)) |
+        (ProcessState::Running, Some(QueueKind::Running)) |
+        (ProcessState::Waiting, Some(QueueKind::Waiting | QueueKind::Semaphore(_))) |
+        (ProcessState::SuspendedReady | ProcessState::SuspendedBlocked, Some(QueueKind::Waiting)) |
+        (ProcessState::New | ProcessState::Terminated, None)
+    )
+}
 
-        let mut current = self.head.as_ref();
-        let mut index = 1;
-        while let Some(node) = current {
-            println!("  [{}] {}", index, node.pcb.display());
-            current = node.next.as_ref();
-            index += 1;
+/// 按运行进程记录的CPU核（pcb.cpu）重建每个核的运行队列
+///
+/// 进程不在总链中、运行进程多于核数、核号超出num_cpus或两个进程记录在同一个核上时返回错误。
+/// 没有记录核号的运行进程（旧版本保存的状态）依次放入编号最小的空闲核，并记下该核号
+pub fn place_running(running: &[u32], chain: &mut HashMap<u32, PCB>, num_cpus: usize) -> Result<Vec<ProcessQueue>, String> {
+    if running.len() > num_cpus {
+        return Err(format!("运行队列中有 {} 个进程，超过CPU核数 {}", running.len(), num_cpus));
+    }
+
+    let mut cores = vec![ProcessQueue::new(); num_cpus];
+    let mut unplaced = Vec::new();
+    for &pid in running {
+        let pcb = chain.get(&pid).ok_or_else(|| format!("队列中的进程 {} 不在总链中", pid))?;
+        match pcb.cpu {
+            Some(core) if core >= num_cpus => {
+                return Err(format!("运行进程 {} 所在的CPU核 {} 超出核数 {}", pid, core, num_cpus));
+            }
+            Some(core) => {
+                if let Some(other) = cores[core].front() {
+                    return Err(format!("运行进程 {} 和 {} 都在CPU核 {} 上", other, pid, core));
+                }
+                cores[core].enqueue(pid);
+            }
+            None => unplaced.push(pid),
         }
     }
 
-    /// 克隆队列（用于快照）
-    pub fn clone(&self) -> Self {
-        let mut new_queue = ProcessQueue::new();
-        let mut current = self.head.as_ref();
-        
-        while let Some(node) = current {
-            new_queue.enqueue(node.pcb.clone());
-            current = node.next.as_ref();
+    for pid in unplaced {
+        // 运行进程不多于核数，总能找到空闲核
+        let Some(core) = cores.iter().position(|queue| queue.is_empty()) else {
+            break;
+        };
+        cores[core].enqueue(pid);
+        if let Some(pcb) = chain.get_mut(&pid) {
+            pcb.cpu = Some(core);
         }
-        
-        new_queue
     }
+    Ok(cores)
 }
 
-impl Clone for ProcessQueue {
-    fn clone(&self) -> Self {
-        let mut new_queue = ProcessQueue::new();
-        let mut current = self.head.as_ref();
-        
-        while let Some(node) = current {
-            new_queue.enqueue(node.pcb.clone());
-            current = node.next.as_ref();
+/// 单个进程的查询结果：总链中的PCB及其当前所在队列
+pub struct PcbView {
+    pub pcb: PCB,
+    pub queue: Option<QueueKind>,   // 不在任何队列中时为None
+}
+
+impl std::fmt::Display for PcbView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let queue = self.queue.map_or("无".to_string(), |queue| queue.name());
+        write!(
+            f,
+            "PID {}: 优先级={}, 状态={}, 所在队列={}, 剩余时间片={}, 剩余运行时间={}",
+            self.pcb.pid,
+            self.pcb.priority,
+            color::state_label(&self.pcb.state),
+            queue,
+            self.pcb.remaining_time,
+            self.pcb.burst_time
+        )
+    }
+}
+
+/// 某个调度周期结束时各队列的长度（用于观察队列深度随时间的变化）
+#[derive(Debug, Clone, Copy)]
+pub struct QueueSample {
+    pub time: u32,      // 采样时的系统时间
+    pub ready: usize,   // 就绪队列长度
+    pub waiting: usize, // 等待队列长度
+    pub running: usize, // 运行队列长度
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 由(pid, 优先级, 入队序号)构造总链
+    fn chain_of(entries: &[(u32, u32, u64)]) -> HashMap<u32, PCB> {
+        entries.iter().map(|&(pid, priority, seq)| {
+            let mut pcb = PCB::new(pid as usize, pid);
+            pcb.priority = priority;
+            pcb.seq = seq;
+            (pid, pcb)
+        }).collect()
+    }
+
+    #[test]
+    fn equal_priorities_dequeue_in_creation_order() {
+        let chain = chain_of(&[(1, 2, 0), (2, 2, 1), (3, 2, 2)]);
+        let mut queue = ProcessQueue::new();
+        for pid in [1, 2, 3] {
+            queue.enqueue_by_priority(pid, &chain);
+        }
+        assert_eq!(queue.pids(), vec![1, 2, 3]);
+
+        // 从打乱的顺序重建（如从快照恢复）后仍按入队序号先来先到
+        let mut rebuilt = ProcessQueue::new();
+        for pid in [3, 1, 2] {
+            rebuilt.enqueue(pid);
+        }
+        rebuilt.reorder_by_priority(&chain);
+        assert_eq!(rebuilt.pids(), vec![1, 2, 3]);
+        assert_eq!((rebuilt.dequeue(), rebuilt.dequeue(), rebuilt.dequeue()), (Some(1), Some(2), Some(3)));
+        assert!(rebuilt.is_empty());
+    }
+
+    #[test]
+    fn queue_keeps_fifo_semantics_and_clones_independently() {
+        let mut queue = ProcessQueue::new();
+        assert!(queue.is_empty());
+        assert_eq!(queue.dequeue(), None);
+        for pid in [1, 2, 3, 4] {
+            queue.enqueue(pid);
+        }
+        assert_eq!((queue.len(), queue.front(), queue.peek(2), queue.get_last_pid()), (4, Some(1), Some(3), Some(4)));
+
+        // 从中间移除后其余元素保持原有顺序，不存在的PID不影响队列
+        assert!(queue.remove_by_pid(3));
+        assert!(!queue.remove_by_pid(3));
+        assert_eq!(queue.pids(), vec![1, 2, 4]);
+
+        let snapshot = queue.clone();
+        assert_eq!(queue.dequeue(), Some(1));
+        queue.push_front(9);
+        queue.enqueue(5);
+        assert_eq!(queue.pids(), vec![9, 2, 4, 5]);
+        assert_eq!(snapshot.pids(), vec![1, 2, 4]);
+        assert!(!snapshot.contains_pid(9));
+    }
+
+    #[test]
+    fn queues_read_pcb_fields_from_the_chain_at_display_time() {
+        let mut chain = chain_of(&[(1, 2, 0), (2, 4, 1)]);
+        let mut queue = ProcessQueue::new();
+        for pid in [1, 2, 9] {
+            queue.enqueue(pid);
+        }
+
+        // 只修改总链：队列中没有副本，显示和取出的都是最新数据
+        chain.get_mut(&1).unwrap().priority = 7;
+        let mut out = Vec::new();
+        queue.print_all(&chain, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("[1] PID: 1, 优先级: 7,"), "{}", text);
+        assert!(text.contains("[2] PID: 2, 优先级: 4,"), "{}", text);
+        assert!(text.contains("[3] PID: 9 (不在总链中)"), "{}", text);
+
+        let resolved = queue.resolve(&chain);
+        assert_eq!(resolved.iter().map(|pcb| (pcb.pid, pcb.priority)).collect::<Vec<_>>(), vec![(1, 7), (2, 4)]);
+    }
+
+    #[test]
+    fn iterator_visits_pids_in_dequeue_order() {
+        let chain = chain_of(&[(1, 1, 0), (2, 5, 1), (3, 3, 2), (4, 5, 3)]);
+        let mut queue = ProcessQueue::new();
+        for pid in [1, 2, 3, 4] {
+            queue.enqueue_by_priority(pid, &chain);
+        }
+
+        let iterated: Vec<u32> = queue.iter().collect();
+        assert_eq!(queue.iter().filter(|pid| chain[pid].priority == 5).count(), 2);
+        let mut dequeued = Vec::new();
+        while let Some(pid) = queue.dequeue() {
+            dequeued.push(pid);
         }
-        
-        new_queue
+        assert_eq!(iterated, dequeued);
+        assert_eq!(iterated, vec![2, 4, 3, 1]);
+        assert_eq!(queue.iter().next(), None);
     }
 }
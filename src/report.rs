@@ -0,0 +1,462 @@
+use std::fs;
+use std::io::{self, Write};
+
+use crate::color;
+use crate::pcb::PCB;
+use crate::queue::QueueSample;
+use crate::summary::SimulationSummary;
+use crate::{ProcessManager, QUEUE_HISTORY_CAPACITY, TOP_CONSUMERS};
+
+/// 输出与导出：系统状态、进程树、甘特图、统计信息、队列长度历史和CSV
+impl ProcessManager {
+    /// 打印进程树
+    ///
+    /// 没有父进程（或父进程已撤销）的进程作为根，子进程按PID顺序缩进显示
+    pub(crate) fn print_process_tree<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "\n=== 进程树 ===")?;
+        let mut roots: Vec<&PCB> = self.total_chain.values()
+            .filter(|pcb| pcb.parent_pid.is_none_or(|parent| !self.total_chain.contains_key(&parent)))
+            .collect();
+        if roots.is_empty() {
+            writeln!(out, "  (空)")?;
+            return Ok(());
+        }
+        roots.sort_by_key(|pcb| pcb.pid);
+        for root in roots {
+            self.print_subtree(root, 0, out)?;
+        }
+        Ok(())
+    }
+
+    fn print_subtree<W: Write>(&self, pcb: &PCB, depth: usize, out: &mut W) -> io::Result<()> {
+        writeln!(out, "{}└─ PID {} (优先级: {}, 状态: {})",
+                      "   ".repeat(depth), pcb.pid, pcb.priority, color::state_label(&pcb.state))?;
+        let mut children: Vec<&PCB> = pcb.children.iter()
+            .filter_map(|pid| self.total_chain.get(pid))
+            .collect();
+        children.sort_by_key(|child| child.pid);
+        for child in children {
+            self.print_subtree(child, depth + 1, out)?;
+        }
+        Ok(())
+    }
+
+    /// 记录当前各队列长度，超过QUEUE_HISTORY_CAPACITY时丢弃最早的采样
+    pub(crate) fn record_queue_metrics(&mut self) {
+        if self.queue_history.len() == QUEUE_HISTORY_CAPACITY {
+            self.queue_history.pop_front();
+        }
+        self.queue_history.push_back(QueueSample {
+            time: self.scheduler.current_time(),
+            ready: self.ready_queue.len(),
+            waiting: self.waiting_queue.len(),
+            running: self.running_pids().len(),
+        });
+    }
+
+    /// 打印队列长度历史，每个采样一行，就绪队列长度附带简单的条形图
+    pub(crate) fn print_queue_history<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "\n=== 队列长度历史（{} 个采样）===", self.queue_history.len())?;
+        if self.queue_history.is_empty() {
+            writeln!(out, "  (暂无记录，执行调度周期后开始采样)")?;
+            return Ok(());
+        }
+        writeln!(out, "  {:>6} {:>6} {:>6} {:>6}", "时间", "就绪", "等待", "运行")?;
+        for sample in &self.queue_history {
+            writeln!(out, "  {:>8} {:>8} {:>8} {:>8} {}",
+                          sample.time, sample.ready, sample.waiting, sample.running, "#".repeat(sample.ready))?;
+        }
+        Ok(())
+    }
+
+    /// 将队列长度历史导出为CSV文件，每个采样一行
+    pub(crate) fn export_queue_history(&self, path: &str) -> io::Result<()> {
+        let mut csv = String::from("time,ready,waiting,running\n");
+        for sample in &self.queue_history {
+            csv.push_str(&format!("{},{},{},{}\n", sample.time, sample.ready, sample.waiting, sample.running));
+        }
+        fs::write(path, csv)
+    }
+
+    /// 将总链导出为CSV文件，每个进程一行，按PID升序
+    ///
+    /// 状态列使用中文状态名，便于直接在表格软件中阅读
+    pub(crate) fn export_csv(&self, path: &str) -> io::Result<()> {
+        let mut pids: Vec<u32> = self.total_chain.keys().copied().collect();
+        pids.sort_unstable();
+
+        let mut csv = String::from(
+            "pid,priority,nice,state,remaining_time,burst_time,total_burst,arrival_time,cpu_time_used,pool_index,memory_kb\n"
+        );
+        for pid in pids {
+            let pcb = &self.total_chain[&pid];
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{},{}\n",
+                pcb.pid, pcb.priority, pcb.nice, pcb.state.to_string(), pcb.remaining_time,
+                pcb.burst_time, pcb.total_burst, pcb.arrival_time, pcb.cpu_time_used, pcb.pool_index,
+                pcb.memory_kb
+            ));
+        }
+        fs::write(path, csv)
+    }
+
+    /// 打印当前状态
+    pub(crate) fn print_status<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let view = self.queues_view();
+
+        writeln!(out, "\n========== 系统状态 ==========")?;
+        writeln!(out, "PCB池: 空闲 {} / 已用 {} / 总计 {}",
+                      self.pcb_pool.get_free_count(),
+                      self.pcb_pool.get_used_count(),
+                      self.pcb_pool.get_pool_size())?;
+        writeln!(out, "调度策略: {}", self.scheduler.policy().name())?;
+        writeln!(out, "抢占模式: {}", if self.scheduler.preemptive() { "开启" } else { "关闭" })?;
+        writeln!(out, "公平激活: {}", if self.fair_activate { "开启" } else { "关闭" })?;
+        writeln!(out, "激活优先级提升: {}", self.activation_boost)?;
+        writeln!(out, "PID复用: {}", if self.recycle_pids { "开启" } else { "关闭" })?;
+        writeln!(out, "挂起策略: {}", self.suspend_policy.name())?;
+        writeln!(out, "总链: {} 个进程", self.total_chain.len())?;
+        writeln!(out, "就绪队列: {} 个进程", view.ready.len())?;
+        writeln!(out, "等待队列: {} 个进程", view.waiting.len())?;
+        writeln!(out, "运行队列: {} 个进程", view.running.len())?;
+
+        if self.num_cpus() == 1 {
+            if let Some(pcb) = view.running.first() {
+                writeln!(out, "当前运行进程: PID={}, 优先级={}, 剩余时间片={}",
+                              pcb.pid, pcb.priority, pcb.remaining_time)?;
+            }
+        } else {
+            writeln!(out, "CPU核数: {}", self.num_cpus())?;
+            for (index, core) in self.cores.iter().enumerate() {
+                let backlog = view.ready.iter().filter(|pcb| pcb.cpu == Some(index)).count();
+                match core.front().and_then(|pid| self.total_chain.get(&pid)) {
+                    Some(pcb) => writeln!(out, "  CPU {}: PID={}, 优先级={}, 剩余时间片={}，归属就绪进程 {} 个",
+                                               index, pcb.pid, pcb.priority, pcb.remaining_time, backlog)?,
+                    None => writeln!(out, "  CPU {}: 空闲，归属就绪进程 {} 个", index, backlog)?,
+                }
+            }
+        }
+
+        writeln!(out, "\n--- 总链详情（所有进程） ---")?;
+        if self.total_chain.is_empty() {
+            writeln!(out, "  (空)")?;
+        } else {
+            let mut processes: Vec<_> = self.total_chain.values().collect();
+            processes.sort_by_key(|p| p.pid);
+            for (i, pcb) in processes.iter().enumerate() {
+                writeln!(out, "  [{}] {}", i + 1, pcb.display())?;
+            }
+        }
+
+        writeln!(out, "\n--- 已终止进程 ---")?;
+        if self.terminated.is_empty() {
+            writeln!(out, "  (空)")?;
+        } else {
+            for (i, pcb) in self.terminated.iter().enumerate() {
+                writeln!(out, "  [{}] {}", i + 1, pcb.display())?;
+            }
+        }
+
+        writeln!(out, "\n--- 就绪队列详情（按调度顺序） ---")?;
+        self.ready_queue.print_all(&self.total_chain, out)?;
+        writeln!(out, "\n--- 等待队列详情 ---")?;
+        self.waiting_queue.print_all(&self.total_chain, out)?;
+        writeln!(out, "\n--- 运行队列详情 ---")?;
+        self.running_queue().print_all(&self.total_chain, out)?;
+
+        if !self.semaphores.is_empty() {
+            writeln!(out, "\n--- 信号量 ---")?;
+            let mut ids: Vec<_> = self.semaphores.keys().copied().collect();
+            ids.sort_unstable();
+            for id in ids {
+                let sem = &self.semaphores[&id];
+                let waiters = sem.waiters();
+                let holder = sem.holder().map_or("无".to_string(), |pid| pid.to_string());
+                let ceiling = sem.ceiling().map_or("无".to_string(), |priority| priority.to_string());
+                writeln!(out, "  信号量 {}: 值={}, 持有者={}, 天花板={}, 阻塞进程={:?}", id, sem.count(), holder, ceiling, waiters)?;
+            }
+        }
+
+        let mut receivers: Vec<u32> = self.mailboxes.iter()
+            .filter(|(_, messages)| !messages.is_empty())
+            .map(|(&pid, _)| pid)
+            .collect();
+        if !receivers.is_empty() {
+            writeln!(out, "\n--- 信箱 ---")?;
+            receivers.sort_unstable();
+            for pid in receivers {
+                writeln!(out, "  进程 {}: {} 条未读消息", pid, self.mailboxes[&pid].len())?;
+            }
+        }
+
+        if !self.pending_arrivals.is_empty() {
+            writeln!(out, "\n--- 尚未到达的定时进程 ---")?;
+            for spec in &self.pending_arrivals {
+                writeln!(out, "  到达时间 {}: 优先级 {}, 运行时间 {}", spec.arrival_time, spec.priority, spec.burst)?;
+            }
+        }
+        writeln!(out, "==============================\n")?;
+        Ok(())
+    }
+
+    /// 解释就绪队列的排序依据
+    ///
+    /// 按调度顺序返回每个就绪进程的PID及当前策略使用的排序键
+    fn ready_order_explanation(&self) -> Vec<(u32, String)> {
+        let policy = self.scheduler.policy();
+        self.ready_queue.resolve(&self.total_chain)
+            .iter()
+            .enumerate()
+            .map(|(i, pcb)| (pcb.pid, policy.ordering_key(pcb, i)))
+            .collect()
+    }
+
+    /// 打印就绪队列的排序依据
+    pub(crate) fn print_ready_order<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "\n=== 就绪队列排序依据（{}） ===", self.scheduler.policy().name())?;
+        let explanation = self.ready_order_explanation();
+        if explanation.is_empty() {
+            writeln!(out, "  (空)")?;
+        } else {
+            for (i, (pid, key)) in explanation.iter().enumerate() {
+                writeln!(out, "  [{}] PID={}: {}", i + 1, pid, key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 打印伙伴系统状态与碎片报告
+    pub(crate) fn print_buddy_status<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        self.pcb_pool.print_status(out)
+    }
+
+    /// 打印PCB池的分配/回收历史时间线
+    pub(crate) fn print_alloc_history<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let history = self.pcb_pool.alloc_history();
+
+        writeln!(out, "\n=== PCB分配历史 ===")?;
+        if history.is_empty() {
+            writeln!(out, "  (空)")?;
+        } else {
+            for event in &history {
+                writeln!(out, "  [时间 {:>4}] {} PCB索引 {} (大小 {})",
+                              event.tick, event.kind.name(), event.index, 1 << event.order)?;
+            }
+        }
+        writeln!(out, "==================\n")?;
+        Ok(())
+    }
+
+    /// 汇总本次模拟的关键指标（退出时显示）
+    pub(crate) fn summary(&self) -> SimulationSummary {
+        let stats = self.scheduler.get_stats();
+        SimulationSummary {
+            created_count: self.created_count,
+            terminated_count: self.terminated_count,
+            alive_count: self.total_chain.len(),
+            total_executed: stats.total_executed,
+            total_switches: stats.total_switches,
+            current_time: stats.current_time,
+            peak_pool_usage: self.pcb_pool.get_peak_used_count(),
+            total_energy: stats.total_energy,
+            avg_turnaround: stats.avg_turnaround,
+            avg_waiting: stats.avg_waiting,
+            pool_size: self.pcb_pool.get_pool_size(),
+        }
+    }
+
+    /// 显示执行历史甘特图
+    pub(crate) fn print_gantt_chart<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        self.scheduler.print_gantt_chart(out)
+    }
+
+    /// 占用CPU时间最多的count个进程（包括已终止的）：(PID, CPU时间)，按CPU时间降序
+    ///
+    /// CPU时间相同时PID小的在前；从未运行过的进程不计入
+    fn top_cpu_consumers(&self, count: usize) -> Vec<(u32, u32)> {
+        let mut consumers: Vec<(u32, u32)> = self.total_chain.values()
+            .chain(&self.terminated)
+            .filter(|pcb| pcb.cpu_time_used > 0)
+            .map(|pcb| (pcb.pid, pcb.cpu_time_used))
+            .collect();
+        consumers.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        consumers.truncate(count);
+        consumers
+    }
+
+    /// 显示调度器统计信息
+    pub(crate) fn show_scheduler_stats<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let mut stats = self.scheduler.get_stats();
+        stats.top_consumers = self.top_cpu_consumers(TOP_CONSUMERS);
+        stats.display(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::SchedulingPolicy;
+
+    #[test]
+    fn gantt_chart_shows_switch_overhead_as_its_own_span() {
+        let mut pm = ProcessManager::new();
+        pm.scheduler.set_context_switch_cost(2);
+        pm.create_process(5, 2).unwrap();
+        pm.create_process(3, 1).unwrap();
+        for _ in 0..3 {
+            pm.run_one_cycle();
+        }
+
+        let mut chart = Vec::new();
+        pm.print_gantt_chart(&mut chart).unwrap();
+        let chart = String::from_utf8(chart).unwrap();
+        let lines: Vec<&str> = chart.lines().collect();
+        let bar = lines.iter().position(|line| line.starts_with('|')).unwrap();
+        // 每次切换占两个时间单位，执行紧接在切换之后，格与格之间没有空隙
+        assert_eq!(lines[bar], "|  CS |  P1 |  P1 |  CS |  P2 |");
+        assert_eq!(lines[bar + 1], "0     2     3     4     6     7");
+        assert_eq!(pm.scheduler.current_time(), 7);
+    }
+
+    #[test]
+    fn sjf_ready_order_explanation_lists_bursts_shortest_first() {
+        let mut pm = ProcessManager::new();
+        pm.set_policy(SchedulingPolicy::Sjf);
+        let long = pm.create_process(1, 9).unwrap();
+        let short = pm.create_process(1, 3).unwrap();
+        let medium = pm.create_process(1, 6).unwrap();
+
+        assert_eq!(pm.ready_order_explanation(), vec![
+            (short, "burst=3".to_string()),
+            (medium, "burst=6".to_string()),
+            (long, "burst=9".to_string()),
+        ]);
+
+        let mut out = Vec::new();
+        pm.print_ready_order(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains(&format!("[1] PID={}: burst=3", short)), "{}", text);
+        assert!(text.contains(&format!("[3] PID={}: burst=9", long)), "{}", text);
+    }
+
+    #[test]
+    fn gantt_chart_follows_round_robin_execution_history() {
+        let mut pm = ProcessManager::new();
+        let mut chart = Vec::new();
+        pm.print_gantt_chart(&mut chart).unwrap();
+        assert!(String::from_utf8(chart).unwrap().contains("(暂无执行记录)"));
+
+        pm.set_policy(SchedulingPolicy::RoundRobin);
+        pm.scheduler.set_default_time_slice(1).unwrap();
+        let first = pm.create_process(1, 2).unwrap();
+        let second = pm.create_process(1, 2).unwrap();
+        for _ in 0..4 {
+            pm.run_one_cycle();
+        }
+        assert_eq!(pm.scheduler.executions_since(0), &[(1, first), (2, second), (3, first), (4, second)]);
+
+        let mut chart = Vec::new();
+        pm.print_gantt_chart(&mut chart).unwrap();
+        let chart = String::from_utf8(chart).unwrap();
+        let lines: Vec<&str> = chart.lines().collect();
+        let bar = lines.iter().position(|line| line.starts_with('|')).unwrap();
+        assert_eq!(lines[bar], "|  P1 |  P2 |  P1 |  P2 |");
+        assert_eq!(lines[bar + 1], "0     1     2     3     4");
+        assert!(!chart.contains("CS: 进程切换开销"));
+    }
+
+    #[test]
+    fn three_level_tree_prints_indented_and_cascades_on_terminate() {
+        let mut pm = ProcessManager::new();
+        let root = pm.create_process(4, 10).unwrap();
+        let child = pm.fork(root).unwrap();
+        let sibling = pm.fork(root).unwrap();
+        let grandchild = pm.fork(child).unwrap();
+        assert_eq!(pm.total_chain[&grandchild].parent_pid, Some(child));
+        assert_eq!(pm.total_chain[&grandchild].priority, 4);
+        assert_eq!(pm.total_chain[&root].children, vec![child, sibling]);
+
+        let mut tree = Vec::new();
+        pm.print_process_tree(&mut tree).unwrap();
+        let tree = String::from_utf8(tree).unwrap();
+        let indent_of = |pid: u32| {
+            let line = tree.lines().find(|line| line.contains(&format!("PID {} ", pid))).unwrap();
+            line.find("└─").unwrap()
+        };
+        assert_eq!((indent_of(root), indent_of(child), indent_of(grandchild), indent_of(sibling)), (0, 3, 6, 3));
+
+        // 撤销中间一层：其子树一并撤销，兄弟和父进程不受影响
+        assert_eq!(pm.terminate_process(child).unwrap(), vec![child, grandchild]);
+        assert!(!pm.total_chain.contains_key(&grandchild));
+        assert_eq!(pm.total_chain[&root].children, vec![sibling]);
+        assert_eq!(pm.pcb_pool.get_used_count(), 2);
+        assert_eq!(pm.terminate_process(root).unwrap(), vec![root, sibling]);
+        assert!(pm.total_chain.is_empty());
+    }
+
+    #[test]
+    fn cpu_time_per_process_sums_to_total_executed() {
+        let mut pm = ProcessManager::new();
+        pm.set_policy(SchedulingPolicy::RoundRobin);
+        pm.scheduler.set_default_time_slice(2).unwrap();
+        let short = pm.create_process(1, 3).unwrap();
+        let medium = pm.create_process(1, 5).unwrap();
+        let long = pm.create_process(1, 20).unwrap();
+        pm.run_cycles(12);
+
+        // 已完成的进程从终止记录中统计，经过多次重新排队计数也不丢失
+        let total: u32 = pm.total_chain.values().chain(&pm.terminated).map(|pcb| pcb.cpu_time_used).sum();
+        assert_eq!(total, pm.scheduler.get_stats().total_executed);
+        assert_eq!(total, 12);
+        assert_eq!(pm.top_cpu_consumers(TOP_CONSUMERS), vec![(medium, 5), (long, 4), (short, 3)]);
+
+        let mut out = Vec::new();
+        pm.show_scheduler_stats(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains(&format!("  1. PID {}: 5 个时间单位", medium)), "{}", text);
+        assert!(text.contains(&format!("  3. PID {}: 3 个时间单位", short)), "{}", text);
+    }
+
+    #[test]
+    fn csv_export_has_a_header_and_one_row_per_process_sorted_by_pid() {
+        let mut pm = ProcessManager::new();
+        let pids: Vec<u32> = [1, 5, 3].iter().map(|&priority| pm.create_process(priority, 10).unwrap()).collect();
+        pm.fill_idle_cores();
+
+        let path = std::env::temp_dir().join(format!("os_exp2_csv_test_{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+        pm.export_csv(path).unwrap();
+        let csv = fs::read_to_string(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 1 + pids.len());
+        assert!(lines[0].starts_with("pid,priority,nice,state,remaining_time,"), "{}", lines[0]);
+        let row_pids: Vec<u32> = lines[1..].iter().map(|line| line.split(',').next().unwrap().parse().unwrap()).collect();
+        assert_eq!(row_pids, pids);
+        let states: Vec<&str> = lines[1..].iter().map(|line| line.split(',').nth(3).unwrap()).collect();
+        assert_eq!(states, vec!["就绪", "运行", "就绪"]);
+    }
+
+    #[test]
+    fn queue_history_records_one_sample_per_cycle() {
+        let mut pm = ProcessManager::new();
+        for _ in 0..3 {
+            pm.create_process(1, 2).unwrap();
+        }
+        pm.run_cycles(5);
+        let samples: Vec<(u32, usize, usize, usize)> = pm.queue_history.iter()
+            .map(|sample| (sample.time, sample.ready, sample.waiting, sample.running))
+            .collect();
+        // 进程完成的周期结束时尚未调度下一个进程，运行队列为空
+        assert_eq!(samples, vec![(1, 2, 0, 1), (2, 2, 0, 0), (3, 1, 0, 1), (4, 1, 0, 0), (5, 0, 0, 1)]);
+
+        // 空闲周期同样采样，超过容量后丢弃最早的采样
+        pm.run_cycles(QUEUE_HISTORY_CAPACITY as u32);
+        assert_eq!(pm.queue_history.len(), QUEUE_HISTORY_CAPACITY);
+        assert_eq!(pm.queue_history.front().unwrap().time, 6);
+
+        pm.reset();
+        assert!(pm.queue_history.is_empty());
+    }
+}
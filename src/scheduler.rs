@@ -1,15 +1,283 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use crate::error::ProcessError;
+use crate::logger::{log, LogLevel};
 use crate::pcb::PCB;
+use crate::workload::Lcg;
+
+/// 调度策略：决定新就绪进程在就绪队列中的位置
+///
+/// 调度时总是从就绪队列队首取进程，因此策略只体现在入队方式上
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SchedulingPolicy {
+    Priority,   // 优先级调度：按优先级插入，优先级高的在前
+    RoundRobin, // 时间片轮转：就绪队列先进先出，时间片用完排到队尾
+    Fcfs,       // 先来先服务：就绪队列先进先出，不按时间片抢占
+    Sjf,        // 短作业优先：剩余运行时间短的在前，不按时间片抢占
+    Lifo,       // 后进先出：新就绪的进程压入队首，最先被调度
+    Mlfq,       // 多级反馈队列：按级别排序，用完时间片降级，级别越低时间片越长
+    Lottery,    // 彩票调度：按彩票数加权随机抽取下一个运行进程
+    Edf,        // 最早截止时间优先：截止时间最近的在前，不按时间片抢占
+    Cfs,        // 完全公平调度：虚拟运行时间最小的在前，时间片用完重新按虚拟运行时间排队
+}
+
+/// 多级反馈队列的级数（第0级优先级最高）
+pub const MLFQ_LEVELS: u8 = 3;
+
+/// 多级反馈队列中各级的时间片：第0级为2，每降一级翻倍
+pub fn mlfq_time_slice(level: u8) -> u32 {
+    2 << level
+}
+
+/// 完全公平调度中权重为1的进程每运行一个时间单位增加的虚拟运行时间
+const CFS_VRUNTIME_SCALE: u64 = 1024;
+
+/// 完全公平调度的权重：有效优先级 + 1（优先级越高、nice越小，权重越大）
+pub fn cfs_weight(pcb: &PCB) -> u64 {
+    pcb.effective_priority() as u64 + 1
+}
+
+/// 进程运行一个时间单位增加的虚拟运行时间，与权重成反比，
+/// 因此长期来看各进程获得的CPU时间与权重成正比
+pub fn cfs_vruntime_delta(pcb: &PCB) -> u64 {
+    CFS_VRUNTIME_SCALE / cfs_weight(pcb)
+}
+
+impl SchedulingPolicy {
+    pub fn name(&self) -> &str {
+        match self {
+            SchedulingPolicy::Priority => "优先级调度",
+            SchedulingPolicy::RoundRobin => "时间片轮转(RR)",
+            SchedulingPolicy::Fcfs => "先来先服务(FCFS)",
+            SchedulingPolicy::Sjf => "短作业优先(SJF)",
+            SchedulingPolicy::Lifo => "后进先出(LIFO)",
+            SchedulingPolicy::Mlfq => "多级反馈队列(MLFQ)",
+            SchedulingPolicy::Lottery => "彩票调度(Lottery)",
+            SchedulingPolicy::Edf => "最早截止时间优先(EDF)",
+            SchedulingPolicy::Cfs => "完全公平调度(CFS)",
+        }
+    }
+
+    /// 描述该策略下某个就绪进程的排序依据
+    ///
+    /// position为进程在就绪队列中的位置（从0开始）
+    pub fn ordering_key(&self, pcb: &PCB, position: usize) -> String {
+        match self {
+            SchedulingPolicy::Priority => format!("priority={}", pcb.effective_priority()),
+            SchedulingPolicy::RoundRobin | SchedulingPolicy::Fcfs => format!("fifo_position={}", position),
+            SchedulingPolicy::Sjf => format!("burst={}", pcb.burst_time),
+            SchedulingPolicy::Lifo => format!("stack_depth={}", position),
+            SchedulingPolicy::Mlfq => format!("level={}", pcb.mlfq_level),
+            SchedulingPolicy::Lottery => format!("tickets={}", pcb.tickets),
+            SchedulingPolicy::Edf => match pcb.deadline {
+                Some(deadline) => format!("deadline={}", deadline),
+                None => "deadline=无".to_string(),
+            },
+            SchedulingPolicy::Cfs => format!("vruntime={}", pcb.vruntime),
+        }
+    }
+
+    /// 按命令行参数中的名称查找策略（不区分大小写）
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "priority" => Some(SchedulingPolicy::Priority),
+            "rr" => Some(SchedulingPolicy::RoundRobin),
+            "fcfs" => Some(SchedulingPolicy::Fcfs),
+            "sjf" => Some(SchedulingPolicy::Sjf),
+            "lifo" => Some(SchedulingPolicy::Lifo),
+            "mlfq" => Some(SchedulingPolicy::Mlfq),
+            "lottery" => Some(SchedulingPolicy::Lottery),
+            "edf" => Some(SchedulingPolicy::Edf),
+            "cfs" => Some(SchedulingPolicy::Cfs),
+            _ => None,
+        }
+    }
+
+    /// 该策略是否在时间片用完时换下运行进程
+    pub fn uses_time_slice(&self) -> bool {
+        !matches!(self, SchedulingPolicy::Fcfs | SchedulingPolicy::Sjf | SchedulingPolicy::Edf)
+    }
+
+    /// 按菜单切换顺序列出所有策略（从优先级调度开始）
+    pub fn all() -> Vec<Self> {
+        let mut policies = vec![SchedulingPolicy::Priority];
+        let mut policy = SchedulingPolicy::Priority.next();
+        while policy != SchedulingPolicy::Priority {
+            policies.push(policy);
+            policy = policy.next();
+        }
+        policies
+    }
+
+    /// 按菜单切换顺序返回下一个策略
+    pub fn next(&self) -> Self {
+        match self {
+            SchedulingPolicy::Priority => SchedulingPolicy::RoundRobin,
+            SchedulingPolicy::RoundRobin => SchedulingPolicy::Fcfs,
+            SchedulingPolicy::Fcfs => SchedulingPolicy::Sjf,
+            SchedulingPolicy::Sjf => SchedulingPolicy::Lifo,
+            SchedulingPolicy::Lifo => SchedulingPolicy::Mlfq,
+            SchedulingPolicy::Mlfq => SchedulingPolicy::Lottery,
+            SchedulingPolicy::Lottery => SchedulingPolicy::Edf,
+            SchedulingPolicy::Edf => SchedulingPolicy::Cfs,
+            SchedulingPolicy::Cfs => SchedulingPolicy::Priority,
+        }
+    }
+}
+
+/// 时间片变化策略：设置后取代按优先级配置的时间片（多级反馈队列仍使用各级固定时间片）
+///
+/// 轮次从0开始，每当运行进程用完时间片重新排队时加1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum QuantumPolicy {
+    Fixed(u32),                                  // 所有进程、所有轮次使用同一时间片
+    Adaptive { start: u32, step: i32, cap: u32 }, // 第n轮为start + step * n，step为正时不超过cap，为负时不低于cap
+}
+
+impl QuantumPolicy {
+    /// 第round轮的时间片，最小为1
+    pub fn quantum(&self, round: u32) -> u32 {
+        let quantum = match *self {
+            QuantumPolicy::Fixed(quantum) => quantum as i64,
+            QuantumPolicy::Adaptive { start, step, cap } => {
+                let value = start as i64 + step as i64 * round as i64;
+                if step >= 0 { value.min(cap as i64) } else { value.max(cap as i64) }
+            }
+        };
+        quantum.clamp(1, u32::MAX as i64) as u32
+    }
+}
+
+impl std::fmt::Display for QuantumPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuantumPolicy::Fixed(quantum) => write!(f, "固定时间片 {}", quantum),
+            QuantumPolicy::Adaptive { start, step, cap } => {
+                write!(f, "自适应时间片（初始 {}，每轮 {:+}，界限 {}）", start, step, cap)
+            }
+        }
+    }
+}
+
+/// 挂起策略：挂起原语完成后是否立即为空闲的CPU核调度就绪进程
+///
+/// 按目标进程的状态，挂起对进程本身的处理与策略无关，策略只决定CPU随后的处理：
+///
+/// | 目标状态 | 目标进程 | KeepIdle | Reschedule |
+/// |----------|----------|----------|------------|
+/// | 就绪 | 转入等待队列，挂起就绪 | 不调度，运行进程不变 | 为空闲核调度就绪进程，运行进程不变 |
+/// | 运行 | 让出CPU，转入等待队列，挂起就绪，记录一次切换 | 该核空闲至下一调度周期 | 立即为该核（及其他空闲核）调度就绪进程 |
+/// | 等待（等待队列中） | 留在等待队列，挂起阻塞，所等事件或I/O不变 | 不调度，运行进程不变 | 为空闲核调度就绪进程，运行进程不变 |
+/// | 等待（信号量上）、已挂起 | 返回错误，不做改动 | 不调度 | 不调度 |
+///
+/// 挂起从不抢占正在运行的其他进程：Reschedule只填补空闲核
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum SuspendPolicy {
+    #[default]
+    KeepIdle,   // 挂起后不调度，空闲的CPU核等下一个调度周期
+    Reschedule, // 挂起后立即为空闲的CPU核调度就绪进程
+}
+
+impl SuspendPolicy {
+    pub fn name(&self) -> &str {
+        match self {
+            SuspendPolicy::KeepIdle => "空闲CPU保持空闲至下一周期",
+            SuspendPolicy::Reschedule => "立即为空闲CPU调度就绪进程",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            SuspendPolicy::KeepIdle => SuspendPolicy::Reschedule,
+            SuspendPolicy::Reschedule => SuspendPolicy::KeepIdle,
+        }
+    }
+}
 
 /// 进程调度器：负责CPU的调度与控制（扩展二）
 ///
-/// 调度策略：
+/// 调度策略（见SchedulingPolicy，可在运行时切换）：
 /// 1. 优先级调度：从就绪队列选择优先级最高的进程
-/// 2. 时间片轮转：每个进程分配固定时间片
-/// 3. 抢占式调度：时间片用完后切换进程
+/// 2. 时间片轮转：每个进程分配固定时间片，用完后排到就绪队列队尾
+/// 3. 先来先服务：按到达顺序调度，进程一直运行直到让出CPU
+/// 4. 短作业优先：剩余运行时间最短的进程最先调度
+/// 5. 后进先出：最近就绪的进程最先调度
+/// 6. 多级反馈队列：新进程进入最高级，用完时间片降一级，同级先来先服务
+/// 7. 彩票调度：每次调度按就绪进程的彩票数加权抽签，中签者运行
+/// 8. 最早截止时间优先：截止时间最近的进程最先调度，没有截止时间的排在最后
+/// 9. 完全公平调度：虚拟运行时间最小的进程最先调度，虚拟运行时间按权重反比增长
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Scheduler {
     total_executed: u32,      // 总执行次数
     total_switches: u32,      // 总切换次数
     current_time: u32,        // 当前系统时间
+    policy: SchedulingPolicy, // 当前调度策略
+    #[serde(default = "default_energy_per_tick")]
+    energy_per_tick: u32,     // 执行一个时间单位的能耗
+    #[serde(default = "default_idle_energy_per_tick")]
+    idle_energy_per_tick: u32, // 每个CPU核空闲一个时间单位的能耗
+    #[serde(default)]
+    total_energy: u64,        // 累计总能耗
+    #[serde(default)]
+    completed: Vec<PCB>,      // 已完成进程的历史记录（用于计算周转/等待时间）
+    #[serde(default)]
+    execution_log: Vec<(u32, u32)>, // 执行记录：(系统时间, PID)，用于绘制甘特图
+    #[serde(default)]
+    switch_log: Vec<(u32, u32)>,    // 有开销的进程切换：(开始时间, 结束时间)，用于绘制甘特图
+    #[serde(default)]
+    idle_cycles: u32,         // 没有进程可执行的周期数
+    #[serde(default)]
+    time_slices: BTreeMap<u32, u32>, // 按优先级配置的时间片，未配置的优先级使用默认时间片
+    #[serde(default = "default_time_slice")]
+    default_time_slice: u32,  // 未单独配置的优先级使用的时间片
+    #[serde(default)]
+    preemptive: bool,         // 优先级调度下，更高优先级的进程就绪时是否立即抢占运行进程
+    #[serde(default = "default_lottery_rng")]
+    lottery_rng: Lcg,         // 彩票调度抽签用的随机数发生器（固定种子，结果可复现）
+    #[serde(default)]
+    missed_deadlines: u32,    // 错过截止时间的进程数
+    #[serde(default)]
+    context_switch_cost: u32, // 每次进程切换消耗的系统时间
+    #[serde(default)]
+    switch_overhead: u32,     // 累计因进程切换损失的时间
+    #[serde(default)]
+    quantum_policy: Option<QuantumPolicy>, // 时间片变化策略（None表示按优先级配置）
+    #[serde(default)]
+    quantum_round: u32,       // 时间片变化策略的当前轮次
+    #[serde(default)]
+    migrations: u32,          // 负载均衡在CPU核之间迁移进程的次数
+}
+
+/// 甘特图每行显示的时间单位数
+const GANTT_CELLS_PER_ROW: usize = 16;
+
+fn default_energy_per_tick() -> u32 {
+    DEFAULT_ENERGY_PER_TICK
+}
+
+const DEFAULT_ENERGY_PER_TICK: u32 = 1;
+
+fn default_idle_energy_per_tick() -> u32 {
+    DEFAULT_IDLE_ENERGY_PER_TICK
+}
+
+/// 空闲能耗默认为0，即只计执行能耗
+const DEFAULT_IDLE_ENERGY_PER_TICK: u32 = 0;
+
+/// 未单独配置时的默认时间片
+const DEFAULT_TIME_SLICE: u32 = 5;
+
+fn default_time_slice() -> u32 {
+    DEFAULT_TIME_SLICE
+}
+
+/// 彩票调度随机数发生器的种子
+const LOTTERY_SEED: u64 = 1;
+
+fn default_lottery_rng() -> Lcg {
+    Lcg::new(LOTTERY_SEED)
 }
 
 impl Scheduler {
@@ -18,25 +286,226 @@ impl Scheduler {
             total_executed: 0,
             total_switches: 0,
             current_time: 0,
+            policy: SchedulingPolicy::Priority,
+            energy_per_tick: DEFAULT_ENERGY_PER_TICK,
+            idle_energy_per_tick: DEFAULT_IDLE_ENERGY_PER_TICK,
+            total_energy: 0,
+            completed: Vec::new(),
+            execution_log: Vec::new(),
+            switch_log: Vec::new(),
+            idle_cycles: 0,
+            time_slices: BTreeMap::new(),
+            default_time_slice: DEFAULT_TIME_SLICE,
+            preemptive: false,
+            lottery_rng: default_lottery_rng(),
+            missed_deadlines: 0,
+            context_switch_cost: 0,
+            switch_overhead: 0,
+            quantum_policy: None,
+            quantum_round: 0,
+            migrations: 0,
+        }
+    }
+
+    /// 当前系统时间
+    pub fn current_time(&self) -> u32 {
+        self.current_time
+    }
+
+    pub fn energy_per_tick(&self) -> u32 {
+        self.energy_per_tick
+    }
+
+    pub fn set_energy_per_tick(&mut self, cost: u32) {
+        self.energy_per_tick = cost;
+    }
+
+    pub fn idle_energy_per_tick(&self) -> u32 {
+        self.idle_energy_per_tick
+    }
+
+    pub fn set_idle_energy_per_tick(&mut self, cost: u32) {
+        self.idle_energy_per_tick = cost;
+    }
+
+    /// 指定优先级的进程获得的时间片（设置了时间片变化策略时按当前轮次计算）
+    pub fn time_slice_for(&self, priority: u32) -> u32 {
+        match self.quantum_policy {
+            Some(policy) => policy.quantum(self.quantum_round),
+            None => self.time_slices.get(&priority).copied().unwrap_or(self.default_time_slice),
+        }
+    }
+
+    pub fn quantum_policy(&self) -> Option<QuantumPolicy> {
+        self.quantum_policy
+    }
+
+    /// 设置时间片变化策略（None恢复按优先级配置），轮次从0重新开始
+    pub fn set_quantum_policy(&mut self, policy: Option<QuantumPolicy>) {
+        self.quantum_policy = policy;
+        self.quantum_round = 0;
+    }
+
+    pub fn quantum_round(&self) -> u32 {
+        self.quantum_round
+    }
+
+    /// 进入下一轮（运行进程用完时间片重新排队时调用）
+    pub fn advance_quantum_round(&mut self) {
+        self.quantum_round = self.quantum_round.saturating_add(1);
+    }
+
+    pub fn default_time_slice(&self) -> u32 {
+        self.default_time_slice
+    }
+
+    /// 设置未单独配置的优先级使用的时间片，时间片为0时返回InvalidTimeSlice，原设置不变
+    pub fn set_default_time_slice(&mut self, slice: u32) -> Result<(), ProcessError> {
+        if slice == 0 {
+            return Err(ProcessError::InvalidTimeSlice(slice));
+        }
+        self.default_time_slice = slice;
+        Ok(())
+    }
+
+    /// 为指定优先级配置时间片，时间片必须大于0
+    ///
+    /// 时间片为0的进程一上CPU就会被换下，永远无法运行，因此拒绝该设置并返回InvalidTimeSlice
+    pub fn set_time_slice(&mut self, priority: u32, slice: u32) -> Result<(), ProcessError> {
+        if slice == 0 {
+            return Err(ProcessError::InvalidTimeSlice(slice));
         }
+        self.time_slices.insert(priority, slice);
+        Ok(())
+    }
+
+    /// 已配置的时间片表：(优先级, 时间片)，按优先级升序
+    pub fn time_slice_table(&self) -> Vec<(u32, u32)> {
+        self.time_slices.iter().map(|(&priority, &slice)| (priority, slice)).collect()
+    }
+
+    pub fn policy(&self) -> SchedulingPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: SchedulingPolicy) {
+        self.policy = policy;
+    }
+
+    /// 是否开启抢占式优先级调度
+    pub fn preemptive(&self) -> bool {
+        self.preemptive
+    }
+
+    pub fn set_preemptive(&mut self, preemptive: bool) {
+        self.preemptive = preemptive;
+    }
+
+    /// 彩票调度抽签
+    ///
+    /// candidates为(PID, 彩票数)，按顺序累加彩票数，抽取[0, 总数)内的随机数，
+    /// 落在哪个进程的区间内即哪个进程中签。没有候选者或彩票总数为0时返回None
+    pub fn draw_lottery(&mut self, candidates: &[(u32, u32)]) -> Option<u32> {
+        let total: u64 = candidates.iter().map(|&(_, tickets)| tickets as u64).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut winning = self.lottery_rng.next_u32() as u64 % total;
+        for &(pid, tickets) in candidates {
+            if winning < tickets as u64 {
+                return Some(pid);
+            }
+            winning -= tickets as u64;
+        }
+        None
     }
 
     /// 执行进程（扩展二：CPU调度）
     ///
-    /// 模拟CPU执行一个时间单位
+    /// 模拟CPU执行一个时间单位，并累计本单位的能耗
     pub fn execute_process(&mut self, pcb: &PCB) {
         self.total_executed += 1;
         self.current_time += 1;
+        self.total_energy += self.energy_per_tick as u64;
+        self.execution_log.push((self.current_time, pcb.pid));
 
-        println!(
+        log(LogLevel::Info, format_args!(
             "  [CPU调度] 执行进程 PID={}, 优先级={}, 剩余时间片={}, 系统时间={}",
             pcb.pid, pcb.priority, pcb.remaining_time, self.current_time
-        );
+        ));
     }
 
-    /// 记录进程切换
+    /// 多核时与本时间单位第一个运行进程并行执行的进程：记录执行，但系统时间不再前进
+    pub fn execute_concurrent(&mut self, pcb: &PCB) {
+        self.total_executed += 1;
+        self.total_energy += self.energy_per_tick as u64;
+        self.execution_log.push((self.current_time, pcb.pid));
+
+        log(LogLevel::Info, format_args!(
+            "  [CPU调度] 并行执行进程 PID={}, 优先级={}, 剩余时间片={}, 系统时间={}",
+            pcb.pid, pcb.priority, pcb.remaining_time, self.current_time
+        ));
+    }
+
+    /// 执行记录的条数，配合executions_since取出之后新增的执行
+    pub fn execution_count(&self) -> usize {
+        self.execution_log.len()
+    }
+
+    /// 第index条之后的执行记录：(该时间单位结束时的系统时间, PID)
+    ///
+    /// 切换开销可能在执行之后继续推进系统时间，因此不能按当前时间查找本周期的执行
+    pub fn executions_since(&self, index: usize) -> &[(u32, u32)] {
+        self.execution_log.get(index..).unwrap_or(&[])
+    }
+
+    /// 记录一个空闲周期：没有进程可执行，系统时间照常前进，累计空闲能耗
+    pub fn record_idle(&mut self) {
+        self.idle_cycles += 1;
+        self.current_time += 1;
+        self.total_energy += self.idle_energy_per_tick as u64;
+        log(LogLevel::Info, format_args!("  [CPU调度] CPU空闲, 系统时间={}", self.current_time));
+    }
+
+    /// 记录一个进程错过了截止时间
+    pub fn record_deadline_miss(&mut self) {
+        self.missed_deadlines += 1;
+    }
+
+    /// 每次进程切换消耗的系统时间
+    pub fn context_switch_cost(&self) -> u32 {
+        self.context_switch_cost
+    }
+
+    pub fn set_context_switch_cost(&mut self, cost: u32) {
+        self.context_switch_cost = cost;
+    }
+
+    /// 多核时记录一个空闲核的空闲周期：系统时间由其他核推进，这里不再前进，但照常累计空闲能耗
+    pub fn record_idle_core(&mut self) {
+        self.idle_cycles += 1;
+        self.total_energy += self.idle_energy_per_tick as u64;
+    }
+
+    /// 记录负载均衡迁移了一个进程
+    pub fn record_migration(&mut self) {
+        self.migrations += 1;
+    }
+
+    /// 记录进程切换：系统时间前进一次切换的开销
     pub fn record_switch(&mut self) {
         self.total_switches += 1;
+        if self.context_switch_cost > 0 {
+            self.switch_log.push((self.current_time, self.current_time + self.context_switch_cost));
+        }
+        self.current_time += self.context_switch_cost;
+        self.switch_overhead += self.context_switch_cost;
+    }
+
+    /// 记录一个已完成的进程（其completion_time应已设置）
+    pub fn record_completion(&mut self, pcb: &PCB) {
+        self.completed.push(pcb.clone());
     }
 
     /// 获取调度统计信息
@@ -45,15 +514,109 @@ impl Scheduler {
             total_executed: self.total_executed,
             total_switches: self.total_switches,
             current_time: self.current_time,
+            total_energy: self.total_energy,
+            completed_count: self.completed.len(),
+            idle_cycles: self.idle_cycles,
+            missed_deadlines: self.missed_deadlines,
+            switch_overhead: self.switch_overhead,
+            migrations: self.migrations,
+            cpu_utilization: self.cpu_utilization(),
+            avg_turnaround: self.average(|p| p.turnaround_time()),
+            avg_waiting: self.average(|p| p.waiting_time()),
+            top_consumers: Vec::new(),
         }
     }
 
-    /// 重置调度器
-    #[allow(dead_code)]
+    /// CPU利用率 = 忙碌周期 / (忙碌周期 + 空闲周期 + 切换开销)，尚未经过任何时间时为None
+    fn cpu_utilization(&self) -> Option<f64> {
+        let total = self.total_executed + self.idle_cycles + self.switch_overhead;
+        if total == 0 {
+            None
+        } else {
+            Some(self.total_executed as f64 / total as f64)
+        }
+    }
+
+    /// 对所有已完成进程的某项指标求平均值
+    fn average<F: Fn(&PCB) -> u32>(&self, metric: F) -> Option<f64> {
+        if self.completed.is_empty() {
+            return None;
+        }
+        let sum: u64 = self.completed.iter().map(|p| metric(p) as u64).sum();
+        Some(sum as f64 / self.completed.len() as f64)
+    }
+
+    /// 重置调度器：清空计数、历史和系统时间，保留调度策略和各项配置
     pub fn reset(&mut self) {
         self.total_executed = 0;
         self.total_switches = 0;
         self.current_time = 0;
+        self.idle_cycles = 0;
+        self.missed_deadlines = 0;
+        self.switch_overhead = 0;
+        self.quantum_round = 0;
+        self.migrations = 0;
+        self.total_energy = 0;
+        self.completed.clear();
+        self.execution_log.clear();
+        self.switch_log.clear();
+    }
+
+    /// 甘特图的各格：(开始时间, 结束时间, 标签)，按开始时间排序
+    ///
+    /// 执行占一个时间单位，有开销的切换占其开销长度，二者之间没有记录的时间为CPU空闲
+    fn gantt_cells(&self) -> Vec<(u32, u32, String)> {
+        let mut spans: Vec<(u32, u32, String)> = self.execution_log.iter()
+            .map(|&(time, pid)| (time - 1, time, format!("P{}", pid)))
+            .chain(self.switch_log.iter().map(|&(start, end)| (start, end, "CS".to_string())))
+            .collect();
+        spans.sort_by_key(|&(start, _, _)| start); // 稳定排序，多核并行的执行保持核的顺序
+
+        let mut cells = Vec::new();
+        let mut covered = 0;
+        for (start, end, label) in spans {
+            if start > covered {
+                cells.push((covered, start, "--".to_string()));
+            }
+            covered = covered.max(end);
+            cells.push((start, end, label));
+        }
+        cells
+    }
+
+    /// 打印执行历史的ASCII甘特图
+    ///
+    /// 每格代表一段时间，格下方标出该段的起始时间，进程执行占一个时间单位，
+    /// CS为进程切换开销，--为CPU空闲，例如：
+    /// |  P1 |  P1 |  CS |  P3 |
+    /// 0     1     2     4     5
+    pub fn print_gantt_chart<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "\n=== 甘特图 ===")?;
+        if self.execution_log.is_empty() {
+            writeln!(out, "  (暂无执行记录)")?;
+            return Ok(());
+        }
+
+        let cells = self.gantt_cells();
+        for row in cells.chunks(GANTT_CELLS_PER_ROW) {
+            let mut bar = String::new();
+            let mut markers = String::new();
+            for (start, _, label) in row {
+                bar.push_str(&format!("|{:>4} ", label));
+                markers.push_str(&format!("{:<6}", start));
+            }
+            bar.push('|');
+            if let Some((_, end, _)) = row.last() {
+                markers.push_str(&end.to_string());
+            }
+            writeln!(out, "{}", bar)?;
+            writeln!(out, "{}", markers)?;
+        }
+        if cells.iter().any(|(_, _, label)| label == "CS" || label == "--") {
+            writeln!(out, "（CS: 进程切换开销，--: CPU空闲）")?;
+        }
+        writeln!(out, "==============\n")?;
+        Ok(())
     }
 }
 
@@ -62,18 +625,99 @@ pub struct SchedulerStats {
     pub total_executed: u32,
     pub total_switches: u32,
     pub current_time: u32,
+    pub total_energy: u64,
+    pub completed_count: usize,
+    pub idle_cycles: u32,
+    pub missed_deadlines: u32,
+    pub switch_overhead: u32,
+    pub migrations: u32,
+    pub cpu_utilization: Option<f64>,
+    pub avg_turnaround: Option<f64>,
+    pub avg_waiting: Option<f64>,
+    pub top_consumers: Vec<(u32, u32)>, // 占用CPU最多的进程：(PID, CPU时间)，由进程管理器填充
 }
 
 impl SchedulerStats {
-    pub fn display(&self) {
-        println!("\n=== 调度器统计信息 ===");
-        println!("总执行次数: {}", self.total_executed);
-        println!("总切换次数: {}", self.total_switches);
-        println!("系统时间: {}", self.current_time);
+    pub fn display<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "\n=== 调度器统计信息 ===")?;
+        writeln!(out, "总执行次数: {}", self.total_executed)?;
+        writeln!(out, "总切换次数: {}", self.total_switches)?;
+        writeln!(out, "系统时间: {}", self.current_time)?;
+        writeln!(out, "空闲周期: {}", self.idle_cycles)?;
+        writeln!(out, "切换开销: {}", self.switch_overhead)?;
+        if self.migrations > 0 {
+            writeln!(out, "进程迁移次数: {}", self.migrations)?;
+        }
+        if let Some(utilization) = self.cpu_utilization {
+            writeln!(out, "CPU利用率: {:.2}%", utilization * 100.0)?;
+        }
+        writeln!(out, "总能耗: {}", self.total_energy)?;
+        writeln!(out, "已完成进程数: {}", self.completed_count)?;
+        writeln!(out, "错过截止时间: {}", self.missed_deadlines)?;
+        if let (Some(turnaround), Some(waiting)) = (self.avg_turnaround, self.avg_waiting) {
+            writeln!(out, "平均周转时间: {:.2}", turnaround)?;
+            writeln!(out, "平均等待时间: {:.2}", waiting)?;
+        }
         if self.total_switches > 0 {
-            println!("平均执行时间: {:.2}",
-                     self.total_executed as f64 / self.total_switches as f64);
+            writeln!(out, "平均执行时间: {:.2}",
+                          self.total_executed as f64 / self.total_switches as f64)?;
+        }
+        if !self.top_consumers.is_empty() {
+            writeln!(out, "CPU占用排行:")?;
+            for (rank, (pid, cpu_time)) in self.top_consumers.iter().enumerate() {
+                writeln!(out, "  {}. PID {}: {} 个时间单位", rank + 1, pid, cpu_time)?;
+            }
+        }
+        writeln!(out, "====================\n")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lottery_selects_in_proportion_to_tickets_with_a_fixed_seed() {
+        const DRAWS: usize = 10_000;
+        let candidates = [(1, 9), (2, 1)];
+        let draw_all = || {
+            let mut scheduler = Scheduler::new();
+            (0..DRAWS).map(|_| scheduler.draw_lottery(&candidates).unwrap()).collect::<Vec<u32>>()
+        };
+
+        let winners = draw_all();
+        assert_eq!(winners, draw_all());
+        // 9张彩票对1张：高彩票进程应中签约90%
+        let share = winners.iter().filter(|&&pid| pid == 1).count() as f64 / DRAWS as f64;
+        assert!((0.87..=0.93).contains(&share), "中签比例 {}", share);
+
+        let mut scheduler = Scheduler::new();
+        assert_eq!(scheduler.draw_lottery(&[(1, 0), (2, 0)]), None);
+        assert_eq!(scheduler.draw_lottery(&[]), None);
+    }
+
+    #[test]
+    fn adaptive_quantum_follows_its_formula_over_rounds() {
+        let growing = QuantumPolicy::Adaptive { start: 2, step: 1, cap: 5 };
+        let shrinking = QuantumPolicy::Adaptive { start: 6, step: -2, cap: 2 };
+        let sequence = |policy: QuantumPolicy| (0..6).map(|round| policy.quantum(round)).collect::<Vec<u32>>();
+        assert_eq!(sequence(growing), vec![2, 3, 4, 5, 5, 5]);
+        assert_eq!(sequence(shrinking), vec![6, 4, 2, 2, 2, 2]);
+        assert_eq!(sequence(QuantumPolicy::Fixed(3)), vec![3; 6]);
+        assert_eq!(QuantumPolicy::Adaptive { start: 1, step: -1, cap: 0 }.quantum(3), 1);
+
+        // 调度器按当前轮次给出时间片，与优先级无关；重新设置策略时轮次归零
+        let mut scheduler = Scheduler::new();
+        scheduler.set_quantum_policy(Some(growing));
+        let mut slices = Vec::new();
+        for _ in 0..5 {
+            slices.push(scheduler.time_slice_for(7));
+            scheduler.advance_quantum_round();
         }
-        println!("====================\n");
+        assert_eq!(slices, vec![2, 3, 4, 5, 5]);
+        scheduler.set_quantum_policy(Some(shrinking));
+        assert_eq!(scheduler.quantum_round(), 0);
+        assert_eq!(scheduler.time_slice_for(0), 6);
     }
 }
@@ -1,5 +1,15 @@
-use crate::queue::ProcessQueue;
-use crate::pcb::PCB;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+
+use crate::error::ProcessError;
+use crate::event_log::Primitive;
+use crate::queue::{place_running, ProcessQueue};
+use crate::pcb::{ProcessState, PCB};
+use crate::buddy_system::BuddySystem;
+use crate::{ProcessManager, UNDO_DEPTH};
 
 /// 系统快照：记录系统在某一时刻的状态
 ///
@@ -7,6 +17,7 @@ use crate::pcb::PCB;
 /// 1. 初始化快照：系统刚启动，PCB池满，运行队列空
 /// 2. 运行前快照：进程执行前的状态
 /// 3. 运行后快照：进程执行后的状态
+#[derive(Clone)]
 pub struct Snapshot {
     name: String,
     free_pcb_count: usize,
@@ -71,8 +82,411 @@ impl Snapshot {
     }
 }
 
+/// 快照的可序列化形式：队列按顺序展开为PCB数组
+///
+/// 加载时只取队列中的PID，进程数据以总链为准
+#[derive(Serialize, Deserialize)]
+struct SnapshotData {
+    name: String,
+    free_pcb_count: usize,
+    used_pcb_count: usize,
+    total_chain: Vec<PCB>,
+    ready_queue: Vec<PCB>,
+    waiting_queue: Vec<PCB>,
+    running_queue: Vec<PCB>,
+}
+
+impl Snapshot {
+    /// 按队列顺序从快照的总链中取出进程
+    fn expand(&self, queue: &ProcessQueue) -> Vec<PCB> {
+        queue.iter()
+            .filter_map(|pid| self.total_chain.iter().find(|pcb| pcb.pid == pid).cloned())
+            .collect()
+    }
+
+    fn to_data(&self) -> SnapshotData {
+        SnapshotData {
+            name: self.name.clone(),
+            free_pcb_count: self.free_pcb_count,
+            used_pcb_count: self.used_pcb_count,
+            total_chain: self.total_chain.clone(),
+            ready_queue: self.expand(&self.ready_queue),
+            waiting_queue: self.expand(&self.waiting_queue),
+            running_queue: self.expand(&self.running_queue),
+        }
+    }
+
+    fn from_data(data: SnapshotData) -> Self {
+        let build_queue = |pcbs: Vec<PCB>| {
+            let mut queue = ProcessQueue::new();
+            for pcb in pcbs {
+                queue.enqueue(pcb.pid);
+            }
+            queue
+        };
+
+        Snapshot {
+            name: data.name,
+            free_pcb_count: data.free_pcb_count,
+            used_pcb_count: data.used_pcb_count,
+            total_chain: data.total_chain,
+            ready_queue: build_queue(data.ready_queue),
+            waiting_queue: build_queue(data.waiting_queue),
+            running_queue: build_queue(data.running_queue),
+        }
+    }
+
+    /// 将快照序列化为JSON（供外部可视化工具使用）
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.to_data()).expect("快照序列化不会失败")
+    }
+
+    /// 将快照保存到文件（JSON格式）
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+
+    /// 从文件加载快照
+    pub fn load(path: &str) -> io::Result<Snapshot> {
+        let json = fs::read_to_string(path)?;
+        let data: SnapshotData = serde_json::from_str(&json)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(Self::from_data(data))
+    }
+
+    /// 比较两个快照：self为之前的状态，other为之后的状态
+    pub fn diff(&self, other: &Snapshot) -> SnapshotDiff {
+        let pids = |snapshot: &Snapshot| -> BTreeSet<u32> {
+            snapshot.total_chain.iter().map(|pcb| pcb.pid).collect()
+        };
+        let (before, after) = (pids(self), pids(other));
+        let delta = |before: usize, after: usize| after as i64 - before as i64;
+
+        SnapshotDiff {
+            before_name: self.name.clone(),
+            after_name: other.name.clone(),
+            added: after.difference(&before).copied().collect(),
+            removed: before.difference(&after).copied().collect(),
+            ready_delta: delta(self.ready_queue.len(), other.ready_queue.len()),
+            waiting_delta: delta(self.waiting_queue.len(), other.waiting_queue.len()),
+            running_delta: delta(self.running_queue.len(), other.running_queue.len()),
+            free_delta: delta(self.free_pcb_count, other.free_pcb_count),
+            used_delta: delta(self.used_pcb_count, other.used_pcb_count),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[allow(dead_code)]
+    pub fn free_pcb_count(&self) -> usize {
+        self.free_pcb_count
+    }
+
+    #[allow(dead_code)]
+    pub fn used_pcb_count(&self) -> usize {
+        self.used_pcb_count
+    }
+
+    /// 快照中的所有进程（按PID排序）
+    pub fn total_chain(&self) -> &[PCB] {
+        &self.total_chain
+    }
+
+    pub fn ready_queue(&self) -> &ProcessQueue {
+        &self.ready_queue
+    }
+
+    pub fn waiting_queue(&self) -> &ProcessQueue {
+        &self.waiting_queue
+    }
+
+    pub fn running_queue(&self) -> &ProcessQueue {
+        &self.running_queue
+    }
+}
+
 impl std::fmt::Display for Snapshot {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.display())
     }
 }
+
+/// 两个快照之间的差异
+pub struct SnapshotDiff {
+    pub before_name: String,
+    pub after_name: String,
+    pub added: Vec<u32>,        // 之后的总链中新出现的进程（升序）
+    pub removed: Vec<u32>,      // 之后的总链中已不存在的进程（升序）
+    pub ready_delta: i64,       // 就绪队列长度变化
+    pub waiting_delta: i64,     // 等待队列长度变化
+    pub running_delta: i64,     // 运行队列长度变化
+    pub free_delta: i64,        // PCB池空闲数变化
+    pub used_delta: i64,        // PCB池已用数变化
+}
+
+impl std::fmt::Display for SnapshotDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "\n=== 快照比较: {} → {} ===", self.before_name, self.after_name)?;
+        writeln!(f, "新增进程: {:?}", self.added)?;
+        writeln!(f, "移除进程: {:?}", self.removed)?;
+        writeln!(f, "就绪队列: {:+}, 等待队列: {:+}, 运行队列: {:+}",
+                 self.ready_delta, self.waiting_delta, self.running_delta)?;
+        write!(f, "PCB池: 空闲 {:+}, 已用 {:+}", self.free_delta, self.used_delta)
+    }
+}
+
+/// 快照与撤销：命名快照、每个原语之后的自动快照和撤销栈
+impl ProcessManager {
+    /// 获取快照
+    pub(crate) fn get_snapshot(&self, name: &str) -> Snapshot {
+        // 将HashMap转换为Vec用于快照
+        let mut total_chain_vec: Vec<PCB> = self.total_chain.values().cloned().collect();
+        total_chain_vec.sort_by_key(|p| p.pid);
+
+        Snapshot::new(
+            name,
+            self.pcb_pool.get_free_count(),
+            self.pcb_pool.get_used_count(),
+            total_chain_vec,
+            self.ready_queue.clone(),
+            self.waiting_queue.clone(),
+            self.running_queue(),
+        )
+    }
+
+    /// 记录撤销点：保存当前完整状态，超过UNDO_DEPTH时丢弃最早的记录
+    pub(crate) fn checkpoint(&mut self) {
+        if self.undo_stack.len() == UNDO_DEPTH {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(self.to_state());
+    }
+
+    /// 在执行primitive之前生成一个以当前系统时间和该原语命名的自动快照，超过容量时丢弃最早的快照
+    pub(crate) fn record_auto_snapshot(&mut self, primitive: &Primitive) {
+        if self.auto_snapshot_capacity == 0 {
+            return;
+        }
+        while self.auto_snapshots.len() >= self.auto_snapshot_capacity {
+            self.auto_snapshots.pop_front();
+        }
+        let name = format!("自动快照 (系统时间 {}，{:?} 之前)", self.scheduler.current_time(), primitive);
+        self.auto_snapshots.push_back(self.get_snapshot(&name));
+    }
+
+    /// 设置自动快照容量（0表示关闭自动快照），超出新容量的最早快照被丢弃
+    pub(crate) fn set_auto_snapshot_capacity(&mut self, capacity: usize) {
+        self.auto_snapshot_capacity = capacity;
+        while self.auto_snapshots.len() > capacity {
+            self.auto_snapshots.pop_front();
+        }
+    }
+
+    /// 撤销上一步：恢复到最近一个撤销点时的完整状态
+    ///
+    /// 进程、队列、PCB池占用、信号量和调度器计数全部回到操作前的样子；
+    /// 伙伴系统的分配历史不属于保存状态，撤销后从空开始记录
+    pub(crate) fn undo_last(&mut self) -> Result<(), String> {
+        let state = self.undo_stack.pop_back().ok_or("没有可撤销的操作")?;
+        let mut restored = Self::from_state(state)?;
+        restored.undo_stack = std::mem::take(&mut self.undo_stack);
+        restored.auto_snapshots = std::mem::take(&mut self.auto_snapshots);
+        restored.auto_snapshot_capacity = self.auto_snapshot_capacity;
+        restored.use_color = self.use_color;
+        *self = restored;
+        Ok(())
+    }
+
+    /// 从快照恢复进程状态
+    ///
+    /// 重建总链和三个队列，并按快照中各进程的pool_index重新标记伙伴系统的已用块。
+    /// 快照不包含调度器计数和已终止记录，这些保持当前值不变；
+    /// 快照也不包含信号量，恢复后信号量表清空，原先阻塞在信号量上的进程放入等待队列，
+    /// 可手动激活。next_pid和入队序号至少推进到快照中的最大值之后，避免与恢复的进程冲突
+    pub(crate) fn restore_from_snapshot(&mut self, snap: Snapshot) -> Result<(), ProcessError> {
+        let occupied: Vec<(usize, usize)> = snap.total_chain().iter()
+            .map(|pcb| (pcb.pool_index, pcb.block_order()))
+            .collect();
+        let mut pcb_pool = BuddySystem::from_occupied(self.pcb_pool.get_pool_size(), &occupied)
+            .ok_or(ProcessError::InvalidSnapshot("PCB索引越界或重复".to_string()))?;
+        pcb_pool.set_strategy(self.pcb_pool.strategy());
+
+        let mut total_chain = HashMap::new();
+        for pcb in snap.total_chain() {
+            pcb_pool.store_pcb(pcb.pool_index, pcb.clone());
+            total_chain.insert(pcb.pid, pcb.clone());
+        }
+        let cores = place_running(&snap.running_queue().pids(), &mut total_chain, self.num_cpus())
+            .map_err(ProcessError::InvalidSnapshot)?;
+
+        // 队列中的每个PID都必须在总链中
+        let rebuild = |queue: &ProcessQueue| -> Result<ProcessQueue, ProcessError> {
+            let mut rebuilt = ProcessQueue::new();
+            for pid in queue.iter() {
+                if !total_chain.contains_key(&pid) {
+                    return Err(ProcessError::InvalidSnapshot(format!("队列中的进程 {} 不在总链中", pid)));
+                }
+                rebuilt.enqueue(pid);
+            }
+            Ok(rebuilt)
+        };
+        self.ready_queue = rebuild(snap.ready_queue())?;
+        self.waiting_queue = rebuild(snap.waiting_queue())?;
+        self.cores = cores;
+
+        self.semaphores.clear();
+        let mut orphans: Vec<u32> = total_chain.values()
+            .filter(|pcb| matches!(
+                pcb.state,
+                ProcessState::Waiting | ProcessState::SuspendedReady | ProcessState::SuspendedBlocked
+            ))
+            .filter(|pcb| !self.waiting_queue.contains_pid(pcb.pid))
+            .map(|pcb| pcb.pid)
+            .collect();
+        orphans.sort_unstable();
+        for pid in orphans {
+            self.waiting_queue.enqueue(pid);
+        }
+
+        let max_pid = total_chain.keys().copied().max().unwrap_or(0);
+        let max_seq = total_chain.values().map(|pcb| pcb.seq + 1).max().unwrap_or(0);
+        self.next_pid = self.next_pid.max(max_pid + 1);
+        self.next_seq = self.next_seq.max(max_seq);
+        self.free_pids.retain(|pid| !total_chain.contains_key(pid));
+        self.total_chain = total_chain;
+        self.pcb_pool = pcb_pool;
+        self.event_log.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::SchedulingPolicy;
+
+    #[test]
+    fn auto_snapshots_are_bounded_and_drop_the_oldest() {
+        let mut pm = ProcessManager::new();
+        pm.set_auto_snapshot_capacity(3);
+        for priority in 1..=5 {
+            pm.create_process(priority, 10).unwrap();
+            assert!(pm.auto_snapshots.len() <= 3);
+        }
+
+        // 保留的是第3、4、5次创建之前的快照
+        let sizes: Vec<usize> = pm.auto_snapshots.iter().map(|snapshot| snapshot.total_chain().len()).collect();
+        assert_eq!(sizes, vec![2, 3, 4]);
+
+        // 嵌套调用的原语不单独生成快照：fork内部的创建只算一次
+        pm.fork(1).unwrap();
+        assert_eq!(pm.auto_snapshots.back().unwrap().total_chain().len(), 5);
+        assert_eq!(pm.auto_snapshots.len(), 3);
+
+        pm.set_auto_snapshot_capacity(0);
+        pm.run_one_cycle();
+        assert!(pm.auto_snapshots.is_empty());
+    }
+
+    #[test]
+    fn snapshot_json_lists_queues_in_order() {
+        let mut pm = ProcessManager::with_pool_size(8);
+        pm.set_policy(SchedulingPolicy::Priority);
+        let pids: Vec<u32> = [1, 4, 2, 3].iter().map(|&priority| pm.create_process(priority, 10).unwrap()).collect();
+        pm.run_one_cycle();
+        pm.suspend_process(pids[0]).unwrap();
+        assert_eq!(pm.running_pids(), vec![pids[1]]);
+
+        let json: serde_json::Value = serde_json::from_str(&pm.get_snapshot("导出").to_json()).unwrap();
+        let queue_pids = |key: &str| -> Vec<u64> {
+            json[key].as_array().unwrap().iter().map(|pcb| pcb["pid"].as_u64().unwrap()).collect()
+        };
+        assert_eq!(json["name"], "导出");
+        assert_eq!(json["free_pcb_count"], 4);
+        assert_eq!(json["used_pcb_count"], 4);
+        assert_eq!(queue_pids("total_chain"), vec![1, 2, 3, 4]);
+        assert_eq!(queue_pids("ready_queue"), vec![pids[3] as u64, pids[2] as u64]);
+        assert_eq!(queue_pids("waiting_queue"), vec![pids[0] as u64]);
+        assert_eq!(queue_pids("running_queue"), vec![pids[1] as u64]);
+        assert_eq!(json["ready_queue"][0]["priority"], 3);
+    }
+
+    #[test]
+    fn snapshot_file_restores_into_a_fresh_manager() {
+        let mut pm = ProcessManager::new();
+        let pids: Vec<u32> = (1..=3).map(|priority| pm.create_process(priority, 10).unwrap()).collect();
+        pm.run_one_cycle();
+        pm.suspend_process(pids[0]).unwrap();
+
+        let path = std::env::temp_dir().join(format!("os_exp2_snapshot_test_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        pm.get_snapshot("检查点").save(path).unwrap();
+        let loaded = Snapshot::load(path);
+        fs::remove_file(path).unwrap();
+
+        // 新的管理器next_pid从1开始，恢复后必须跳过快照中已有的PID
+        let mut restored = ProcessManager::new();
+        restored.restore_from_snapshot(loaded.unwrap()).unwrap();
+        assert!(restored.verify_consistency().is_empty(), "{:?}", restored.verify_consistency());
+        assert_eq!(restored.ready_queue.pids(), pm.ready_queue.pids());
+        assert_eq!(restored.waiting_queue.pids(), vec![pids[0]]);
+        assert_eq!(restored.running_pids(), pm.running_pids());
+        assert_eq!(restored.pcb_pool.allocated_blocks(), pm.pcb_pool.allocated_blocks());
+
+        let newcomer = restored.create_process(1, 10).unwrap();
+        assert_eq!(newcomer, 4);
+        assert!(!pids.iter().any(|pid| restored.total_chain[pid].pool_index == restored.total_chain[&newcomer].pool_index));
+    }
+
+    #[test]
+    fn diff_reports_one_create_and_one_terminate() {
+        let mut pm = ProcessManager::new();
+        let running = pm.create_process(5, 10).unwrap();
+        let ready = pm.create_process(3, 10).unwrap();
+        pm.fill_idle_cores();
+        let before = pm.get_snapshot("之前");
+
+        let created = pm.create_process(1, 10).unwrap();
+        let middle = pm.get_snapshot("创建后");
+        let grown = before.diff(&middle);
+        assert_eq!((grown.free_delta, grown.used_delta), (-1, 1));
+        pm.terminate_process(running).unwrap();
+        let after = pm.get_snapshot("之后");
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.added, vec![created]);
+        assert_eq!(diff.removed, vec![running]);
+        assert_eq!((diff.ready_delta, diff.waiting_delta, diff.running_delta), (1, 0, -1));
+        assert_eq!((diff.free_delta, diff.used_delta), (0, 0));
+        assert_eq!(after.ready_queue().pids(), vec![ready, created]);
+
+        // 反向比较时增删互换
+        let reverse = after.diff(&before);
+        assert_eq!((reverse.added, reverse.removed), (vec![running], vec![created]));
+        assert_eq!(reverse.running_delta, 1);
+    }
+
+    #[test]
+    fn accessors_expose_the_snapshot_contents() {
+        let mut pm = ProcessManager::with_pool_size(8);
+        let pids: Vec<u32> = [4, 2, 3].iter().map(|&priority| pm.create_process(priority, 10).unwrap()).collect();
+        pm.fill_idle_cores();
+        pm.block_on(pids[2], 1).unwrap();
+
+        let snapshot = pm.get_snapshot("检查点");
+        assert_eq!(snapshot.name(), "检查点");
+        assert_eq!((snapshot.free_pcb_count(), snapshot.used_pcb_count()), (5, 3));
+        let chain: Vec<u32> = snapshot.total_chain().iter().map(|pcb| pcb.pid).collect();
+        assert_eq!(chain, pids);
+        assert_eq!(snapshot.running_queue().pids(), vec![pids[0]]);
+        assert_eq!(snapshot.ready_queue().pids(), vec![pids[1]]);
+        assert_eq!(snapshot.waiting_queue().pids(), vec![pids[2]]);
+
+        // 快照是只读副本，之后的修改不影响它
+        pm.terminate_process(pids[1]).unwrap();
+        assert_eq!(snapshot.ready_queue().pids(), vec![pids[1]]);
+        assert_eq!(snapshot.total_chain().len(), 3);
+    }
+}
@@ -0,0 +1,327 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io;
+
+use crate::buddy_system::{AllocStrategy, BuddySystem};
+use crate::color;
+use crate::event_log::EventLog;
+use crate::ipc::{Message, Semaphore};
+use crate::pcb::{PriorityRange, PCB};
+use crate::queue::{place_running, ProcessQueue};
+use crate::scheduler::{Scheduler, SuspendPolicy};
+use crate::workload::ProcessSpec;
+use crate::{ProcessManager, AUTO_SNAPSHOT_CAPACITY};
+
+/// 进程管理器的完整状态（用于保存/恢复）
+///
+/// 与快照不同，这里保存了恢复系统所需的全部信息：
+/// 队列只记录PID顺序，PCB数据以总链为准；
+/// 伙伴系统只记录已占用的块，空闲链表在加载时重新构建
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ManagerState {
+    pub total_chain: Vec<PCB>,      // 总链中的所有进程（按PID排序）
+    pub ready_queue: Vec<u32>,      // 就绪队列中的PID（按队列顺序）
+    pub waiting_queue: Vec<u32>,    // 等待队列中的PID
+    pub running_queue: Vec<u32>,    // 运行队列中的PID（按CPU核编号，依次放回各核）
+    #[serde(default = "default_num_cpus")]
+    pub num_cpus: usize,            // CPU核数
+    #[serde(default)]
+    pub terminated: Vec<PCB>,       // 已终止进程记录
+    pub pool_size: usize,           // PCB池大小
+    pub occupied: Vec<(usize, usize)>, // 伙伴系统中已分配的块：(起始索引, 阶数)
+    #[serde(default)]
+    pub alloc_strategy: AllocStrategy,  // 伙伴系统的分配策略
+    pub next_pid: u32,
+    #[serde(default)]
+    pub recycle_pids: bool,         // 是否复用已撤销进程的PID
+    #[serde(default)]
+    pub free_pids: Vec<u32>,        // 可复用的PID栈
+    pub scheduler: Scheduler,       // 调度器计数与策略
+    pub fair_activate: bool,
+    #[serde(default)]
+    pub activation_boost: u32,
+    #[serde(default)]
+    pub default_max_lifetime: Option<u32>, // 新建进程的最长存活周期数
+    #[serde(default)]
+    pub suspend_policy: SuspendPolicy,
+    #[serde(default)]
+    pub created_count: u32,
+    #[serde(default)]
+    pub terminated_count: u32,
+    #[serde(default)]
+    pub semaphores: Vec<SemaphoreState>, // 信号量（按编号排序）
+    #[serde(default)]
+    pub priority_range: PriorityRange,   // 允许用户指定的优先级范围
+    #[serde(default)]
+    pub mailboxes: Vec<(u32, Vec<Message>)>, // 各进程信箱中的消息（按PID排序，消息按到达顺序）
+    #[serde(default)]
+    pub pending_arrivals: Vec<ProcessSpec>,  // 尚未到达的定时进程（按到达时间排序）
+}
+
+/// 单个信号量的保存形式：阻塞进程只记录PID顺序
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SemaphoreState {
+    pub id: u32,
+    pub count: i32,
+    pub waiters: Vec<u32>,
+    #[serde(default)]
+    pub holder: Option<u32>,
+    #[serde(default)]
+    pub ceiling: Option<u32>,
+}
+
+fn default_num_cpus() -> usize {
+    1
+}
+
+/// 保存与恢复：进程管理器与ManagerState之间的转换，以及读写状态文件
+impl ProcessManager {
+    /// 导出完整的系统状态
+    pub(crate) fn to_state(&self) -> ManagerState {
+        let mut total_chain: Vec<PCB> = self.total_chain.values().cloned().collect();
+        total_chain.sort_by_key(|p| p.pid);
+
+        let mut mailboxes: Vec<(u32, Vec<Message>)> = self.mailboxes.iter()
+            .filter(|(_, messages)| !messages.is_empty())
+            .map(|(&pid, messages)| (pid, messages.iter().cloned().collect()))
+            .collect();
+        mailboxes.sort_by_key(|(pid, _)| *pid);
+
+        let mut semaphores: Vec<SemaphoreState> = self.semaphores.iter()
+            .map(|(&id, sem)| SemaphoreState {
+                id,
+                count: sem.count(),
+                waiters: sem.waiters(),
+                holder: sem.holder(),
+                ceiling: sem.ceiling(),
+            })
+            .collect();
+        semaphores.sort_by_key(|s| s.id);
+
+        ManagerState {
+            total_chain,
+            ready_queue: self.ready_queue.pids(),
+            waiting_queue: self.waiting_queue.pids(),
+            running_queue: self.running_pids(),
+            num_cpus: self.num_cpus(),
+            terminated: self.terminated.clone(),
+            pool_size: self.pcb_pool.get_pool_size(),
+            occupied: self.pcb_pool.allocated_blocks(),
+            alloc_strategy: self.pcb_pool.strategy(),
+            next_pid: self.next_pid,
+            recycle_pids: self.recycle_pids,
+            free_pids: self.free_pids.clone(),
+            scheduler: self.scheduler.clone(),
+            fair_activate: self.fair_activate,
+            activation_boost: self.activation_boost,
+            default_max_lifetime: self.default_max_lifetime,
+            suspend_policy: self.suspend_policy,
+            created_count: self.created_count,
+            terminated_count: self.terminated_count,
+            semaphores,
+            priority_range: self.priority_range,
+            mailboxes,
+            pending_arrivals: self.pending_arrivals.clone(),
+        }
+    }
+
+    /// 从完整状态重建进程管理器
+    ///
+    /// 伙伴系统的空闲链表由占用情况重新构建，队列按保存的PID顺序重建，
+    /// 其中的每个PID都必须在总链中。状态内部不一致时返回错误
+    pub(crate) fn from_state(state: ManagerState) -> Result<Self, String> {
+        let mut pcb_pool = BuddySystem::from_occupied(state.pool_size, &state.occupied)
+            .ok_or("PCB池占用信息无效")?;
+        pcb_pool.set_strategy(state.alloc_strategy);
+
+        let mut total_chain = HashMap::new();
+        for pcb in state.total_chain {
+            if !state.occupied.iter().any(|&(index, _)| index == pcb.pool_index) {
+                return Err(format!("进程 {} 的PCB索引 {} 未被占用", pcb.pid, pcb.pool_index));
+            }
+            pcb_pool.store_pcb(pcb.pool_index, pcb.clone());
+            total_chain.insert(pcb.pid, pcb);
+        }
+        let cores = place_running(&state.running_queue, &mut total_chain, state.num_cpus.max(1))?;
+
+        let build_queue = |pids: &[u32]| -> Result<ProcessQueue, String> {
+            let mut queue = ProcessQueue::new();
+            for &pid in pids {
+                if !total_chain.contains_key(&pid) {
+                    return Err(format!("队列中的进程 {} 不在总链中", pid));
+                }
+                queue.enqueue(pid);
+            }
+            Ok(queue)
+        };
+        let ready_queue = build_queue(&state.ready_queue)?;
+        let waiting_queue = build_queue(&state.waiting_queue)?;
+
+        let mut semaphores = HashMap::new();
+        for sem in &state.semaphores {
+            let waiters = build_queue(&sem.waiters)?.pids();
+            semaphores.insert(sem.id, Semaphore::restore(sem.count, waiters, sem.holder, sem.ceiling));
+        }
+        let next_sem_id = state.semaphores.iter().map(|s| s.id + 1).max().unwrap_or(1);
+
+        let mut mailboxes = HashMap::new();
+        for (pid, messages) in state.mailboxes {
+            if !total_chain.contains_key(&pid) {
+                return Err(format!("信箱的接收进程 {} 不在总链中", pid));
+            }
+            mailboxes.insert(pid, VecDeque::from(messages));
+        }
+
+        // 保证新分配的PID和入队序号不会与已有进程冲突
+        let max_pid = total_chain.keys().copied().max().unwrap_or(0);
+        let next_seq = total_chain.values().map(|pcb| pcb.seq + 1).max().unwrap_or(0);
+        let mut free_pids = state.free_pids;
+        free_pids.retain(|pid| !total_chain.contains_key(pid));
+
+        Ok(ProcessManager {
+            pcb_pool,
+            total_chain,
+            ready_queue,
+            waiting_queue,
+            cores,
+            terminated: state.terminated,
+            scheduler: state.scheduler,
+            next_pid: state.next_pid.max(max_pid + 1),
+            recycle_pids: state.recycle_pids,
+            free_pids,
+            next_seq,
+            semaphores,
+            mailboxes,
+            next_sem_id,
+            fair_activate: state.fair_activate,
+            activation_boost: state.activation_boost,
+            default_max_lifetime: state.default_max_lifetime,
+            suspend_policy: state.suspend_policy,
+            created_count: state.created_count,
+            terminated_count: state.terminated_count,
+            undo_stack: VecDeque::new(),
+            auto_snapshots: VecDeque::new(),
+            auto_snapshot_capacity: AUTO_SNAPSHOT_CAPACITY,
+            priority_range: state.priority_range,
+            use_color: color::default_enabled(),
+            queue_history: VecDeque::new(),
+            pending_arrivals: state.pending_arrivals,
+            cycles_since_balance: 0,
+            event_log: EventLog::default(),
+            primitive_depth: 0,
+        })
+    }
+
+    /// 保存完整系统状态到文件（JSON格式）
+    pub(crate) fn save_state(&self, path: &str) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(&self.to_state())
+            .map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    /// 从文件加载完整系统状态，替换当前状态
+    pub(crate) fn load_state(&mut self, path: &str) -> io::Result<()> {
+        let json = fs::read_to_string(path)?;
+        let state: ManagerState = serde_json::from_str(&json)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let use_color = self.use_color;
+        *self = Self::from_state(state)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.use_color = use_color;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2个核、3个就绪进程：运行一个周期后两个核各运行一个进程，剩下一个等待；
+    /// 然后撤销0号核上的进程，使仍在运行的进程位于1号核
+    fn two_cores_with_core_zero_idle() -> (ProcessManager, u32) {
+        let mut pm = ProcessManager::new();
+        pm.set_num_cpus(2);
+        let pids: Vec<u32> = (0..3).map(|_| pm.create_process(1, 20).unwrap()).collect();
+
+        pm.run_one_cycle();
+        let running = pm.running_pids();
+        assert_eq!(running.len(), 2);
+        assert!(running.iter().all(|pid| pm.total_chain[pid].cpu_time_used == 1));
+        assert_eq!(pm.ready_queue.len(), 1);
+        assert!(pids.iter().any(|pid| pm.ready_queue.contains_pid(*pid)));
+
+        let on_core_zero = pm.cores[0].front().unwrap();
+        let on_core_one = pm.cores[1].front().unwrap();
+        pm.terminate_process(on_core_zero).unwrap();
+        assert!(pm.cores[0].is_empty());
+        (pm, on_core_one)
+    }
+
+    #[test]
+    fn from_state_keeps_running_processes_on_their_cores() {
+        let (pm, on_core_one) = two_cores_with_core_zero_idle();
+        let restored = ProcessManager::from_state(pm.to_state()).unwrap();
+        assert!(restored.cores[0].is_empty());
+        assert_eq!(restored.cores[1].front(), Some(on_core_one));
+    }
+
+    #[test]
+    fn restore_from_snapshot_keeps_running_processes_on_their_cores() {
+        let (mut pm, on_core_one) = two_cores_with_core_zero_idle();
+        let snapshot = pm.get_snapshot("测试");
+        pm.restore_from_snapshot(snapshot).unwrap();
+        assert!(pm.cores[0].is_empty());
+        assert_eq!(pm.cores[1].front(), Some(on_core_one));
+    }
+
+    #[test]
+    fn from_state_rejects_invalid_core_placement() {
+        let mut pm = ProcessManager::new();
+        pm.set_num_cpus(2);
+        for _ in 0..2 {
+            pm.create_process(1, 20).unwrap();
+        }
+        pm.run_one_cycle();
+        let running = pm.running_pids();
+
+        let mut state = pm.to_state();
+        for pcb in state.total_chain.iter_mut().filter(|pcb| running.contains(&pcb.pid)) {
+            pcb.cpu = Some(0);
+        }
+        assert!(ProcessManager::from_state(state).err().unwrap().contains("都在CPU核 0 上"));
+
+        let mut state = pm.to_state();
+        let pcb = state.total_chain.iter_mut().find(|pcb| pcb.pid == running[0]).unwrap();
+        pcb.cpu = Some(2);
+        assert!(ProcessManager::from_state(state).err().unwrap().contains("超出核数"));
+    }
+
+    #[test]
+    fn save_then_load_restores_an_identical_consistent_system() {
+        let mut pm = ProcessManager::new();
+        pm.set_num_cpus(2);
+        let pids: Vec<u32> = (0..5).map(|i| pm.create_process(i, 10 + i).unwrap()).collect();
+        pm.run_cycles(3);
+        pm.suspend_process(pids[4]).unwrap();
+        pm.block_on(pids[3], 7).unwrap();
+        let sem = pm.sem_create(0);
+        pm.run_cycles(1);
+        let holder = pm.running_pids()[0];
+        pm.sem_wait(sem, holder).unwrap();
+        assert!(pm.verify_consistency().is_empty());
+
+        let path = std::env::temp_dir().join(format!("os_exp2_state_test_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        pm.save_state(path).unwrap();
+        let mut loaded = ProcessManager::new();
+        let result = loaded.load_state(path);
+        fs::remove_file(path).unwrap();
+        result.unwrap();
+
+        assert!(loaded.verify_consistency().is_empty(), "{:?}", loaded.verify_consistency());
+        assert_eq!(serde_json::to_value(loaded.to_state()).unwrap(), serde_json::to_value(pm.to_state()).unwrap());
+        assert_eq!(loaded.running_pids(), pm.running_pids());
+        assert_eq!(loaded.pcb_pool.allocated_blocks(), pm.pcb_pool.allocated_blocks());
+    }
+}
@@ -0,0 +1,63 @@
+use std::fmt;
+
+/// 模拟总结：退出时汇总整个会话的关键指标
+pub struct SimulationSummary {
+    pub created_count: u32,         // 共创建的进程数
+    pub terminated_count: u32,      // 共撤销的进程数
+    pub alive_count: usize,         // 退出时仍存在的进程数
+    pub total_executed: u32,        // 总执行次数
+    pub total_switches: u32,        // 总切换次数
+    pub current_time: u32,          // 结束时的系统时间
+    pub peak_pool_usage: usize,     // PCB池使用峰值
+    pub pool_size: usize,           // PCB池大小
+    pub total_energy: u64,          // 总能耗
+    pub avg_turnaround: Option<f64>, // 平均周转时间（没有已完成进程时为None）
+    pub avg_waiting: Option<f64>,   // 平均等待时间
+}
+
+/// 压力测试报告
+pub struct StressReport {
+    pub rounds: u32,                // 执行的轮数
+    pub alloc_success: u32,         // 创建成功次数
+    pub alloc_failure: u32,         // 因PCB池已满而创建失败的次数
+    pub terminated: u32,            // 撤销的进程数
+    pub violations: Vec<String>,    // 检查中发现的不变量问题
+}
+
+impl fmt::Display for StressReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "\n========== 压力测试报告 ==========")?;
+        writeln!(f, "轮数: {}", self.rounds)?;
+        writeln!(f, "分配成功: {}", self.alloc_success)?;
+        writeln!(f, "分配失败（池满）: {}", self.alloc_failure)?;
+        writeln!(f, "撤销进程: {}", self.terminated)?;
+        if self.violations.is_empty() {
+            writeln!(f, "✓ 未发现不变量问题")?;
+        } else {
+            writeln!(f, "✗ 发现 {} 处不变量问题:", self.violations.len())?;
+            for violation in &self.violations {
+                writeln!(f, "  - {}", violation)?;
+            }
+        }
+        write!(f, "==================================")
+    }
+}
+
+impl fmt::Display for SimulationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "\n========== 模拟总结 ==========")?;
+        writeln!(f, "创建进程总数: {}", self.created_count)?;
+        writeln!(f, "撤销进程总数: {}", self.terminated_count)?;
+        writeln!(f, "剩余进程数: {}", self.alive_count)?;
+        if let (Some(turnaround), Some(waiting)) = (self.avg_turnaround, self.avg_waiting) {
+            writeln!(f, "平均周转时间: {:.2}", turnaround)?;
+            writeln!(f, "平均等待时间: {:.2}", waiting)?;
+        }
+        writeln!(f, "总执行次数: {}", self.total_executed)?;
+        writeln!(f, "总切换次数: {}", self.total_switches)?;
+        writeln!(f, "系统时间: {}", self.current_time)?;
+        writeln!(f, "PCB池使用峰值: {} / {}", self.peak_pool_usage, self.pool_size)?;
+        writeln!(f, "总能耗: {}", self.total_energy)?;
+        write!(f, "==============================")
+    }
+}
@@ -0,0 +1,228 @@
+use std::io::{self, BufRead, Write};
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+use crate::menu::{self, MENU_ITEMS};
+use crate::ProcessManager;
+
+/// 状态面板左侧起始列（菜单列表占用其左侧）
+const STATUS_COLUMN: u16 = 44;
+
+/// 按键对应的菜单操作
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Moved,                // 光标移动，只需重绘
+    Select(&'static str), // 执行该按键对应的菜单选项
+    Quit,                 // 退出（与选择菜单项0相同）
+    Ignored,              // 无关按键
+}
+
+/// 菜单光标：当前选中的菜单项，上下移动时首尾循环
+pub struct MenuCursor {
+    selected: usize,
+}
+
+impl MenuCursor {
+    pub fn new() -> Self {
+        MenuCursor { selected: 0 }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// 处理一个按键，返回应执行的操作（不涉及终端，便于单独验证分派逻辑）
+    ///
+    /// 上下方向键移动光标，Home/End跳到首尾，回车选择当前项，Esc退出；
+    /// 直接输入某个菜单项的单字符按键时跳到该项
+    pub fn handle_key(&mut self, code: KeyCode) -> Action {
+        let last = MENU_ITEMS.len() - 1;
+        match code {
+            KeyCode::Up => self.selected = if self.selected == 0 { last } else { self.selected - 1 },
+            KeyCode::Down => self.selected = if self.selected == last { 0 } else { self.selected + 1 },
+            KeyCode::Home => self.selected = 0,
+            KeyCode::End => self.selected = last,
+            KeyCode::Enter => return Action::Select(MENU_ITEMS[self.selected].0),
+            KeyCode::Esc => return Action::Quit,
+            KeyCode::Char(c) => {
+                let key = c.to_ascii_lowercase().to_string();
+                match MENU_ITEMS.iter().position(|(item, _)| *item == key) {
+                    Some(position) => self.selected = position,
+                    None => return Action::Ignored,
+                }
+            }
+            _ => return Action::Ignored,
+        }
+        Action::Moved
+    }
+}
+
+/// 状态面板的内容：系统时间、调度策略、各队列长度及每个CPU核上的进程
+fn status_lines(pm: &ProcessManager) -> Vec<String> {
+    let mut lines = vec![
+        "系统状态".to_string(),
+        format!("系统时间: {}", pm.scheduler.current_time()),
+        format!("调度策略: {}", pm.scheduler.policy().name()),
+        format!("PCB池: 已用 {} / {}", pm.pcb_pool.get_used_count(), pm.pcb_pool.get_pool_size()),
+        format!("总链: {} 个进程", pm.total_chain.len()),
+        format!("就绪队列: {} 个进程", pm.ready_queue.len()),
+        format!("等待队列: {} 个进程", pm.waiting_queue.len()),
+    ];
+    for (index, core) in pm.cores.iter().enumerate() {
+        match core.front().and_then(|pid| pm.total_chain.get(&pid)) {
+            Some(pcb) => lines.push(format!("CPU {}: PID {} (剩余时间片 {})", index, pcb.pid, pcb.remaining_time)),
+            None => lines.push(format!("CPU {}: 空闲", index)),
+        }
+    }
+    lines
+}
+
+/// 绘制一帧：左侧为菜单（选中项反色显示，放不下时随光标滚动），右侧为状态面板
+fn draw<W: Write>(out: &mut W, pm: &ProcessManager, cursor: &MenuCursor) -> io::Result<()> {
+    let (_, height) = terminal::size()?;
+    let visible = (height as usize).saturating_sub(2).max(1);
+    let top = cursor.selected().saturating_sub(visible - 1);
+
+    queue!(out, Clear(ClearType::All), MoveTo(0, 0), Print("↑/↓ 选择，回车执行，Esc 退出"))?;
+    for (row, (index, (key, label))) in MENU_ITEMS.iter().enumerate().skip(top).take(visible).enumerate() {
+        queue!(out, MoveTo(0, row as u16 + 2))?;
+        if index == cursor.selected() {
+            queue!(out, SetAttribute(Attribute::Reverse), Print(format!("> {}. {}", key, label)), SetAttribute(Attribute::Reset))?;
+        } else {
+            queue!(out, Print(format!("  {}. {}", key, label)))?;
+        }
+    }
+    for (row, line) in status_lines(pm).iter().enumerate() {
+        queue!(out, MoveTo(STATUS_COLUMN, row as u16 + 2), Print(line))?;
+    }
+    out.flush()
+}
+
+/// 进入全屏菜单：隐藏光标并切换到备用屏幕，按键不回显
+fn enter_screen<W: Write>(out: &mut W) -> io::Result<()> {
+    terminal::enable_raw_mode()?;
+    execute!(out, EnterAlternateScreen, Hide)
+}
+
+/// 离开全屏菜单，恢复普通的行输入输出
+fn leave_screen<W: Write>(out: &mut W) -> io::Result<()> {
+    execute!(out, Show, LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()
+}
+
+/// 运行TUI菜单：方向键选择菜单项，回车执行，直到选择退出
+///
+/// 执行选项时暂时离开全屏模式，选项所需的参数与文本菜单一样逐行输入，
+/// 完成后按回车返回菜单。输入结束时与选择退出相同；出错时先恢复终端再返回错误
+pub fn run_tui(pm: &mut ProcessManager) -> io::Result<()> {
+    let mut out = io::stdout();
+    let result = tui_loop(pm, &mut out);
+    if result.is_err() {
+        let _ = leave_screen(&mut out);
+    }
+    result
+}
+
+fn tui_loop<W: Write>(pm: &mut ProcessManager, out: &mut W) -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut cursor = MenuCursor::new();
+
+    enter_screen(out)?;
+    loop {
+        draw(out, pm, &cursor)?;
+        let Event::Key(key) = event::read()? else {
+            continue; // 窗口大小变化等事件只需重绘
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let choice = match cursor.handle_key(key.code) {
+            Action::Select(choice) => choice,
+            Action::Quit => "0",
+            Action::Moved | Action::Ignored => continue,
+        };
+
+        leave_screen(out)?;
+        writeln!(out, "\n> {}", choice)?;
+        let keep_running = match menu::execute_choice(pm, choice, &mut input, out) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return end_of_input(pm, out),
+            result => result?,
+        };
+        if !keep_running {
+            return Ok(());
+        }
+        write!(out, "\n按回车返回菜单...")?;
+        out.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return end_of_input(pm, out);
+        }
+        enter_screen(out)?;
+    }
+}
+
+/// 输入已结束：与文本菜单相同，输出模拟总结后退出
+fn end_of_input<W: Write>(pm: &ProcessManager, out: &mut W) -> io::Result<()> {
+    writeln!(out, "\n输入已结束，退出程序")?;
+    writeln!(out, "{}", pm.summary())?;
+    writeln!(out, "感谢使用！再见！")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arrow_keys_wrap_around_the_menu() {
+        let last = MENU_ITEMS.len() - 1;
+        let mut cursor = MenuCursor::new();
+        assert_eq!(cursor.handle_key(KeyCode::Up), Action::Moved);
+        assert_eq!(cursor.selected(), last);
+        assert_eq!(cursor.handle_key(KeyCode::Down), Action::Moved);
+        assert_eq!(cursor.selected(), 0);
+        cursor.handle_key(KeyCode::Down);
+        assert_eq!(cursor.selected(), 1);
+        cursor.handle_key(KeyCode::End);
+        assert_eq!(cursor.selected(), last);
+        cursor.handle_key(KeyCode::Home);
+        assert_eq!(cursor.selected(), 0);
+    }
+
+    #[test]
+    fn enter_selects_the_highlighted_item() {
+        let mut cursor = MenuCursor::new();
+        assert_eq!(cursor.handle_key(KeyCode::Enter), Action::Select(MENU_ITEMS[0].0));
+        cursor.handle_key(KeyCode::Down);
+        cursor.handle_key(KeyCode::Down);
+        assert_eq!(cursor.handle_key(KeyCode::Enter), Action::Select(MENU_ITEMS[2].0));
+    }
+
+    #[test]
+    fn typed_key_jumps_to_its_item() {
+        let mut cursor = MenuCursor::new();
+        assert_eq!(cursor.handle_key(KeyCode::Char('Z')), Action::Moved);
+        assert_eq!(cursor.handle_key(KeyCode::Enter), Action::Select("z"));
+        assert_eq!(cursor.handle_key(KeyCode::Char('#')), Action::Ignored);
+        assert_eq!(cursor.handle_key(KeyCode::Tab), Action::Ignored);
+        assert_eq!(cursor.handle_key(KeyCode::Enter), Action::Select("z"));
+        assert_eq!(cursor.handle_key(KeyCode::Esc), Action::Quit);
+    }
+
+    #[test]
+    fn status_panel_shows_every_core() {
+        let mut pm = ProcessManager::new();
+        pm.set_num_cpus(2);
+        let pid = pm.create_process(3, 10).unwrap();
+        pm.run_one_cycle();
+
+        let lines = status_lines(&pm);
+        assert!(lines.contains(&"总链: 1 个进程".to_string()));
+        assert!(lines.iter().any(|line| line.starts_with(&format!("CPU 0: PID {}", pid))));
+        assert!(lines.contains(&"CPU 1: 空闲".to_string()));
+    }
+}
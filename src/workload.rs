@@ -0,0 +1,353 @@
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+
+use crate::error::ProcessError;
+use crate::event_log::{describe, Primitive};
+use crate::logger::{self, log, LogLevel};
+use crate::pcb::{PriorityRange, PCB};
+use crate::scheduler::{SchedulerStats, SchedulingPolicy};
+use crate::summary::StressReport;
+use crate::{ProcessManager, MAX_RUN_CYCLES};
+
+/// 线性同余伪随机数生成器
+///
+/// 参数取自Knuth的MMIX：x' = a * x + c (mod 2^64)。
+/// 同一种子总是产生相同的序列，用于生成可复现的测试负载和彩票调度；
+/// 可随调度器一起保存，加载后继续产生相同的后续序列
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Lcg {
+    state: u64,
+}
+
+const LCG_MULTIPLIER: u64 = 6364136223846793005;
+const LCG_INCREMENT: u64 = 1442695040888963407;
+
+impl Lcg {
+    pub fn new(seed: u64) -> Self {
+        Lcg { state: seed }
+    }
+
+    /// 下一个32位伪随机数（取状态的高32位，低位的周期较短）
+    pub fn next_u32(&mut self) -> u32 {
+        self.state = self.state
+            .wrapping_mul(LCG_MULTIPLIER)
+            .wrapping_add(LCG_INCREMENT);
+        (self.state >> 32) as u32
+    }
+
+    /// [low, high] 区间内的伪随机数
+    pub fn range(&mut self, low: u32, high: u32) -> u32 {
+        low + self.next_u32() % (high - low + 1)
+    }
+}
+
+/// 随机负载中进程运行时间的范围
+pub const WORKLOAD_BURST: (u32, u32) = (1, 10);
+
+/// 压力测试选择撤销对象时使用的随机种子（保证可复现）
+const STRESS_SEED: u64 = 20240601;
+
+/// 可重复使用的进程描述：按同一组描述创建进程，总能得到相同的负载
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProcessSpec {
+    pub priority: u32,
+    pub burst: u32,
+    #[serde(default)]
+    pub arrival_time: u32, // 到达时间（系统时间），到达前不创建进程
+}
+
+/// 按种子生成count个进程描述：优先级在priority_range内，运行时间在WORKLOAD_BURST内
+pub fn random_specs(count: usize, seed: u64, priority_range: PriorityRange) -> Vec<ProcessSpec> {
+    let mut rng = Lcg::new(seed);
+    (0..count)
+        .map(|_| {
+            let priority = rng.range(priority_range.min, priority_range.max);
+            let burst = rng.range(WORKLOAD_BURST.0, WORKLOAD_BURST.1);
+            ProcessSpec { priority, burst, arrival_time: 0 }
+        })
+        .collect()
+}
+
+/// 负载：随机负载生成、定时到达、调度策略对比和PCB池压力测试
+impl ProcessManager {
+    /// 按种子生成可复现的随机负载：创建count个进程，优先级和运行时间均为伪随机数
+    ///
+    /// 相同的种子总是产生相同的优先级和运行时间序列。PCB池满时停止创建，
+    /// 返回成功创建的进程PID
+    pub(crate) fn generate_workload(&mut self, count: usize, seed: u64) -> Vec<u32> {
+        let mut created = Vec::new();
+        for spec in random_specs(count, seed, self.priority_range) {
+            match self.create_process(spec.priority, spec.burst) {
+                Ok(pid) => created.push(pid),
+                Err(e) => {
+                    log(LogLevel::Error, format_args!("{}", e));
+                    break;
+                }
+            }
+        }
+        created
+    }
+
+    /// 登记一个定时进程：到达时间晚于当前系统时间时暂不创建，
+    /// 由调度周期在到达时自动创建；否则立即创建。返回立即创建的进程PID
+    pub(crate) fn schedule_arrival(&mut self, spec: ProcessSpec) -> Result<Option<u32>, ProcessError> {
+        self.logged(
+            Primitive::ScheduleArrival { spec },
+            |pm| pm.schedule_arrival_unlogged(spec),
+            |result| describe(result, |created| match created {
+                Some(pid) => format!("pid {}", pid),
+                None => "已登记".to_string(),
+            }),
+        )
+    }
+
+    fn schedule_arrival_unlogged(&mut self, spec: ProcessSpec) -> Result<Option<u32>, ProcessError> {
+        if !self.priority_range.contains(spec.priority) {
+            return Err(ProcessError::InvalidPriority(spec.priority, self.priority_range));
+        }
+        if spec.arrival_time <= self.scheduler.current_time() {
+            return self.create_process(spec.priority, spec.burst).map(Some);
+        }
+
+        // 插在所有到达时间不晚于它的定时进程之后，同时到达的保持登记顺序
+        let position = self.pending_arrivals.partition_point(|pending| pending.arrival_time <= spec.arrival_time);
+        self.pending_arrivals.insert(position, spec);
+        log(LogLevel::Info, format_args!("✓ 已登记定时进程：优先级 {}，运行时间 {}，将在系统时间 {} 到达",
+                 spec.priority, spec.burst, spec.arrival_time));
+        Ok(None)
+    }
+
+    /// 创建所有已到达（到达时间不晚于当前系统时间）的定时进程
+    pub(crate) fn admit_arrivals(&mut self) {
+        let now = self.scheduler.current_time();
+        let arrived = self.pending_arrivals.partition_point(|pending| pending.arrival_time <= now);
+        for spec in self.pending_arrivals.drain(..arrived).collect::<Vec<_>>() {
+            log(LogLevel::Info, format_args!("定时进程到达（系统时间 {}）", now));
+            if let Err(e) = self.create_process(spec.priority, spec.burst) {
+                log(LogLevel::Error, format_args!("{}", e));
+            }
+        }
+    }
+
+    /// 当前负载的描述：总链中的进程视为立即到达（运行时间取剩余运行时间，按PID升序），
+    /// 尚未到达的定时进程保留相对于当前系统时间的到达时间
+    pub(crate) fn current_workload(&self) -> Vec<ProcessSpec> {
+        let mut pcbs: Vec<&PCB> = self.total_chain.values().collect();
+        pcbs.sort_by_key(|pcb| pcb.pid);
+        let now = self.scheduler.current_time();
+        pcbs.iter()
+            .map(|pcb| ProcessSpec { priority: pcb.priority, burst: pcb.burst_time.max(1), arrival_time: 0 })
+            .chain(self.pending_arrivals.iter().map(|spec| ProcessSpec {
+                arrival_time: spec.arrival_time.saturating_sub(now),
+                ..*spec
+            }))
+            .collect()
+    }
+
+    /// 用同一负载在每个调度策略下分别运行，返回各策略的调度统计
+    ///
+    /// 每个策略使用一个全新的进程管理器（PCB池大小和优先级范围与当前相同），
+    /// 按描述登记全部进程（到达时间为0的立即创建，其余到时创建）后运行至空闲。期间日志级别临时降为错误以屏蔽过程输出；
+    /// 当前系统状态不受影响
+    pub(crate) fn compare_policies(&self, workload: &[ProcessSpec], policies: &[SchedulingPolicy]) -> Vec<(SchedulingPolicy, SchedulerStats)> {
+        let previous = logger::level();
+        logger::set_level(LogLevel::Error);
+
+        let results = policies.iter()
+            .map(|&policy| {
+                let mut trial = ProcessManager::with_pool_size(self.pcb_pool.get_pool_size());
+                trial.priority_range = self.priority_range;
+                trial.scheduler.set_policy(policy);
+                for &spec in workload {
+                    if let Err(e) = trial.schedule_arrival(spec) {
+                        log(LogLevel::Error, format_args!("{}", e));
+                    }
+                }
+                trial.run_until_idle(MAX_RUN_CYCLES);
+                (policy, trial.scheduler.get_stats())
+            })
+            .collect();
+
+        logger::set_level(previous);
+        results
+    }
+
+    /// 打印调度策略比较表
+    pub(crate) fn print_policy_comparison<W: Write>(results: &[(SchedulingPolicy, SchedulerStats)], out: &mut W) -> io::Result<()> {
+        let format_metric = |value: Option<f64>| value.map_or("-".to_string(), |v| format!("{:.2}", v));
+
+        writeln!(out, "\n=== 调度策略比较 ===")?;
+        writeln!(out, "{:>10} {:>10} {:>8} {:>10}  策略", "平均周转", "平均等待", "切换", "CPU利用率")?;
+        for (policy, stats) in results {
+            let utilization = stats.cpu_utilization.map_or("-".to_string(), |u| format!("{:.2}%", u * 100.0));
+            writeln!(out, "{:>14} {:>14} {:>10} {:>13}  {}",
+                          format_metric(stats.avg_turnaround), format_metric(stats.avg_waiting),
+                          stats.total_switches, utilization, policy.name())?;
+        }
+        Ok(())
+    }
+
+    /// 从当前状态分叉比较各调度策略：每个策略在当前系统的完整副本上切换策略后运行至空闲
+    ///
+    /// 与compare_policies不同，副本保留运行中的进程、各进程已用的CPU时间和已有的调度统计，
+    /// 因此统计结果包含分叉前的部分。期间日志级别临时降为错误；当前系统状态不受影响
+    pub(crate) fn compare_policies_forked(&self, policies: &[SchedulingPolicy]) -> Vec<(SchedulingPolicy, SchedulerStats)> {
+        let previous = logger::level();
+        logger::set_level(LogLevel::Error);
+
+        let results = policies.iter()
+            .map(|&policy| {
+                let mut trial = self.clone();
+                trial.set_policy(policy);
+                trial.run_until_idle(MAX_RUN_CYCLES);
+                (policy, trial.scheduler.get_stats())
+            })
+            .collect();
+
+        logger::set_level(previous);
+        results
+    }
+
+    /// 压力测试：反复填满并部分清空PCB池，检查伙伴系统的不变量
+    ///
+    /// 每轮先不断创建进程直到PCB池已满，再随机撤销约一半的进程。
+    /// 每次填满和清空后检查已用数量不超过池大小、空闲链表无重复或重叠等不变量，
+    /// 发现的问题记入报告（附带轮次）
+    pub(crate) fn stress_test(&mut self, rounds: u32) -> StressReport {
+        let mut rng = Lcg::new(STRESS_SEED);
+        let mut report = StressReport {
+            rounds,
+            alloc_success: 0,
+            alloc_failure: 0,
+            terminated: 0,
+            violations: Vec::new(),
+        };
+
+        for round in 1..=rounds {
+            loop {
+                let priority = rng.range(self.priority_range.min, self.priority_range.max);
+                let burst = rng.range(WORKLOAD_BURST.0, WORKLOAD_BURST.1);
+                match self.create_process(priority, burst) {
+                    Ok(_) => report.alloc_success += 1,
+                    Err(_) => {
+                        report.alloc_failure += 1;
+                        break;
+                    }
+                }
+            }
+            self.record_pool_violations(round, "填满", &mut report.violations);
+
+            let mut pids: Vec<u32> = self.total_chain.keys().copied().collect();
+            pids.sort_unstable();
+            for pid in pids {
+                // 级联撤销可能已经撤销了该进程
+                if rng.next_u32().is_multiple_of(2) && self.total_chain.contains_key(&pid) {
+                    let before = self.terminated_count;
+                    if self.terminate_process(pid).is_ok() {
+                        report.terminated += self.terminated_count - before;
+                    }
+                }
+            }
+            self.record_pool_violations(round, "清空", &mut report.violations);
+        }
+        report
+    }
+
+    /// 检查PCB池的不变量，把问题连同轮次和阶段记入violations
+    fn record_pool_violations(&self, round: u32, phase: &str, violations: &mut Vec<String>) {
+        for problem in self.pcb_pool.check_invariants() {
+            violations.push(format!("第{}轮{}后: {}", round, phase, problem));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stress_test_fills_and_drains_pool_without_violations() {
+        let mut pm = ProcessManager::with_pool_size(16);
+        let rounds = 20;
+        let report = pm.stress_test(rounds);
+
+        assert!(report.violations.is_empty(), "{:?}", report.violations);
+        assert_eq!(report.alloc_failure, rounds);
+        assert!(report.alloc_success >= 16);
+        assert!(report.terminated > 0);
+        assert!(pm.pcb_pool.get_used_count() <= pm.pcb_pool.get_pool_size());
+        assert_eq!(pm.pcb_pool.get_used_count(), pm.total_chain.len());
+        assert!(pm.verify_consistency().is_empty());
+
+        let pids: Vec<u32> = pm.total_chain.keys().copied().collect();
+        for pid in pids {
+            if pm.total_chain.contains_key(&pid) {
+                pm.terminate_process(pid).unwrap();
+            }
+        }
+        assert_eq!(pm.pcb_pool.get_used_count(), 0);
+        assert!(pm.pcb_pool.check_invariants().is_empty());
+    }
+
+    #[test]
+    fn same_seed_yields_the_same_priority_sequence() {
+        let workload = |seed: u64| {
+            let mut pm = ProcessManager::new();
+            let pids = pm.generate_workload(8, seed);
+            assert_eq!(pids.len(), 8);
+            pids.iter().map(|pid| (pm.total_chain[pid].priority, pm.total_chain[pid].burst_time)).collect::<Vec<_>>()
+        };
+
+        let first = workload(42);
+        assert_eq!(first, workload(42));
+        assert_ne!(first, workload(43));
+        let range = ProcessManager::new().priority_range;
+        assert!(first.iter().all(|&(priority, burst)| range.contains(priority) && burst > 0));
+        // 生成的优先级并非全部相同
+        assert!(first.iter().any(|&(priority, _)| priority != first[0].0));
+    }
+
+    #[test]
+    fn sjf_beats_fcfs_on_average_waiting_time() {
+        let pm = ProcessManager::new();
+        let workload: Vec<ProcessSpec> = [8, 1, 2].iter()
+            .map(|&burst| ProcessSpec { priority: 1, burst, arrival_time: 0 })
+            .collect();
+        let results = pm.compare_policies(&workload, &[SchedulingPolicy::Fcfs, SchedulingPolicy::Sjf]);
+        let [(_, fcfs), (_, sjf)] = results.as_slice() else {
+            panic!("每个策略应有一组结果");
+        };
+
+        // FCFS按到达顺序等待0、8、9；SJF按运行时间等待0、1、3
+        assert_eq!((fcfs.completed_count, sjf.completed_count), (3, 3));
+        assert_eq!(fcfs.avg_waiting, Some(17.0 / 3.0));
+        assert_eq!(sjf.avg_waiting, Some(4.0 / 3.0));
+        assert!(sjf.avg_turnaround < fcfs.avg_turnaround);
+        assert!(pm.total_chain.is_empty());
+    }
+
+    #[test]
+    fn a_process_arriving_at_time_3_is_not_scheduled_before_then() {
+        let mut pm = ProcessManager::new();
+        let early = pm.create_process(1, 10).unwrap();
+        let spec = ProcessSpec { priority: 1, burst: 2, arrival_time: 3 };
+        assert_eq!(pm.schedule_arrival(spec).unwrap(), None);
+        assert_eq!(pm.pending_arrivals, vec![spec]);
+
+        pm.run_cycles(3);
+        assert_eq!(pm.total_chain.len(), 1);
+        assert_eq!(pm.scheduler.current_time(), 3);
+
+        // 第4个周期开始时系统时间为3，定时进程到达并进入就绪队列
+        pm.run_one_cycle();
+        let late = pm.total_chain.keys().copied().find(|&pid| pid != early).unwrap();
+        assert_eq!(pm.total_chain[&late].arrival_time, 3);
+        assert!(pm.pending_arrivals.is_empty());
+        pm.run_until_idle(MAX_RUN_CYCLES);
+        let first_run = pm.scheduler.executions_since(0).iter()
+            .find(|&&(_, pid)| pid == late)
+            .map(|&(time, _)| time);
+        assert!(first_run.is_some_and(|time| time > 3), "{:?}", first_run);
+        assert_eq!(pm.scheduler.get_stats().completed_count, 2);
+    }
+}